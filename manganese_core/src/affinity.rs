@@ -0,0 +1,333 @@
+// Thread affinity: with `--pin` every worker of the global thread pool is pinned to one logical
+// CPU, so each chunk is always walked by the same core and bandwidth figures don't wander with
+// the scheduler's placement. The CPU set can leave out SMT siblings (one worker per physical
+// core) or be limited to the performance or efficiency cores of a hybrid CPU, whose memory paths
+// differ noticeably in bandwidth and latency.
+// With `--l3-domains` the CPUs are grouped by the L3 cache they share (the CCDs of a Ryzen or
+// EPYC, as the OS reads them from CPUID's cache topology) and every group tests a region of its
+// own, or with `--cross-domains` the region of the next group.
+use log::warn;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    /// Every logical CPU the process may run on.
+    All,
+    /// The first logical CPU of every physical core.
+    NoSmt,
+    /// Performance cores of a hybrid CPU.
+    PCores,
+    /// Efficiency cores of a hybrid CPU.
+    ECores,
+}
+
+impl Affinity {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "all" => Some(Affinity::All),
+            "no-smt" => Some(Affinity::NoSmt),
+            "p-cores" => Some(Affinity::PCores),
+            "e-cores" => Some(Affinity::ECores),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LogicalCpu {
+    id: usize,
+    // shared by the SMT siblings of a physical core
+    core: usize,
+    // efficiency core of a hybrid CPU
+    efficient: bool,
+    // id of the L3 cache, if known
+    l3: Option<usize>,
+}
+
+// The CPUs `affinity` selects from `cpus` (ordered by id), all of them if the CPU has no such cores
+fn select(affinity: Affinity, cpus: &[LogicalCpu]) -> Vec<usize> {
+    let hybrid = cpus.iter().any(|cpu| cpu.efficient) && cpus.iter().any(|cpu| !cpu.efficient);
+    match affinity {
+        Affinity::All => cpus.iter().map(|cpu| cpu.id).collect(),
+        Affinity::NoSmt => {
+            let mut cores = Vec::new();
+            cpus.iter()
+                .filter(|cpu| {
+                    let first = !cores.contains(&cpu.core);
+                    cores.push(cpu.core);
+                    first
+                })
+                .map(|cpu| cpu.id)
+                .collect()
+        }
+        Affinity::PCores | Affinity::ECores if !hybrid => {
+            warn!("not a hybrid CPU (or its core types are unknown), pinning to all CPUs");
+            cpus.iter().map(|cpu| cpu.id).collect()
+        }
+        Affinity::PCores => cpus.iter().filter(|cpu| !cpu.efficient).map(|cpu| cpu.id).collect(),
+        Affinity::ECores => cpus.iter().filter(|cpu| cpu.efficient).map(|cpu| cpu.id).collect(),
+    }
+}
+
+/// Build the global thread pool with one worker per CPU `affinity` selects (the first `limit`
+/// of them), every worker pinned to its CPU. Returns the CPUs in worker order, or None if pinning isn't possible (the global
+/// pool is then left to `hardware_cpu_count()`).
+pub fn pin_threads(affinity: Affinity, limit: Option<usize>) -> Option<Vec<usize>> {
+    let mut cpus = select(affinity, &logical_cpus());
+    cpus.truncate(limit.unwrap_or(usize::MAX));
+    if cpus.is_empty() {
+        warn!("CPU topology unknown on this platform, threads are not pinned");
+        return None;
+    }
+    let workers = cpus.clone();
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(cpus.len())
+        .thread_name(|i| format!("pinned-{}", i))
+        .start_handler(move |i| pin_to_cpu(workers[i]))
+        .build_global()
+        .map_err(|e| warn!("can't start the pinned thread pool: {}", e))
+        .ok()?;
+    Some(cpus)
+}
+
+/// The CPUs the process may run on, grouped by the L3 cache they share and in the order of their
+/// first CPU; empty if the cache topology is unknown.
+pub fn l3_domains() -> Vec<Vec<usize>> {
+    domains(&logical_cpus())
+}
+
+fn domains(cpus: &[LogicalCpu]) -> Vec<Vec<usize>> {
+    let mut domains: Vec<(usize, Vec<usize>)> = Vec::new();
+    for cpu in cpus {
+        let Some(l3) = cpu.l3 else {
+            return Vec::new();
+        };
+        match domains.iter_mut().find(|(id, _)| *id == l3) {
+            Some((_, cpus)) => cpus.push(cpu.id),
+            None => domains.push((l3, vec![cpu.id])),
+        }
+    }
+    domains.into_iter().map(|(_, cpus)| cpus).collect()
+}
+
+/// Thread pool with one worker per CPU of `cpus`, every worker pinned to its CPU.
+pub fn domain_thread_pool(cpus: &[usize]) -> Result<ThreadPool, rayon::ThreadPoolBuildError> {
+    let workers = cpus.to_vec();
+    ThreadPoolBuilder::new()
+        .num_threads(cpus.len())
+        .thread_name(|i| format!("domain-{}", i))
+        .start_handler(move |i| pin_to_cpu(workers[i]))
+        .build()
+}
+
+/// Thread pool with `threads` workers beside the current one, worker `i` on the CPUs the current
+/// pool's worker `i` (modulo its size) may run on, so a pinned or node bound pool keeps its placement.
+pub fn sibling_thread_pool(threads: usize) -> Result<ThreadPool, rayon::ThreadPoolBuildError> {
+    let masks = rayon::broadcast(|_| thread_affinity());
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("sibling-{}", i))
+        .start_handler(move |i| {
+            if let Some(mask) = &masks[i % masks.len()] {
+                set_thread_affinity(mask);
+            }
+        })
+        .build()
+}
+
+/// "0-3,8-11" for [0, 1, 2, 3, 8, 9, 10, 11]
+pub fn format_cpulist(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges.iter()
+        .map(|&(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(target_os = "linux")]
+fn logical_cpus() -> Vec<LogicalCpu> {
+    use crate::numa::parse_cpulist;
+    use std::fs;
+
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) } != 0 {
+        return Vec::new();
+    }
+    // only hybrid Intel CPUs have the separate cpu_core/cpu_atom PMUs
+    let atoms = fs::read_to_string("/sys/devices/cpu_atom/cpus").map(|list| parse_cpulist(&list)).unwrap_or_default();
+    (0..libc::CPU_SETSIZE as usize)
+        .filter(|&id| unsafe { libc::CPU_ISSET(id, &set) })
+        .map(|id| {
+            let core = fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list", id))
+                .ok()
+                .and_then(|list| parse_cpulist(&list).into_iter().min())
+                .unwrap_or(id);
+            // index3 is the L3 on x86 and on the ARM parts that have one
+            let l3 = fs::read_to_string(format!("/sys/devices/system/cpu/cpu{}/cache/index3/id", id))
+                .ok()
+                .and_then(|l3| l3.trim().parse().ok());
+            LogicalCpu { id, core, efficient: atoms.contains(&id), l3 }
+        })
+        .collect()
+}
+
+// Flat ids are group * 64 + processor number
+#[cfg(windows)]
+fn logical_cpus() -> Vec<LogicalCpu> {
+    use winapi::um::sysinfoapi::GetLogicalProcessorInformationEx;
+    use winapi::um::winnt::{RelationCache, RelationProcessorCore, LOGICAL_PROCESSOR_RELATIONSHIP, SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX};
+
+    // the records of `relation`, each with its offset in the buffer
+    fn records(relation: LOGICAL_PROCESSOR_RELATIONSHIP) -> (Vec<u8>, Vec<usize>) {
+        let mut len = 0;
+        unsafe { GetLogicalProcessorInformationEx(relation, std::ptr::null_mut(), &mut len); }
+        let mut buffer = vec![0u8; len as usize];
+        if unsafe { GetLogicalProcessorInformationEx(relation, buffer.as_mut_ptr() as *mut _, &mut len) } == 0 {
+            return (Vec::new(), Vec::new());
+        }
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        while offset < len as usize {
+            offsets.push(offset);
+            offset += unsafe { (*(buffer.as_ptr().add(offset) as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX)).Size } as usize;
+        }
+        (buffer, offsets)
+    }
+
+    // (id, core, efficiency class); a higher class is a faster core
+    let mut found = Vec::new();
+    let (buffer, offsets) = records(RelationProcessorCore);
+    for offset in offsets {
+        unsafe {
+            let info = &*(buffer.as_ptr().add(offset) as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX);
+            let processor = info.u.Processor();
+            for g in 0..processor.GroupCount as usize {
+                let affinity = &*processor.GroupMask.as_ptr().add(g);
+                for bit in (0..64).filter(|bit| affinity.Mask & (1 << bit) != 0) {
+                    found.push((affinity.Group as usize * 64 + bit, offset, processor.EfficiencyClass));
+                }
+            }
+        }
+    }
+    // L3 caches by their offset, with the flat ids of the CPUs sharing them
+    let (buffer, offsets) = records(RelationCache);
+    let l3_caches: Vec<(usize, usize, u64)> = offsets.into_iter()
+        .filter_map(|offset| unsafe {
+            let cache = (*(buffer.as_ptr().add(offset) as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX)).u.Cache();
+            (cache.Level == 3).then(|| (offset, cache.GroupMask.Group as usize, cache.GroupMask.Mask as u64))
+        })
+        .collect();
+    let l3 = |id: usize| l3_caches.iter()
+        .find(|&&(_, group, mask)| group == id / 64 && mask & (1 << (id % 64)) != 0)
+        .map(|&(offset, _, _)| offset);
+    let fastest = found.iter().map(|&(_, _, class)| class).max().unwrap_or(0);
+    let mut cpus: Vec<LogicalCpu> = found.into_iter()
+        .map(|(id, core, class)| LogicalCpu { id, core, efficient: class < fastest, l3: l3(id) })
+        .collect();
+    cpus.sort_by_key(|cpu| cpu.id);
+    cpus
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn logical_cpus() -> Vec<LogicalCpu> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_cpu(cpu: usize) {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        // best effort like the NUMA pinning, an unpinned worker still tests its chunk
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(windows)]
+fn pin_to_cpu(cpu: usize) {
+    use winapi::um::processthreadsapi::GetCurrentThread;
+    use winapi::um::processtopologyapi::SetThreadGroupAffinity;
+    use winapi::um::winnt::GROUP_AFFINITY;
+
+    let affinity = GROUP_AFFINITY { Mask: 1 << (cpu % 64), Group: (cpu / 64) as u16, Reserved: [0; 3] };
+    unsafe { SetThreadGroupAffinity(GetCurrentThread(), &affinity, std::ptr::null_mut()); }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn pin_to_cpu(_cpu: usize) {}
+
+#[cfg(target_os = "linux")]
+type CpuMask = libc::cpu_set_t;
+#[cfg(windows)]
+type CpuMask = winapi::um::winnt::GROUP_AFFINITY;
+#[cfg(not(any(target_os = "linux", windows)))]
+type CpuMask = ();
+
+#[cfg(target_os = "linux")]
+fn thread_affinity() -> Option<CpuMask> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        (libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) == 0).then_some(set)
+    }
+}
+
+#[cfg(windows)]
+fn thread_affinity() -> Option<CpuMask> {
+    use winapi::um::processthreadsapi::GetCurrentThread;
+    use winapi::um::processtopologyapi::GetThreadGroupAffinity;
+
+    unsafe {
+        let mut affinity: CpuMask = std::mem::zeroed();
+        (GetThreadGroupAffinity(GetCurrentThread(), &mut affinity) != 0).then_some(affinity)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn thread_affinity() -> Option<CpuMask> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn set_thread_affinity(set: &CpuMask) {
+    unsafe {
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), set);
+    }
+}
+
+#[cfg(windows)]
+fn set_thread_affinity(affinity: &CpuMask) {
+    use winapi::um::processthreadsapi::GetCurrentThread;
+    use winapi::um::processtopologyapi::SetThreadGroupAffinity;
+
+    unsafe { SetThreadGroupAffinity(GetCurrentThread(), affinity, std::ptr::null_mut()); }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn set_thread_affinity(_mask: &CpuMask) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hybrid_selection() {
+        // two P-cores with SMT siblings 0/1 and 2/3, then two E-cores
+        let cpus: Vec<LogicalCpu> = [(0, 0, false), (1, 0, false), (2, 2, false), (3, 2, false), (4, 4, true), (5, 5, true)]
+            .into_iter()
+            .map(|(id, core, efficient)| LogicalCpu { id, core, efficient, l3: Some(id / 4) })
+            .collect();
+        assert_eq!(select(Affinity::NoSmt, &cpus), vec![0, 2, 4, 5]);
+        assert_eq!(select(Affinity::PCores, &cpus), vec![0, 1, 2, 3]);
+        assert_eq!(select(Affinity::ECores, &cpus), vec![4, 5]);
+        assert_eq!(select(Affinity::ECores, &cpus[..4]), vec![0, 1, 2, 3]);
+        assert_eq!(format_cpulist(&select(Affinity::All, &cpus)), "0-5");
+        assert_eq!(format_cpulist(&[0, 2, 3, 4, 7]), "0,2-4,7");
+        assert_eq!(domains(&cpus), vec![vec![0, 1, 2, 3], vec![4, 5]]);
+    }
+}
@@ -0,0 +1,150 @@
+// Error attribution to memory modules: the miscompares of the run are counted per DIMM at the end
+// so a technician knows which stick to pull. The physical address of every record (pagemap)
+// is looked up in the SMBIOS Type 20 ranges; where a range is interleaved across several modules,
+// or the firmware has no Type 20 entries, the module is inferred from the channel interleave the
+// channel stripes test assumes (address / interleave modulo the channels), which is a heuristic:
+// real controllers hash more address bits into the channel.
+use std::collections::{BTreeMap, HashMap};
+use log::{error, info};
+use crate::hardware::{MemoryInfo, SystemInfo};
+use crate::pagemap::physical_address;
+use crate::platform::getpagesize;
+use crate::records::ErrorRecord;
+use crate::tests::TestDefinition;
+
+// stripe size of the channel stripes test when it isn't part of the run
+const DEFAULT_INTERLEAVE: usize = 256;
+
+// Where the error of one physical address most likely is
+#[derive(Debug, PartialEq, Eq)]
+enum Location {
+    /// The only module decoding the address.
+    Dimm(u16),
+    /// One module picked by the interleave heuristic.
+    InferredDimm(u16),
+    /// A channel picked by the interleave heuristic, with several modules on it.
+    InferredChannel(String),
+}
+
+fn channel(m: &MemoryInfo) -> String {
+    m.channel_name.clone().unwrap_or_else(|| "Channel 0".to_string())
+}
+
+fn locate(info: &SystemInfo, address: u64, interleave: u64) -> Option<Location> {
+    let mut candidates = info.dimms_at(address);
+    if candidates.is_empty() && info.mapped_ranges.is_empty() {
+        candidates = info.memory_devices.iter().filter(|m| m.populated).collect();
+    }
+    if candidates.len() <= 1 {
+        return candidates.first().map(|m| Location::Dimm(m.handle));
+    }
+    let mut channels: BTreeMap<String, Vec<&MemoryInfo>> = BTreeMap::new();
+    for m in candidates {
+        channels.entry(channel(m)).or_default().push(m);
+    }
+    let n = channels.len();
+    let (name, dimms) = channels.into_iter().nth((address / interleave) as usize % n)?;
+    match dimms.as_slice() {
+        [m] => Some(Location::InferredDimm(m.handle)),
+        _ => Some(Location::InferredChannel(name)),
+    }
+}
+
+/// Miscompares of the run counted per module (or channel, where the module can't be told).
+#[derive(Debug)]
+pub struct DimmErrors {
+    interleave: usize,
+    dimms: HashMap<u16, usize>,
+    channels: BTreeMap<String, usize>,
+    inferred: bool,
+    unattributed: usize,
+}
+
+impl DimmErrors {
+    /// Counters inferring channels from the stripe size of the channel stripes test in `tests`.
+    pub fn new(tests: &[TestDefinition]) -> Self {
+        let interleave = tests.iter()
+            .find(|test| test.name == "channel_stripes")
+            .map_or(DEFAULT_INTERLEAVE, |test| test.options.interleave.max(64));
+        DimmErrors { interleave, dimms: HashMap::new(), channels: BTreeMap::new(), inferred: false, unattributed: 0 }
+    }
+
+    /// Count the miscompares in `records` of the region at `mem`; `dropped` weren't recorded.
+    pub fn add_records(&mut self, mem: *const u8, records: &[ErrorRecord], dropped: usize, info: &SystemInfo) {
+        let page = getpagesize();
+        let mut pages: HashMap<usize, Option<u64>> = HashMap::new();
+        for record in records {
+            let virt = mem as usize + record.offset;
+            let base = *pages.entry(virt / page).or_insert_with(|| physical_address(virt / page * page).map(|phys| phys.address));
+            match base.and_then(|base| locate(info, base + (virt % page) as u64, self.interleave as u64)) {
+                Some(Location::Dimm(handle)) => *self.dimms.entry(handle).or_default() += 1,
+                Some(Location::InferredDimm(handle)) => {
+                    *self.dimms.entry(handle).or_default() += 1;
+                    self.inferred = true;
+                }
+                Some(Location::InferredChannel(name)) => {
+                    *self.channels.entry(name).or_default() += 1;
+                    self.inferred = true;
+                }
+                None => self.unattributed += 1,
+            }
+        }
+        self.unattributed += dropped;
+    }
+
+    /// Log one line per populated module with its error count.
+    pub fn log(&self, info: &SystemInfo) {
+        let attributed = self.dimms.values().sum::<usize>() + self.channels.values().sum::<usize>();
+        if attributed == 0 {
+            return;
+        }
+        if self.inferred {
+            info!("Errors per DIMM (interleaved ranges inferred from a {}-byte channel interleave, a heuristic):", self.interleave);
+        } else {
+            info!("Errors per DIMM:");
+        }
+        for (name, dimms) in info.memory_channels() {
+            for m in dimms.iter().filter(|m| m.populated) {
+                let slot = m.slot_index.map_or(String::new(), |slot| format!(" DIMM{}", slot));
+                let errors = self.dimms.get(&m.handle).copied().unwrap_or(0);
+                if errors > 0 {
+                    error!("  {}{} ({}): {} errors", name, slot, m.locator, errors);
+                } else {
+                    info!("  {}{} ({}): 0 errors", name, slot, m.locator);
+                }
+            }
+            if let Some(errors) = self.channels.get(&name) {
+                error!("  {} (module unknown): {} errors", name, errors);
+            }
+        }
+        if self.unattributed > 0 {
+            error!("  without a known module: {} errors", self.unattributed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::MappedRange;
+
+    #[test]
+    fn locate_exact_and_interleaved() {
+        let dimm = |handle, locator: &str, channel: &str| MemoryInfo {
+            handle, locator: locator.into(), channel_name: Some(channel.into()), populated: true, ..Default::default()
+        };
+        let range = |start, end, device_handle| MappedRange { start, end, device_handle };
+        let mut info = SystemInfo {
+            memory_devices: vec![dimm(1, "A1", "Channel A"), dimm(2, "B1", "Channel B"), dimm(3, "B2", "Channel B")],
+            // 0-4GiB interleaved over A1 and B1, 4-8GiB only on B2
+            mapped_ranges: vec![range(0, 4 << 30, 1), range(0, 4 << 30, 2), range(4 << 30, 8 << 30, 3)],
+            ..Default::default()
+        };
+        assert_eq!(locate(&info, 5 << 30, 256), Some(Location::Dimm(3)));
+        assert_eq!(locate(&info, 0x1000, 256), Some(Location::InferredDimm(1)));
+        assert_eq!(locate(&info, 0x1100, 256), Some(Location::InferredDimm(2)));
+        // without Type 20 ranges channel B has two modules to choose from
+        info.mapped_ranges.clear();
+        assert_eq!(locate(&info, 0x1100, 256), Some(Location::InferredChannel("Channel B".into())));
+    }
+}
@@ -0,0 +1,82 @@
+// Bad page lists for the boot loader: the physical pages that miscompared during the run are
+// printed as GRUB BadRAM address/mask pairs (`GRUB_BADRAM=`, the format memtest86 prints for the
+// `badram` command) and as a Windows `bcdedit` bad memory list, so the failing frames can be kept
+// out of use right away instead of waiting for a replacement module.
+use std::collections::BTreeSet;
+use log::error;
+use crate::pagemap::physical_address;
+use crate::platform::getpagesize;
+use crate::records::ErrorRecord;
+
+// Windows lists page frame numbers of 4KiB pages whatever the page size of the tester
+const WINDOWS_PAGE: u64 = 4096;
+
+#[derive(Debug, Default)]
+pub struct BadPages {
+    frames: BTreeSet<u64>,
+}
+
+impl BadPages {
+    /// Add the physical pages of the miscompares in `records` of the region at `mem`.
+    pub fn add_records(&mut self, mem: *const u8, records: &[ErrorRecord]) {
+        let page = getpagesize();
+        let pages: BTreeSet<usize> = records.iter().map(|record| (mem as usize + record.offset) / page).collect();
+        self.frames.extend(pages.into_iter().filter_map(|virt| physical_address(virt * page)).map(|phys| phys.pfn));
+    }
+
+    /// BadRAM address/mask pairs covering exactly the bad pages of `page` bytes, consecutive pages
+    /// merged into naturally aligned power-of-two blocks.
+    fn badram_pairs(&self, page: u64) -> Vec<(u64, u64)> {
+        let mut pairs = Vec::new();
+        let mut frames = self.frames.iter().copied().peekable();
+        while let Some(first) = frames.next() {
+            let mut end = first + 1;
+            while frames.next_if_eq(&end).is_some() {
+                end += 1;
+            }
+            let mut start = first;
+            while start < end {
+                let mut size = 1 << start.trailing_zeros().min(63);
+                while start + size > end {
+                    size /= 2;
+                }
+                pairs.push((start * page, !(size * page - 1)));
+                start += size;
+            }
+        }
+        pairs
+    }
+
+    /// Log the bad pages in GRUB BadRAM and Windows bcdedit syntax.
+    pub fn log(&self) {
+        if self.frames.is_empty() {
+            return;
+        }
+        let page = getpagesize() as u64;
+        let badram: Vec<String> = self.badram_pairs(page).iter()
+            .map(|(address, mask)| format!("0x{:016x},0x{:016x}", address, mask))
+            .collect();
+        let windows: Vec<String> = self.frames.iter()
+            .flat_map(|pfn| (pfn * page / WINDOWS_PAGE..(pfn + 1) * page / WINDOWS_PAGE).map(|frame| format!("0x{:x}", frame)))
+            .collect();
+        error!("Bad Pages         : {} physical pages of {}K failed, exclude them with", self.frames.len(), page / 1024);
+        error!("  GRUB (/etc/default/grub): GRUB_BADRAM=\"{}\"", badram.join(","));
+        error!("  Windows (as Administrator): bcdedit /set {{badmemory}} badmemorylist {}", windows.join(" "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BadPages;
+
+    #[test]
+    fn badram_merges_aligned_runs() {
+        let pages = BadPages { frames: [0x101, 0x102, 0x103, 0x104, 0x200].into_iter().collect() };
+        assert_eq!(pages.badram_pairs(4096), vec![
+            (0x101000, !0xfff),
+            (0x102000, !0x1fff),
+            (0x104000, !0xfff),
+            (0x200000, !0xfff),
+        ]);
+    }
+}
@@ -0,0 +1,172 @@
+// Bandwidth and latency benchmark, instead of testing (`--benchmark`).
+// The locked region is split into three arrays of doubles that the STREAM kernels stream through:
+// copy (c = a), scale (b = s * c), add (c = a + b) and triad (a = b + s * c). Every kernel runs
+// with 1, 2, 4 ... threads up to all of them and the best of a few runs is reported, as STREAM
+// does. Nothing is verified. Random access latency is measured by chasing a pointer cycle over
+// a span of the region well beyond the caches, see `pointer_chase`.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use log::{info, warn};
+use serde::{Serialize, Serializer};
+use rayon::prelude::*;
+use crate::pointer_chase::build_chain;
+
+const RUNS: usize = 5;
+const SCALAR: f64 = 3.0;
+const LATENCY_SPAN: usize = 512 * 1024 * 1024;
+const LATENCY_STEPS: usize = 1 << 22;
+
+/// Best bandwidth of the STREAM kernels with `threads` threads, in MB/s.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub threads: usize,
+    #[serde(rename = "copy_mbs")]
+    pub copy: f64,
+    #[serde(rename = "scale_mbs")]
+    pub scale: f64,
+    #[serde(rename = "add_mbs")]
+    pub add: f64,
+    #[serde(rename = "triad_mbs")]
+    pub triad: f64,
+}
+
+/// Outcome of `--benchmark`, see `RunReport::benchmark`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Benchmark {
+    /// Size of each of the three arrays.
+    pub array_bytes: usize,
+    /// One result per thread count, fewest threads first.
+    pub scaling: Vec<BenchmarkResult>,
+    /// Average time of a dependent load from a random cache line, None if stopped before.
+    #[serde(rename = "latency_ns", serialize_with = "nanoseconds")]
+    pub latency: Option<Duration>,
+}
+
+fn nanoseconds<S: Serializer>(latency: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    latency.map(|latency| latency.as_secs_f64() * 1e9).serialize(s)
+}
+
+#[derive(Clone, Copy)]
+enum Kernel {
+    Copy,
+    Scale,
+    Add,
+    Triad,
+}
+
+impl Kernel {
+    // Bytes read and written per element, as STREAM counts them
+    fn bytes(self) -> usize {
+        match self {
+            Kernel::Copy | Kernel::Scale => 16,
+            Kernel::Add | Kernel::Triad => 24,
+        }
+    }
+
+    // Runs the kernel over elements `range` of the arrays
+    unsafe fn run(self, arrays: [usize; 3], range: std::ops::Range<usize>) {
+        let array = |i: usize| std::slice::from_raw_parts_mut((arrays[i] as *mut f64).add(range.start), range.len());
+        let (a, b, c) = (array(0), array(1), array(2));
+        match self {
+            Kernel::Copy => c.copy_from_slice(a),
+            Kernel::Scale => b.iter_mut().zip(c.iter()).for_each(|(b, c)| *b = SCALAR * c),
+            Kernel::Add => c.iter_mut().zip(a.iter().zip(b.iter())).for_each(|(c, (a, b))| *c = a + b),
+            Kernel::Triad => a.iter_mut().zip(b.iter().zip(c.iter())).for_each(|(a, (b, c))| *a = b + SCALAR * c),
+        }
+    }
+}
+
+// Thread counts of the scaling runs: powers of two below `cpus`, then `cpus`
+fn thread_counts(cpus: usize) -> Vec<usize> {
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |n| Some(n * 2)).take_while(|&n| n < cpus).collect();
+    counts.push(cpus);
+    counts
+}
+
+// Best time of RUNS runs of `kernel` over the `len` elements of the arrays, split evenly among
+// the threads of the current pool; None if stopped
+unsafe fn best_time(kernel: Kernel, arrays: [usize; 3], len: usize, threads: usize, stop_signal: &AtomicBool) -> Option<Duration> {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        if stop_signal.load(Ordering::SeqCst) {
+            return None;
+        }
+        let timer = Instant::now();
+        (0..threads).into_par_iter().for_each(|t| kernel.run(arrays, t * len / threads..(t + 1) * len / threads));
+        best = best.min(timer.elapsed());
+    }
+    Some(best)
+}
+
+// Average time of `steps` dependent loads along the pointer cycle over `mem[..span]`
+unsafe fn chase_latency(mem: *mut u8, span: usize, seed: u64) -> Duration {
+    build_chain(mem, 0, span, seed);
+    let steps = LATENCY_STEPS.min(span / 64);
+    let timer = Instant::now();
+    let mut line = 0;
+    for _ in 0..steps {
+        line = std::ptr::read_volatile(mem.add(line * 64) as *const u64) as usize;
+    }
+    // keeps the chase from being optimized out
+    std::hint::black_box(line);
+    timer.elapsed() / steps as u32
+}
+
+/// Measures the STREAM bandwidth with 1 up to `cpus` threads and the random access latency on
+/// `mem[..size]`, logs and returns the results; stops early if `stop_signal` is set.
+pub unsafe fn run_benchmark(mem: *mut u8, size: usize, cpus: usize, seed: u64, stop_signal: &AtomicBool) -> Benchmark {
+    // page aligned arrays, so that none of them shares a line with another
+    let array_bytes = size / 3 / 4096 * 4096;
+    let len = array_bytes / 8;
+    let arrays = [0, 1, 2].map(|i| mem as usize + i * array_bytes);
+    let mut benchmark = Benchmark { array_bytes, scaling: Vec::new(), latency: None };
+
+    // random bits may be NaNs or denormals, which are slower to compute with
+    (0..cpus).into_par_iter().for_each(|t| {
+        for (i, value) in [1.0, 2.0, 0.0].into_iter().enumerate() {
+            std::slice::from_raw_parts_mut(arrays[i] as *mut f64, len)[t * len / cpus..(t + 1) * len / cpus].fill(value);
+        }
+    });
+
+    info!("Benchmark         : 3 arrays of {}MiB, best of {} runs", array_bytes / (1024 * 1024), RUNS);
+    info!("{:>8} {:>10} {:>10} {:>10} {:>10}  (MB/s)", "THREADS", "COPY", "SCALE", "ADD", "TRIAD");
+    'scaling: for threads in thread_counts(cpus) {
+        let pool = match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(e) => {
+                warn!("can't start a thread pool of {} threads: {}", threads, e);
+                continue;
+            }
+        };
+        let mut bandwidth = [0.0; 4];
+        for (kernel, bandwidth) in [Kernel::Copy, Kernel::Scale, Kernel::Add, Kernel::Triad].into_iter().zip(&mut bandwidth) {
+            let Some(time) = pool.install(|| best_time(kernel, arrays, len, threads, stop_signal)) else {
+                break 'scaling;
+            };
+            *bandwidth = (kernel.bytes() * len) as f64 / (1000. * 1000.) / time.as_secs_f64();
+        }
+        let [copy, scale, add, triad] = bandwidth;
+        info!("{:>8} {:>10.0} {:>10.0} {:>10.0} {:>10.0}", threads, copy, scale, add, triad);
+        benchmark.scaling.push(BenchmarkResult { threads, copy, scale, add, triad });
+    }
+
+    if !stop_signal.load(Ordering::SeqCst) {
+        let span = LATENCY_SPAN.min(size) / 64 * 64;
+        let latency = chase_latency(mem, span, seed);
+        info!("Memory latency    : {:.1}ns per random access over {}MiB", latency.as_secs_f64() * 1e9, span / (1024 * 1024));
+        benchmark.latency = Some(latency);
+    }
+    benchmark
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_counts_double_up_to_all_cpus() {
+        assert_eq!(thread_counts(1), [1]);
+        assert_eq!(thread_counts(6), [1, 2, 4, 6]);
+        assert_eq!(thread_counts(8), [1, 2, 4, 8]);
+    }
+}
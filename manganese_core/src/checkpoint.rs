@@ -0,0 +1,87 @@
+// Checkpoints of a run for `--resume`: after every test and every pass, the passes done, the tests
+// of the current pass done, the errors (and flipped bits and lines) so far and the seed of the run's generator are written to a
+// small TOML state file. A resumed run locks its memory again, starts from the stored counts and
+// seed and skips the tests that its interrupted pass had already done. The file is removed when a
+// run ends on its own (pass, time or error limit); a stopped or killed run leaves it behind.
+use log::{info, warn};
+use std::sync::atomic::Ordering;
+use crate::tests::RunState;
+
+pub(crate) struct Checkpoint {
+    path: String,
+    pub passes: usize,
+    /// Tests of pass `passes` + 1 done, in running order.
+    pub tests_done: Vec<String>,
+    pub errors: u64,
+    /// Flipped bits and corrupted cache lines so far, see `RunState::flip_totals()`.
+    pub flips: (u64, u64),
+    pub seed: u64,
+}
+
+impl Checkpoint {
+    /// A new run's checkpoint, written right away so that even its first test can be resumed.
+    pub fn new(path: &str, seed: u64) -> Self {
+        let checkpoint = Checkpoint { path: path.to_string(), passes: 0, tests_done: Vec::new(), errors: 0, flips: (0, 0), seed };
+        checkpoint.save();
+        checkpoint
+    }
+
+    /// The checkpoint in the state file at `path`, None if there is none.
+    pub fn load(path: &str) -> Result<Option<Self>, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("can't read the state file {}: {}", path, e)),
+        };
+        let table: toml::Table = toml::from_str(&text).map_err(|e| format!("invalid state file {}: {}", path, e))?;
+        let invalid = |key: &str| format!("invalid state file {}: no valid {}", path, key);
+        let count = |key: &str| table.get(key).and_then(toml::Value::as_integer).and_then(|n| u64::try_from(n).ok()).ok_or_else(|| invalid(key));
+        let tests_done = table.get("tests_done").and_then(toml::Value::as_array)
+            .and_then(|tests| tests.iter().map(|test| test.as_str().map(str::to_string)).collect::<Option<Vec<_>>>())
+            .ok_or_else(|| invalid("tests_done"))?;
+        // TOML integers are signed, the seed is stored in hex
+        let seed = table.get("seed").and_then(toml::Value::as_str)
+            .and_then(|seed| u64::from_str_radix(seed.trim_start_matches("0x"), 16).ok())
+            .ok_or_else(|| invalid("seed"))?;
+        // not in the state files of older versions
+        let flips = (count("flipped_bits").unwrap_or(0), count("corrupted_lines").unwrap_or(0));
+        Ok(Some(Checkpoint { path: path.to_string(), passes: count("passes")? as usize, tests_done, errors: count("errors")?, flips, seed }))
+    }
+
+    fn save(&self) {
+        let tests: Vec<String> = self.tests_done.iter().map(|test| toml::Value::from(test.as_str()).to_string()).collect();
+        let text = format!("# manganese run state, see --resume\npasses = {}\ntests_done = [{}]\nerrors = {}\nflipped_bits = {}\ncorrupted_lines = {}\nseed = \"0x{:016x}\"\n",
+            self.passes, tests.join(", "), self.errors, self.flips.0, self.flips.1, self.seed);
+        // a torn file would lose the checkpoint, so it replaces the old one in one rename
+        let temp = format!("{}.tmp", self.path);
+        if let Err(e) = std::fs::write(&temp, text).and_then(|()| std::fs::rename(&temp, &self.path)) {
+            warn!("can't write the state file {}: {}", self.path, e);
+        }
+    }
+
+    /// `test` of the current pass is done, with the errors of `run` so far.
+    pub fn test_done(&mut self, test: &str, run: &RunState) {
+        self.tests_done.push(test.to_string());
+        self.errors = run.errors.load(Ordering::Relaxed);
+        self.flips = run.flip_totals();
+        self.save();
+    }
+
+    /// `passes` passes are done, with the errors of `run` so far.
+    pub fn pass_done(&mut self, passes: usize, run: &RunState) {
+        self.passes = passes;
+        self.tests_done.clear();
+        self.errors = run.errors.load(Ordering::Relaxed);
+        self.flips = run.flip_totals();
+        self.save();
+    }
+
+    /// The run ended on its own, there's nothing to resume.
+    pub fn remove(self) {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => info!("Run complete, removed the state file {}", self.path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("can't remove the state file {}: {}", self.path, e),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+// Coherence ping-pong test, shared by all instruction set backends.
+// Threads are paired up and every pair owns a slice of the region. Both sides of a pair take
+// turns on a window of cache lines: verify what the partner wrote, write their own value and hand
+// the window over with a release/acquire token, so every line's ownership bounces between two
+// cores. On Linux the partners are pinned to CPUs behind different L3 caches (CCDs, sockets)
+// where possible, so the transfers cross the coherence fabric instead of staying in one cache.
+use std::sync::atomic::{AtomicU64, Ordering};
+use log::error;
+use crate::tests::{log_miscompare, miscompared_bytes, record_error, TestContext};
+
+const WINDOW: usize = 64 * 64;
+const ROUNDS: u64 = 2;
+
+// Value written by `side` to the `word`-th word in `round`; distinct per word, round and side
+fn value(seed: u64, word: usize, round: u64, side: u64) -> u64 {
+    seed ^ (word as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ (round << 1 | side).rotate_right(8)
+}
+
+#[repr(align(128))]
+struct Token(AtomicU64);
+
+fn wait_for(token: &AtomicU64, turn: u64) {
+    let mut spins = 0u32;
+    while token.load(Ordering::Acquire) != turn {
+        std::hint::spin_loop();
+        spins += 1;
+        // partners may share a core on oversubscribed systems
+        if spins.is_multiple_of(1024) {
+            std::thread::yield_now();
+        }
+    }
+}
+
+// Runs one pair side over `mem[start..start + len]`, returns the miscompared bytes.
+unsafe fn play(mem: *mut u8, start: usize, len: usize, side: u64, token: &AtomicU64, seed: u64, ctx: &TestContext) -> u64 {
+    let mut errors = 0;
+    let words = WINDOW / 8;
+    let mut turn = 0;
+
+    for window in (start..start + len / WINDOW * WINDOW).step_by(WINDOW) {
+        let base = window / 8;
+        for round in 0..ROUNDS {
+            for writer in 0..2 {
+                if writer == side {
+                    wait_for(token, turn);
+                    // verify the partner's writes of the previous turn
+                    if (round, writer) != (0, 0) {
+                        let (prev_round, prev_side) = if writer == 0 { (round - 1, 1) } else { (round, 0) };
+                        for w in 0..words {
+                            let actual = std::ptr::read_volatile((mem.add(window) as *const u64).add(w));
+                            let expected = value(seed, base + w, prev_round, prev_side);
+                            if actual != expected {
+                                if log_miscompare(ctx) {
+                                    error!("errors detected at offset 0x{:016x}", window + w * 8);
+                                }
+                                record_error(window + w * 8, expected, actual, ctx);
+                                errors += miscompared_bytes(expected, actual);
+                            }
+                        }
+                    }
+                    for w in 0..words {
+                        std::ptr::write_volatile((mem.add(window) as *mut u64).add(w), value(seed, base + w, round, side));
+                    }
+                    token.store(turn + 1, Ordering::Release);
+                }
+                turn += 1;
+            }
+        }
+        // the window's last writer is side 1; side 0 checks it before moving on
+        if side == 0 {
+            wait_for(token, turn);
+            for w in 0..words {
+                let actual = std::ptr::read_volatile((mem.add(window) as *const u64).add(w));
+                let expected = value(seed, base + w, ROUNDS - 1, 1);
+                if actual != expected {
+                    if log_miscompare(ctx) {
+                        error!("errors detected at offset 0x{:016x}", window + w * 8);
+                    }
+                    record_error(window + w * 8, expected, actual, ctx);
+                    errors += miscompared_bytes(expected, actual);
+                }
+            }
+            token.store(turn + 1, Ordering::Release);
+        }
+        turn += 1;
+    }
+
+    errors
+}
+
+// CPU pairs (side 0, side 1) preferring partners behind different L3 caches
+#[cfg(target_os = "linux")]
+fn cpu_pairs(pairs: usize) -> Vec<Option<(usize, usize)>> {
+    use glob::glob;
+
+    let mut cpus: Vec<(usize, usize)> = glob("/sys/devices/system/cpu/cpu[0-9]*/cache/index3/id")
+        .map(|paths| paths.flatten()
+            .filter_map(|path| {
+                let cpu = path.components()
+                    .find_map(|c| c.as_os_str().to_str()?.strip_prefix("cpu")?.parse().ok())?;
+                let l3 = std::fs::read_to_string(&path).ok()?.trim().parse().ok()?;
+                Some((l3, cpu))
+            })
+            .collect())
+        .unwrap_or_default();
+    cpus.sort();
+
+    // first half of the L3 domains against the second half
+    let half = cpus.len() / 2;
+    (0..pairs)
+        .map(|p| (half > 0).then(|| (cpus[p % half].1, cpus[half + p % half].1)))
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cpu_pairs(pairs: usize) -> Vec<Option<(usize, usize)>> {
+    vec![None; pairs]
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to(cpu: usize) {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        // best effort; an unpinned thread still bounces the lines
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_to(_cpu: usize) {}
+
+/// Ping-pong `mem[..size]` between `ctx.cpus / 2` thread pairs, returns the miscompared bytes.
+pub unsafe fn coherence_ping_pong(mem: *mut u8, size: usize, seed: u64, ctx: &TestContext) -> u64 {
+    let pairs = (ctx.cpus / 2).max(1);
+    let slice = size / pairs;
+    let tokens: Vec<Token> = (0..pairs).map(|_| Token(AtomicU64::new(0))).collect();
+    let cpus = cpu_pairs(pairs);
+    let mem_usize = mem as usize;
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..pairs)
+            .flat_map(|p| [(p, 0u64), (p, 1u64)])
+            .map(|(p, side)| {
+                let token = &tokens[p].0;
+                let cpu = cpus[p].map(|(a, b)| if side == 0 { a } else { b });
+                scope.spawn(move || {
+                    if let Some(cpu) = cpu {
+                        pin_to(cpu);
+                    }
+                    play(mem_usize as *mut u8, p * slice, slice, side, token, seed, ctx)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
+}
@@ -1,9 +1,214 @@
-use crate::InstructionSet;
-use crate::tests::{avx2_definitions, avx512_definitions, TestDefinition, TestKind};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::{parse_duration, InstructionSet, RunLimits};
+use crate::tests::{avx2_definitions, avx512_definitions, get_test_definitions_for_isa, neon_definitions, scalar_definitions, CacheMode, Partition, Phases, Prefetch, TestDefinition, TestKind, TestOptions};
 
+#[derive(Clone)]
 pub struct TestConfigEntry {
     pub kind: TestKind,
     pub loops: Option<usize>,
+    pub sleep: Option<u64>,
+    pub cache: Option<CacheMode>,
+    pub patterns: Option<Vec<u64>>,
+    pub dwell: Option<u64>,
+    pub interleave: Option<usize>,
+    pub ratio: Option<(usize, usize)>,
+    pub heat: Option<u64>,
+    pub stride: Option<usize>,
+    pub partition: Option<Partition>,
+    pub threads: Option<usize>,
+    pub phases: Option<Phases>,
+}
+
+impl TestConfigEntry {
+    /// `kind` with its default options.
+    pub fn new(kind: TestKind) -> Self {
+        TestConfigEntry {
+            kind, loops: None, sleep: None, cache: None, patterns: None, dwell: None, interleave: None,
+            ratio: None, heat: None, stride: None, partition: None, threads: None, phases: None,
+        }
+    }
+
+    fn loops(self, loops: usize) -> Self {
+        TestConfigEntry { loops: Some(loops), ..self }
+    }
+}
+
+/// Built-in test plans for `--profile`, in place of the test lines of `manganese.conf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// Streaming and address line tests, about 50 passes over the region: a few minutes smoke test.
+    Quick,
+    /// Every test enabled by default, as without a config file.
+    Standard,
+    /// The standard plan plus bit fade, more row hammer and refresh stress, for hours.
+    Extended,
+}
+
+impl Profile {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "quick" => Some(Profile::Quick),
+            "standard" => Some(Profile::Standard),
+            "extended" => Some(Profile::Extended),
+            _ => None,
+        }
+    }
+
+    /// The test lines of the plan; empty for the defaults.
+    pub fn entries(self, isa: InstructionSet) -> Vec<TestConfigEntry> {
+        use TestKind::*;
+        match self {
+            Profile::Quick => [BasicTests, March, Checkerboard, AddressLine]
+                .into_iter()
+                .map(|kind| TestConfigEntry::new(kind).loops(1))
+                .collect(),
+            Profile::Standard => Vec::new(),
+            Profile::Extended => {
+                let mut defaults: Vec<(TestKind, TestDefinition)> = get_test_definitions_for_isa(isa).into_iter().collect();
+                defaults.sort_by_key(|(_, def)| def.name);
+                defaults.into_iter()
+                    .filter_map(|(kind, def)| match kind {
+                        BitFade => Some(TestConfigEntry::new(kind).loops(1)),
+                        RowHammer => Some(TestConfigEntry::new(kind).loops(4)),
+                        RefreshStress => Some(TestConfigEntry::new(kind).loops(2)),
+                        Walking1Line | Walking0Line => Some(TestConfigEntry::new(kind).loops(1)),
+                        _ if def.loops > 0 => Some(TestConfigEntry::new(kind)),
+                        _ => None,
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The test lines of `manganese.conf` and its global settings.
+#[derive(Clone, Default)]
+pub struct CustomConfig {
+    pub entries: Vec<TestConfigEntry>,
+    /// `passes=N`, `duration=2h`, `max_errors=N` and `per_test_budget=3m` lines.
+    pub limits: RunLimits,
+    /// `ram=` size to test, used when none is given on the command line.
+    pub ram: Option<String>,
+    /// `threads=N` threads to run, all logical CPUs without.
+    pub threads: Option<usize>,
+    /// `prefetch=` distance of the verify loops, calibrated without.
+    pub prefetch: Option<Prefetch>,
+    /// The file it was read from.
+    pub path: Option<PathBuf>,
+    /// `[plan.NAME]` plans by name, for `--plan`.
+    pub plans: BTreeMap<String, CustomConfig>,
+    /// The plan in use.
+    pub plan: Option<String>,
+}
+
+impl CustomConfig {
+    /// The tests of plan `name` in place of the top-level ones, with its settings taking
+    /// precedence over the global ones.
+    pub fn use_plan(mut self, name: &str) -> Result<CustomConfig, String> {
+        let Some(plan) = self.plans.remove(name) else {
+            let names: Vec<&str> = self.plans.keys().map(String::as_str).collect();
+            return Err(if names.is_empty() {
+                format!("no plan '{}', the config file has no [plan.NAME] sections", name)
+            } else {
+                format!("no plan '{}' (the config file has {})", name, names.join(", "))
+            });
+        };
+        Ok(CustomConfig {
+            entries: plan.entries,
+            limits: plan.limits.or(self.limits),
+            ram: plan.ram.or(self.ram),
+            threads: plan.threads.or(self.threads),
+            prefetch: plan.prefetch.or(self.prefetch),
+            path: self.path,
+            plans: BTreeMap::new(),
+            plan: Some(name.to_string()),
+        })
+    }
+
+    /// The config in the TOML format, plans included, for `load_custom_config()` to read back.
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        write_globals(&mut out, self, "[global]", false);
+        write_tests(&mut out, &self.entries, "[[test]]");
+        for (name, plan) in &self.plans {
+            write_globals(&mut out, plan, &format!("[plan.{}]", name), true);
+            write_tests(&mut out, &plan.entries, &format!("[[plan.{}.test]]", name));
+        }
+        out
+    }
+}
+
+// The global settings of `config` as `section`, left out without any unless `always`
+fn write_globals(out: &mut String, config: &CustomConfig, section: &str, always: bool) {
+    let mut settings = Vec::new();
+    if let Some(ram) = &config.ram {
+        settings.push(format!("ram = {}", toml::Value::from(ram.as_str())));
+    }
+    if let Some(threads) = config.threads {
+        settings.push(format!("threads = {}", threads));
+    }
+    match config.prefetch {
+        Some(Prefetch::Auto) => settings.push("prefetch = \"auto\"".to_string()),
+        Some(Prefetch::Off) => settings.push("prefetch = \"off\"".to_string()),
+        Some(Prefetch::Distance(distance)) => settings.push(format!("prefetch = {}", distance)),
+        None => {}
+    }
+    if let Some(passes) = config.limits.passes {
+        settings.push(format!("passes = {}", passes));
+    }
+    if let Some(duration) = config.limits.duration {
+        settings.push(format!("duration = \"{}s\"", duration.as_secs()));
+    }
+    if let Some(max_errors) = config.limits.max_errors {
+        settings.push(format!("max_errors = {}", max_errors));
+    }
+    if let Some(budget) = config.limits.test_budget {
+        settings.push(format!("per_test_budget = \"{}s\"", budget.as_secs()));
+    }
+    if settings.is_empty() && !always {
+        return;
+    }
+    let _ = writeln!(out, "{}", section);
+    for setting in settings {
+        let _ = writeln!(out, "{}", setting);
+    }
+    out.push('\n');
+}
+
+// One `section` table per entry, with the options it sets
+fn write_tests(out: &mut String, entries: &[TestConfigEntry], section: &str) {
+    for entry in entries {
+        let _ = writeln!(out, "{}", section);
+        let _ = writeln!(out, "kind = \"{}\"", entry.kind.name());
+        let numbers = [("loops", entry.loops), ("sleep", entry.sleep.map(|v| v as usize)), ("dwell", entry.dwell.map(|v| v as usize)),
+            ("interleave", entry.interleave), ("heat", entry.heat.map(|v| v as usize)), ("stride", entry.stride), ("threads", entry.threads)];
+        for (key, value) in numbers {
+            if let Some(value) = value {
+                let _ = writeln!(out, "{} = {}", key, value);
+            }
+        }
+        if let Some(cache) = entry.cache {
+            let _ = writeln!(out, "cache = \"{}\"", match cache { CacheMode::Bypass => "bypass", CacheMode::Cached => "cached" });
+        }
+        if let Some(partition) = entry.partition {
+            let _ = writeln!(out, "partition = \"{}\"", match partition { Partition::Contiguous => "contiguous", Partition::Interleaved => "interleaved" });
+        }
+        if let Some(phases) = entry.phases {
+            let _ = writeln!(out, "phases = \"{}\"", match phases { Phases::Strict => "strict", Phases::Overlapped => "overlapped" });
+        }
+        if let Some((reads, writes)) = entry.ratio {
+            let _ = writeln!(out, "ratio = \"{}:{}\"", reads, writes);
+        }
+        if let Some(patterns) = &entry.patterns {
+            let patterns: Vec<String> = patterns.iter().map(|p| format!("\"0x{:016X}\"", p)).collect();
+            let _ = writeln!(out, "patterns = [{}]", patterns.join(", "));
+        }
+        out.push('\n');
+    }
 }
 
 pub fn build_tests_from_config(
@@ -13,7 +218,8 @@ pub fn build_tests_from_config(
     let defs = match isa {
         InstructionSet::AVX2 => avx2_definitions(),
         InstructionSet::AVX512 => avx512_definitions(),
-        _ => std::collections::HashMap::new(),
+        InstructionSet::NEON => neon_definitions(),
+        InstructionSet::SSE => scalar_definitions(),
     };
 
     // if no entries are given (empty/non-existant config; use defaults)
@@ -30,9 +236,24 @@ pub fn build_tests_from_config(
             result.push(TestDefinition {
                 name:   def.name,
                 passes: def.passes,
-                iters:  def.iters,
+                // the custom patterns test runs its passes once per pattern
+                iters:  entry.patterns.as_ref().map_or(def.iters, |p| def.iters * p.len()),
                 run:    def.run,
-                loops:  entry.loops.unwrap_or(def.loops),
+                // giving patterns enables the custom patterns test, disabled by default
+                loops:  entry.loops.unwrap_or(if entry.patterns.is_some() { def.loops.max(1) } else { def.loops }),
+                options: TestOptions {
+                    sleep: entry.sleep.unwrap_or(def.options.sleep),
+                    cache: entry.cache.unwrap_or(def.options.cache),
+                    patterns: entry.patterns.clone().unwrap_or_else(|| def.options.patterns.clone()),
+                    dwell: entry.dwell.unwrap_or(def.options.dwell),
+                    interleave: entry.interleave.unwrap_or(def.options.interleave),
+                    ratio: entry.ratio.unwrap_or(def.options.ratio),
+                    heat: entry.heat.unwrap_or(def.options.heat),
+                    stride: entry.stride.unwrap_or(def.options.stride),
+                    partition: entry.partition.unwrap_or(def.options.partition),
+                    threads: entry.threads.unwrap_or(def.options.threads),
+                    phases: entry.phases.unwrap_or(def.options.phases),
+                },
             });
         }
     }
@@ -40,9 +261,192 @@ pub fn build_tests_from_config(
     result
 }
 
-pub fn load_custom_config(path: &str) -> Result<Vec<TestConfigEntry>, Box<dyn std::error::Error>> {
+/// The tests of `kinds` that `isa` has no implementation of, which `build_tests_from_config` skips.
+pub fn unsupported_tests(kinds: impl IntoIterator<Item = TestKind>, isa: InstructionSet) -> Vec<TestKind> {
+    let defs = get_test_definitions_for_isa(isa);
+    kinds.into_iter().filter(|kind| !defs.contains_key(kind)).collect()
+}
+
+/// One line per test of the plan: its name, loops and the options it doesn't run with the
+/// defaults of.
+pub fn describe_plan(tests: &[TestDefinition], isa: InstructionSet) -> Vec<String> {
+    let defs = get_test_definitions_for_isa(isa);
+    tests.iter()
+        .filter(|test| test.loops > 0)
+        .map(|test| {
+            let mut changed = vec![format!("{}x", test.loops)];
+            let Some(def) = TestKind::parse(test.name).and_then(|kind| defs.get(&kind)) else {
+                return format!("{} ({})", test.name, changed.join(", "));
+            };
+            let (options, defaults) = (&test.options, &def.options);
+            if options.sleep != defaults.sleep { changed.push(format!("sleep={}", options.sleep)); }
+            if options.cache != defaults.cache { changed.push(format!("cache={:?}", options.cache).to_lowercase()); }
+            if options.patterns != defaults.patterns { changed.push(format!("patterns={}", options.patterns.len())); }
+            if options.dwell != defaults.dwell { changed.push(format!("dwell={}", options.dwell)); }
+            if options.interleave != defaults.interleave { changed.push(format!("interleave={}", options.interleave)); }
+            if options.ratio != defaults.ratio { changed.push(format!("ratio={}:{}", options.ratio.0, options.ratio.1)); }
+            if options.heat != defaults.heat { changed.push(format!("heat={}", options.heat)); }
+            if options.stride != defaults.stride { changed.push(format!("stride={}", options.stride)); }
+            if options.partition != defaults.partition { changed.push(format!("partition={:?}", options.partition).to_lowercase()); }
+            if options.threads != defaults.threads { changed.push(format!("threads={}", options.threads)); }
+            if options.phases != defaults.phases { changed.push(format!("phases={:?}", options.phases).to_lowercase()); }
+            format!("{} ({})", test.name, changed.join(", "))
+        })
+        .collect()
+}
+
+/// Narrow `tests` to the `only` tests, in that order, and drop the `skip` tests. Selected tests
+/// the config doesn't list run with their defaults, and disabled ones at least once.
+pub fn select_tests(tests: Vec<TestDefinition>, isa: InstructionSet, only: &[TestKind], skip: &[TestKind]) -> Vec<TestDefinition> {
+    let defs = get_test_definitions_for_isa(isa);
+    let mut tests = if only.is_empty() {
+        tests
+    } else {
+        only.iter()
+            .filter_map(|kind| tests.iter().find(|t| TestKind::parse(t.name) == Some(*kind)).or(defs.get(kind)))
+            .map(|test| TestDefinition { loops: test.loops.max(1), ..test.clone() })
+            .collect()
+    };
+    tests.retain(|t| !TestKind::parse(t.name).is_some_and(|kind| skip.contains(&kind)));
+    tests
+}
+
+/// A manganese.conf in the TOML format listing every test of `isa` with its default options,
+/// commented; unchanged it runs the same plan as no config file.
+pub fn default_config(isa: InstructionSet) -> String {
+    let mut tests: Vec<(TestKind, TestDefinition)> = get_test_definitions_for_isa(isa).into_iter().collect();
+    tests.sort_by_key(|(_, def)| def.name);
+
+    let mut out = String::from("\
+# manganese.conf: the test plan and run settings, see --list-tests for the test names.
+# Tests run in the order listed here; tests not listed don't run. A test left out runs with its
+# defaults when named with --tests.
+
+[global]
+# size to test when none is given on the command line: % of free RAM, %t of total RAM or a size
+# ram = \"50%\"
+# end the run after this many complete passes, or stop starting tests after this long
+# passes = 1
+# duration = \"8h\"
+# abort the run once this many errors were detected
+# max_errors = 100
+# run every test for about this long, its loops scaled to the time its first loop takes
+# per_test_budget = \"3m\"
+# threads to test with, all logical CPUs by default
+# threads = 8
+# bytes the verify loops prefetch ahead: auto (calibrated when the run starts), off or e.g. 1024
+# prefetch = \"auto\"
+
+# Options of every test:
+#   loops = N                  runs per pass, 0 disables the test
+#   cache = \"bypass\"           or \"cached\": cached stores and flush sweeps instead of streaming stores
+#   stride = 1                 fill and verify only every Nth cache line
+#   partition = \"contiguous\"   or \"interleaved\": threads take turns by cache line
+#   threads = N                run on N of the threads only, e.g. 1 for latency-bound tests
+#   phases = \"strict\"          or \"overlapped\": write one half of the region while verifying the other
+");
+    for (kind, def) in tests {
+        let prefix = if def.loops == 0 { "# " } else { "" };
+        let _ = write!(out, "\n# {}: {} passes of {} iterations", def.name, def.passes, def.iters);
+        out.push_str(if def.loops == 0 { ", disabled by default\n" } else { "\n" });
+        let _ = writeln!(out, "{}[[test]]", prefix);
+        let _ = writeln!(out, "{}kind = \"{}\"", prefix, def.name);
+        let _ = writeln!(out, "{}loops = {}", prefix, def.loops.max(1));
+        let options = &def.options;
+        match kind {
+            TestKind::BitFade => {
+                let _ = writeln!(out, "{}sleep = {}  # seconds between writing and verifying", prefix, options.sleep);
+            }
+            TestKind::ThermalCycle => {
+                let _ = writeln!(out, "{}heat = {}  # seconds of full bandwidth per cycle", prefix, options.heat);
+                let _ = writeln!(out, "{}sleep = {}  # seconds of cooldown per cycle", prefix, options.sleep);
+            }
+            TestKind::RefreshStress => {
+                let _ = writeln!(out, "{}dwell = {}  # milliseconds per window", prefix, options.dwell);
+            }
+            TestKind::ChannelStripes => {
+                let _ = writeln!(out, "{}interleave = {}  # channel interleave in bytes, a power of two", prefix, options.interleave);
+            }
+            TestKind::MixedRw => {
+                let _ = writeln!(out, "{}ratio = \"{}:{}\"  # random reads per sequential writes", prefix, options.ratio.0, options.ratio.1);
+            }
+            TestKind::CustomPatterns => {
+                let _ = writeln!(out, "{}patterns = [\"0xDEADBEEF\", \"0x0123456789ABCDEF\"]  # 64-bit, with their complements", prefix);
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+const CONFIG_NAME: &str = "manganese.conf";
+
+/// Where manganese.conf is looked for, in order: the working directory, then the user's and the
+/// system's configuration directory.
+pub fn config_search_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(CONFIG_NAME)];
+    #[cfg(not(windows))]
+    {
+        let user = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        paths.extend(user.map(|dir| dir.join("manganese").join(CONFIG_NAME)));
+        paths.push(PathBuf::from("/etc").join(CONFIG_NAME));
+    }
+    #[cfg(windows)]
+    {
+        paths.extend(env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("manganese").join(CONFIG_NAME)));
+        paths.extend(env::var_os("PROGRAMDATA").map(|dir| PathBuf::from(dir).join("manganese").join(CONFIG_NAME)));
+    }
+    paths
+}
+
+/// The first of `config_search_paths()` that exists.
+pub fn find_config() -> Option<PathBuf> {
+    config_search_paths().into_iter().find(|path| path.is_file())
+}
+
+/// Read `path`, in the TOML format if it has `[global]` or `[[test]]` sections and in the line
+/// format otherwise.
+pub fn load_custom_config(path: &Path) -> Result<CustomConfig, Box<dyn std::error::Error>> {
     let text = std::fs::read_to_string(path)?;
-    let mut list = Vec::new();
+    let config = if text.lines().any(|line| line.trim_start().starts_with('[')) {
+        parse_toml_config(&text)?
+    } else {
+        parse_line_config(&text)?
+    };
+    Ok(CustomConfig { path: Some(path.to_path_buf()), ..config })
+}
+
+/// Notices edits of the config file by its modification time.
+pub struct ConfigWatch {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatch {
+    pub fn new(path: &Path) -> Self {
+        ConfigWatch { path: path.to_path_buf(), modified: modified(path) }
+    }
+
+    /// Whether the file changed since the last call (or `new`).
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// One test per line: the test name, then key=value options. Global settings are lines with a
+// single key=value token.
+fn parse_line_config(text: &str) -> Result<CustomConfig, Box<dyn std::error::Error>> {
+    let mut config = CustomConfig::default();
 
     for (line_no, raw_line) in text.lines().enumerate() {
         let line = raw_line.trim();
@@ -52,6 +456,12 @@ pub fn load_custom_config(path: &str) -> Result<Vec<TestConfigEntry>, Box<dyn st
             continue;
         }
 
+        if let Some((key, val)) = line.split_once('=').filter(|(key, _)| !key.contains(char::is_whitespace)) {
+            if set_global(&mut config, key, val).map_err(|e| format!("{} on line {}", e, line_no + 1))? {
+                continue;
+            }
+        }
+
         // split into tokens
         let mut parts = line.split_whitespace();
 
@@ -63,20 +473,258 @@ pub fn load_custom_config(path: &str) -> Result<Vec<TestConfigEntry>, Box<dyn st
         let kind = TestKind::parse(test_name)
             .ok_or_else(|| format!("Unknown test '{}' on line {}", test_name, line_no + 1))?;
 
-        let mut loops = None;
-
-        // parse passes= and iters=
+        let mut entry = TestConfigEntry::new(kind);
         for token in parts {
-            if let Some(val) = token.strip_prefix("loops=") {
-                loops = Some(val.parse::<usize>()
-                    .map_err(|_| format!("Invalid loops value '{}' on line {}", val, line_no + 1))?);
-            } else {
-                return Err(format!("Unknown token '{}' on line {}", token, line_no + 1).into());
+            let (key, val) = token.split_once('=').unwrap_or((token, ""));
+            set_option(&mut entry, key, val).map_err(|e| format!("{} on line {}", e, line_no + 1))?;
+        }
+        config.entries.push(entry);
+    }
+
+    Ok(config)
+}
+
+// `[global]` with the global settings, one `[[test]]` table per test with its `kind` and options:
+// the keys of the line format, numbers as TOML integers and `patterns` as an array. Named plans
+// are `[plan.NAME]` tables with global settings of their own and `[[plan.NAME.test]]` tests.
+fn parse_toml_config(text: &str) -> Result<CustomConfig, Box<dyn std::error::Error>> {
+    let mut table: toml::Table = toml::from_str(text)?;
+    let mut config = CustomConfig::default();
+
+    if let Some(global) = table.remove("global") {
+        let global = global.as_table().ok_or("[global] must be a table")?;
+        toml_globals(&mut config, global.iter(), "[global]")?;
+    }
+    if let Some(tests) = table.remove("test") {
+        config.entries = toml_tests(&tests, "[[test]]")?;
+    }
+    if let Some(plans) = table.remove("plan") {
+        let plans = plans.as_table().ok_or("plans must be [plan.NAME] tables")?;
+        for (name, plan) in plans {
+            let plan = plan.as_table().ok_or_else(|| format!("[plan.{}] must be a table", name))?;
+            let mut entry = CustomConfig::default();
+            toml_globals(&mut entry, plan.iter().filter(|(key, _)| *key != "test"), &format!("[plan.{}]", name))?;
+            if let Some(tests) = plan.get("test") {
+                entry.entries = toml_tests(tests, &format!("[[plan.{}.test]]", name))?;
             }
+            config.plans.insert(name.clone(), entry);
+        }
+    }
+    if let Some(key) = table.keys().next() {
+        return Err(format!("Unknown section '{}' (expected [global], [[test]] or [plan.NAME])", key).into());
+    }
+
+    Ok(config)
+}
+
+// Global settings of `section`
+fn toml_globals<'a>(config: &mut CustomConfig, settings: impl Iterator<Item = (&'a String, &'a toml::Value)>, section: &str) -> Result<(), String> {
+    for (key, value) in settings {
+        let val = toml_option(value).ok_or_else(|| format!("Invalid value for '{}' in {}", key, section))?;
+        if !set_global(config, key, &val).map_err(|e| format!("{} in {}", e, section))? {
+            return Err(format!("Unknown setting '{}' in {}", key, section));
         }
+    }
+    Ok(())
+}
+
+// The tests of the `section` array of tables
+fn toml_tests(tests: &toml::Value, section: &str) -> Result<Vec<TestConfigEntry>, String> {
+    let tests = tests.as_array().ok_or_else(|| format!("tests must be {} tables", section))?;
+    let mut entries = Vec::new();
+    for (i, test) in tests.iter().enumerate() {
+        let test = test.as_table().ok_or_else(|| format!("tests must be {} tables", section))?;
+        let name = test.get("kind").and_then(toml::Value::as_str)
+            .ok_or_else(|| format!("Missing kind in {} {}", section, i + 1))?;
+        let kind = TestKind::parse(name)
+            .ok_or_else(|| format!("Unknown test '{}' in {} {}", name, section, i + 1))?;
+        let mut entry = TestConfigEntry::new(kind);
+        for (key, value) in test.iter().filter(|(key, _)| *key != "kind") {
+            let val = toml_option(value).ok_or_else(|| format!("Invalid value for '{}' in {} {}", key, section, i + 1))?;
+            set_option(&mut entry, key, &val).map_err(|e| format!("{} in {} {}", e, section, i + 1))?;
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+// A TOML value as the line format writes it: arrays comma separated
+fn toml_option(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Array(values) => values.iter()
+            .map(|v| match v {
+                toml::Value::Array(_) => None,
+                v => toml_option(v),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|values| values.join(",")),
+        _ => None,
+    }
+}
+
+// Apply the global setting `key`; false if it isn't one
+fn set_global(config: &mut CustomConfig, key: &str, val: &str) -> Result<bool, String> {
+    match key {
+        "ram" => config.ram = Some(val.to_string()),
+        "threads" => config.threads = Some(val.parse::<usize>().ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("Invalid threads value '{}' (expected a thread count >= 1)", val))?),
+        "prefetch" => config.prefetch = Some(Prefetch::parse(val)
+            .ok_or_else(|| format!("Invalid prefetch value '{}' (expected auto, off or a multiple of 64 up to 65536)", val))?),
+        "max_errors" => config.limits.max_errors = Some(val.parse::<u64>().ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("Invalid max_errors value '{}' (expected an error count >= 1)", val))?),
+        "passes" => config.limits.passes = Some(val.parse::<usize>().ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("Invalid passes value '{}' (expected a pass count >= 1)", val))?),
+        "duration" => config.limits.duration = Some(parse_duration(val)
+            .ok_or_else(|| format!("Invalid duration '{}' (expected e.g. 90s, 30m, 2h or 1d)", val))?),
+        "per_test_budget" => config.limits.test_budget = Some(parse_duration(val)
+            .ok_or_else(|| format!("Invalid per_test_budget '{}' (expected e.g. 90s, 30m, 2h or 1d)", val))?),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
 
-        list.push(TestConfigEntry { kind, loops });
+// Apply the test option `key`: loops, sleep, cache, patterns, dwell, interleave, ratio, heat,
+// stride, partition, threads or phases
+fn set_option(entry: &mut TestConfigEntry, key: &str, val: &str) -> Result<(), String> {
+    match key {
+        "loops" => entry.loops = Some(val.parse::<usize>()
+            .map_err(|_| format!("Invalid loops value '{}'", val))?),
+        "sleep" => entry.sleep = Some(val.parse::<u64>()
+            .map_err(|_| format!("Invalid sleep value '{}'", val))?),
+        "cache" => entry.cache = Some(CacheMode::parse(val)
+            .ok_or_else(|| format!("Invalid cache mode '{}' (expected bypass or cached)", val))?),
+        "patterns" => entry.patterns = Some(val.split(',')
+            .map(|p| parse_pattern(p).ok_or_else(|| format!("Invalid pattern '{}'", p)))
+            .collect::<Result<Vec<_>, _>>()?),
+        "dwell" => entry.dwell = Some(val.parse::<u64>()
+            .map_err(|_| format!("Invalid dwell value '{}'", val))?),
+        "interleave" => entry.interleave = Some(val.parse::<usize>().ok()
+            .filter(|v| v.is_power_of_two() && *v >= 64)
+            .ok_or_else(|| format!("Invalid interleave value '{}' (expected a power of two >= 64)", val))?),
+        "ratio" => entry.ratio = Some(parse_ratio(val)
+            .ok_or_else(|| format!("Invalid ratio '{}' (expected reads:writes, e.g. 2:1)", val))?),
+        "heat" => entry.heat = Some(val.parse::<u64>()
+            .map_err(|_| format!("Invalid heat value '{}'", val))?),
+        "stride" => entry.stride = Some(val.parse::<usize>().ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("Invalid stride value '{}' (expected a line count >= 1)", val))?),
+        "partition" => entry.partition = Some(Partition::parse(val)
+            .ok_or_else(|| format!("Invalid partition '{}' (expected contiguous or interleaved)", val))?),
+        "threads" => entry.threads = Some(val.parse::<usize>().ok()
+            .filter(|v| *v > 0)
+            .ok_or_else(|| format!("Invalid threads value '{}' (expected a thread count >= 1)", val))?),
+        "phases" => entry.phases = Some(Phases::parse(val)
+            .ok_or_else(|| format!("Invalid phases '{}' (expected strict or overlapped)", val))?),
+        _ => return Err(format!("Unknown option '{}'", key)),
     }
+    Ok(())
+}
+
+// 64-bit pattern, hexadecimal with a 0x prefix or decimal
+fn parse_pattern(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
 
-    Ok(list)
+// reads:writes, writes must be non-zero
+fn parse_ratio(s: &str) -> Option<(usize, usize)> {
+    let (reads, writes) = s.split_once(':')?;
+    let (reads, writes) = (reads.parse().ok()?, writes.parse().ok()?);
+    (writes > 0).then_some((reads, writes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn toml_and_line_formats_agree() {
+        let lines = parse_line_config("ram=4GiB\npasses=3\nmarch loops=2 stride=16\ncustom_patterns patterns=0xDEADBEEF,7 cache=cached\n").unwrap();
+        let toml = parse_toml_config(r#"
+            [global]
+            ram = "4GiB"
+            passes = 3
+
+            [[test]]
+            kind = "march"
+            loops = 2
+            stride = 16
+
+            [[test]]
+            kind = "custom_patterns"
+            patterns = ["0xDEADBEEF", 7]
+            cache = "cached"
+        "#).unwrap();
+        for config in [&lines, &toml] {
+            assert_eq!(config.ram.as_deref(), Some("4GiB"));
+            assert_eq!(config.limits.passes, Some(3));
+            assert_eq!(config.entries.len(), 2);
+            assert_eq!((config.entries[0].kind, config.entries[0].loops, config.entries[0].stride), (TestKind::March, Some(2), Some(16)));
+            assert_eq!(config.entries[1].patterns, Some(vec![0xDEADBEEF, 7]));
+            assert_eq!(config.entries[1].cache, Some(CacheMode::Cached));
+        }
+        assert!(parse_toml_config("[[test]]\nkind = \"march\"\nloop = 2\n").is_err());
+        assert!(parse_toml_config("[plans]\n").is_err());
+
+        let plans = parse_toml_config(r#"
+            [global]
+            passes = 3
+
+            [[test]]
+            kind = "march"
+
+            [plan.overnight]
+            duration = "8h"
+
+            [[plan.overnight.test]]
+            kind = "bit_fade"
+            loops = 1
+        "#).unwrap();
+        assert!(plans.plans.contains_key("overnight"));
+        let overnight = plans.use_plan("overnight").unwrap();
+        assert_eq!(overnight.entries.len(), 1);
+        assert_eq!(overnight.entries[0].kind, TestKind::BitFade);
+        assert_eq!((overnight.limits.passes, overnight.limits.duration), (Some(3), Some(Duration::from_secs(8 * 3600))));
+    }
+
+    #[test]
+    fn written_config_reads_back() {
+        let config = parse_line_config("ram=-2GiB\nduration=1h30m\nper_test_budget=3m\nprefetch=512\nmixed_rw ratio=3:1 partition=interleaved phases=overlapped\ncustom_patterns patterns=0xDEADBEEF threads=1\n").unwrap();
+        let written = parse_toml_config(&config.to_toml()).unwrap();
+        assert_eq!(written.ram.as_deref(), Some("-2GiB"));
+        assert_eq!(written.limits.duration, Some(Duration::from_secs(90 * 60)));
+        assert_eq!(written.limits.test_budget, Some(Duration::from_secs(180)));
+        assert_eq!(written.prefetch, Some(Prefetch::Distance(512)));
+        assert_eq!(written.entries.len(), 2);
+        assert_eq!((written.entries[0].ratio, written.entries[0].partition), (Some((3, 1)), Some(Partition::Interleaved)));
+        assert_eq!(written.entries[0].phases, Some(Phases::Overlapped));
+        assert_eq!((written.entries[1].patterns.clone(), written.entries[1].threads), (Some(vec![0xDEADBEEF]), Some(1)));
+    }
+
+    #[test]
+    fn default_config_runs_the_defaults() {
+        let isa = InstructionSet::SSE;
+        let config = parse_toml_config(&default_config(isa)).unwrap();
+        let names = |tests: Vec<TestDefinition>| tests.into_iter()
+            .filter(|t| t.loops > 0)
+            .map(|t| (t.name, t.loops))
+            .collect::<Vec<_>>();
+        assert_eq!(names(build_tests_from_config(&config.entries, isa)), names(build_tests_from_config(&[], isa)));
+    }
+
+    #[test]
+    fn patterns_enable_the_custom_patterns_test() {
+        let config = parse_line_config("custom_patterns patterns=0xDEADBEEF,7
+").unwrap();
+        let tests = build_tests_from_config(&config.entries, InstructionSet::SSE);
+        assert_eq!((tests[0].loops, tests[0].iters), (1, 2));
+    }
 }
@@ -0,0 +1,178 @@
+// Cycle mode ("memory hot-borrowing"): instead of locking a fixed region for the whole run, every
+// cycle allocates and locks most of whatever memory is free right now, runs a few quick tests on
+// it and gives it back. The OS moves its own pages around in between, so on a busy system the
+// frames it was using get borrowed in later cycles and, over time, nearly all of RAM is tested.
+// Where page frame numbers are visible (root on Linux) the distinct frames tested are counted.
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use log::{error, info, warn};
+use crate::badram::BadPages;
+use crate::pagemap::{page_frames, physical_address};
+use crate::platform::{aligned_alloc, aligned_free, getpagesize, mlock, munlock, sysinfo};
+use crate::records::log_pass_summary;
+use crate::report::ReportBuilder;
+use crate::tests::{run_test, tests_configure, TestContext, TestDefinition};
+use crate::RunLimits;
+
+// Quick streaming tests run on every borrowed block
+const CYCLE_TESTS: [&str; 3] = ["basic_tests", "march", "checkerboard"];
+// Share of the free memory borrowed, the rest is left to the OS
+const BORROW_PERCENT: usize = 90;
+
+// Distinct page frames seen, as a bitmap indexed by PFN
+struct FrameCoverage(Vec<u64>);
+
+impl FrameCoverage {
+    fn add(&mut self, pfns: &[u64]) {
+        for &pfn in pfns {
+            let (word, bit) = (pfn as usize / 64, pfn % 64);
+            if word >= self.0.len() {
+                self.0.resize(word + 1, 0);
+            }
+            self.0[word] |= 1 << bit;
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+/// Borrow, test and release the free memory in cycles until the run is stopped or one of the
+/// `limits` is reached, running the enabled tests of `test_config` that are in CYCLE_TESTS, adding every
+/// test run to `report`.
+/// Returns the number of errors detected and the number of complete cycles.
+pub fn run_cycles(test_config: &[TestDefinition], alignment: usize, limits: RunLimits, report: &mut ReportBuilder, ctx: &mut TestContext) -> (u64, usize) {
+    let run = ctx.run;
+    let tests: Vec<&TestDefinition> = test_config.iter()
+        .filter(|t| t.loops > 0 && CYCLE_TESTS.contains(&t.name))
+        .collect();
+    if tests.is_empty() {
+        error!("Cycle mode needs at least one of {:?} enabled", CYCLE_TESTS);
+        return (0, 0);
+    }
+
+    let page = getpagesize();
+    let total_pages = sysinfo().totalram / page;
+    let mut coverage = FrameCoverage(Vec::new());
+    let mut frames_visible = true;
+    let mut bad_pages = BadPages::default();
+    let start = Instant::now();
+    run.set_limits(limits.max_errors, limits.deadline(start));
+    let mut cycles = 0;
+    let mut aborted = false;
+    info!("Cycle Mode        : borrowing {}% of the free memory per cycle for {}", BORROW_PERCENT,
+        tests.iter().map(|t| t.name).collect::<Vec<_>>().join(", "));
+
+    while !run.stop_signal.load(Ordering::SeqCst) && !aborted && !limits.time_reached(start)
+        && limits.passes.is_none_or(|passes| cycles < passes) {
+        let free = sysinfo().freeram;
+        let mut size = free / 100 * BORROW_PERCENT / alignment * alignment;
+        // halve until the borrowed block can be locked as well as allocated
+        let mem = loop {
+            if size < alignment {
+                break None;
+            }
+            let mem = unsafe { aligned_alloc(alignment, size) };
+            if !mem.is_null() {
+                if unsafe { mlock(mem, size) } == 0 {
+                    break Some(mem);
+                }
+                unsafe { aligned_free(mem) };
+            }
+            size = size / 2 / alignment * alignment;
+        };
+        let Some(mem) = mem else {
+            warn!("Cycle {}: no memory to borrow ({}MiB free), retrying", cycles + 1, free / (1024 * 1024));
+            std::thread::sleep(Duration::from_secs(1));
+            continue;
+        };
+
+        let cycle_start = Instant::now();
+        let errors = run.errors.load(Ordering::Relaxed);
+        let mut cycle_records = Vec::new();
+        let mut cycle_dropped = 0;
+        for test in &tests {
+            if run.stop_signal.load(Ordering::SeqCst) {
+                break;
+            }
+            if limits.errors_reached(run.errors.load(Ordering::Relaxed)) {
+                aborted = true;
+                break;
+            }
+            tests_configure(test, ctx);
+            run.take_first_error();
+            run.take_error_offsets();
+            let test_start = Instant::now();
+            let test_errors = run.errors.load(Ordering::Relaxed);
+            run.dump.buffer(mem, size);
+            unsafe { run_test(test, mem, size, ctx) };
+            let (records, dropped) = run.records.take(test.name, cycles + 1);
+            if frames_visible {
+                bad_pages.add_records(mem, &records);
+            }
+            run.log_unlogged_miscompares(test.name);
+            let seconds = test_start.elapsed().as_secs_f64();
+            let bandwidth = (test.passes * test.iters) as f64 * (size as f64 / (1000. * 1000.)) / seconds;
+            report.test_result(test.name, cycles + 1, seconds, bandwidth, run.errors.load(Ordering::Relaxed) - test_errors);
+            report.records(&records, dropped);
+            report.tested_bytes(size);
+            cycle_records.extend(records);
+            cycle_dropped += dropped;
+            // the block is gone after this cycle, its physical addresses are what identifies a fault
+            for offset in run.take_error_offsets() {
+                match physical_address(mem as usize + offset) {
+                    Some(phys) => error!("{}: miscompare at physical address 0x{:012x} (PFN 0x{:x})", test.name, phys.address, phys.pfn),
+                    None => error!("{}: miscompare at offset 0x{:016x} of a {}MiB block", test.name, offset, size / (1024 * 1024)),
+                }
+            }
+        }
+
+        log_pass_summary(&format!("Cycle {}", cycles + 1), &cycle_records, cycle_dropped);
+
+        if frames_visible {
+            let pfns = page_frames(mem as usize, size);
+            frames_visible = !pfns.is_empty();
+            coverage.add(&pfns);
+        }
+        unsafe {
+            munlock(mem, size);
+            aligned_free(mem);
+        }
+        cycles += 1;
+
+        let coverage_info = if frames_visible {
+            format!(", {} distinct frames tested so far ({:.1}% of RAM)", coverage.count(), 100. * coverage.count() as f64 / total_pages as f64)
+        } else {
+            String::new()
+        };
+        info!("Cycle {}: tested {}MiB in {:.2} sec, {} errors{}", cycles, size / (1024 * 1024),
+            cycle_start.elapsed().as_secs_f64(), run.errors.load(Ordering::Relaxed) - errors, coverage_info);
+    }
+
+    let errors = run.errors.load(Ordering::Relaxed);
+    info!("Cycle mode stopped after {} cycles in {:.2}s", cycles, start.elapsed().as_secs_f64());
+    if aborted {
+        error!("\x1b[1;91mAborted due to error threshold: {} errors, limit {}\x1b[0m", errors, limits.max_errors.unwrap_or(0));
+    }
+    if errors > 0 {
+        error!("\x1b[1;91mSummary: {} errors in {} cycles\x1b[0m", errors, cycles);
+    } else {
+        info!("Summary: no errors in {} cycles", cycles);
+    }
+    bad_pages.log();
+    (errors, cycles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameCoverage;
+
+    #[test]
+    fn frame_coverage_counts_distinct() {
+        let mut coverage = FrameCoverage(Vec::new());
+        coverage.add(&[3, 64, 200]);
+        coverage.add(&[3, 65, 200]);
+        assert_eq!(coverage.count(), 4);
+    }
+}
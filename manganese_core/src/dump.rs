@@ -0,0 +1,83 @@
+// Memory dump of the first miscompare (`--dump-on-error`): the window around the failing word is
+// copied the moment the miscompare is recorded, before later passes rewrite it, and written as a
+// hex dump (xxd format, `xxd -r` turns it back into binary) for offline analysis of the
+// corruption's shape: a single bit, a whole burst or an entire cache line.
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use log::{error, info};
+
+struct Dump {
+    path: String,
+    // bytes before and after the failing word
+    window: usize,
+    // buffer the running test checks
+    mem: usize,
+    size: usize,
+}
+
+/// The dump of one run's first miscompare, see `RunState::dump`.
+#[derive(Default)]
+pub struct ErrorDump {
+    armed: AtomicBool,
+    dump: Mutex<Option<Dump>>,
+}
+
+impl ErrorDump {
+    /// Write the memory within `window` bytes of the next miscompare to `path`, once.
+    pub fn arm(&self, path: &str, window: usize) {
+        *self.dump.lock().unwrap() = Some(Dump { path: path.to_string(), window, mem: 0, size: 0 });
+        self.armed.store(true, Ordering::Relaxed);
+    }
+
+    /// The buffer the next test runs on, which miscompare offsets are relative to.
+    pub fn buffer(&self, mem: *const u8, size: usize) {
+        if let Some(dump) = self.dump.lock().unwrap().as_mut() {
+            dump.mem = mem as usize;
+            dump.size = size;
+        }
+    }
+
+    /// Dump the memory around the miscompare at `offset` if armed, from the reporting thread.
+    pub(crate) fn first_error(&self, offset: usize) {
+        if !self.armed.load(Ordering::Relaxed) || !self.armed.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let Some(dump) = self.dump.lock().unwrap().take() else { return };
+        write_dump(dump, offset);
+    }
+}
+
+fn write_dump(dump: Dump, offset: usize) {
+    if dump.mem == 0 || offset >= dump.size {
+        return;
+    }
+    let start = offset.saturating_sub(dump.window) / 16 * 16;
+    let end = (offset + 8 + dump.window).div_ceil(16).saturating_mul(16).min(dump.size);
+    // other threads keep testing around it, this is a snapshot
+    let mut bytes = vec![0u8; end - start];
+    unsafe { std::ptr::copy_nonoverlapping((dump.mem + start) as *const u8, bytes.as_mut_ptr(), bytes.len()); }
+    match std::fs::write(&dump.path, hex_dump(start, &bytes)) {
+        Ok(()) => info!("Memory around the first miscompare (offset 0x{:016x}) written to {}: 0x{:x}..0x{:x}",
+            offset, dump.path, start, end),
+        Err(e) => error!("can't write the memory dump to {}: {}", dump.path, e),
+    }
+}
+
+// xxd style: offset, 8 groups of 2 bytes, printable ASCII
+fn hex_dump(start: usize, bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 5);
+    for (i, line) in bytes.chunks(16).enumerate() {
+        let _ = write!(out, "{:08x}:", start + i * 16);
+        for pair in line.chunks(2) {
+            out.push(' ');
+            for byte in pair {
+                let _ = write!(out, "{:02x}", byte);
+            }
+        }
+        out.push_str("  ");
+        out.extend(line.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }));
+        out.push('\n');
+    }
+    out
+}
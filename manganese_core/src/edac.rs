@@ -0,0 +1,94 @@
+// Linux EDAC (ECC) counters: snapshots of the corrected/uncorrected error counts of every DIMM
+// known to the kernel's memory controller drivers, taken around each test so ECC events can be
+// attributed to the test and DIMM that triggered them. Without EDAC (other platforms, no driver
+// loaded, non-ECC memory) no snapshot is available and reporting is skipped.
+
+#[derive(Debug, Clone)]
+pub struct DimmCounters {
+    pub label: String,
+    pub corrected: u64,
+    pub uncorrected: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EdacSnapshot {
+    pub dimms: Vec<DimmCounters>,
+}
+
+impl EdacSnapshot {
+    /// Per-DIMM counter increase since `before`, only DIMMs that saw new events.
+    pub fn since(&self, before: &EdacSnapshot) -> Vec<DimmCounters> {
+        self.dimms.iter()
+            .filter_map(|now| {
+                let prev = before.dimms.iter().find(|d| d.label == now.label)?;
+                let corrected = now.corrected.saturating_sub(prev.corrected);
+                let uncorrected = now.uncorrected.saturating_sub(prev.uncorrected);
+                (corrected > 0 || uncorrected > 0).then(|| DimmCounters {
+                    label: now.label.clone(),
+                    corrected,
+                    uncorrected,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn edac_snapshot() -> Option<EdacSnapshot> {
+    use glob::glob;
+    use std::fs;
+    use std::path::Path;
+
+    let read_u64 = |path: &Path| -> Option<u64> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    };
+    // fall back to the sysfs location when the firmware left the label empty
+    let label = |path: &Path, fallback: &Path| -> String {
+        fs::read_to_string(path).ok()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| fallback.display().to_string())
+    };
+
+    let mut snapshot = EdacSnapshot::default();
+
+    // current layout: mcX/dimmY (or mcX/rankY for rank-granular drivers)
+    let dirs = ["dimm*", "rank*"].iter()
+        .filter_map(|d| glob(&format!("/sys/devices/system/edac/mc/mc*/{}", d)).ok())
+        .flat_map(|paths| paths.flatten());
+    for dir in dirs {
+        let (Some(corrected), Some(uncorrected)) = (
+            read_u64(&dir.join("dimm_ce_count")),
+            read_u64(&dir.join("dimm_ue_count")),
+        ) else {
+            continue;
+        };
+        snapshot.dimms.push(DimmCounters {
+            label: label(&dir.join("dimm_label"), &dir),
+            corrected,
+            uncorrected,
+        });
+    }
+
+    // legacy layout: mcX/csrowY/chZ_{ce_count,dimm_label}; uncorrected errors are only counted
+    // per csrow and are attributed to its first channel
+    if snapshot.dimms.is_empty() {
+        for path in glob("/sys/devices/system/edac/mc/mc*/csrow*/ch*_ce_count").ok()?.flatten() {
+            let Some(corrected) = read_u64(&path) else { continue };
+            let name = path.file_name()?.to_string_lossy().replace("_ce_count", "");
+            let csrow = path.parent()?;
+            snapshot.dimms.push(DimmCounters {
+                label: label(&csrow.join(format!("{}_dimm_label", name)), &csrow.join(&name)),
+                corrected,
+                uncorrected: if name == "ch0" { read_u64(&csrow.join("ue_count")).unwrap_or(0) } else { 0 },
+            });
+        }
+    }
+
+    if snapshot.dimms.is_empty() { None } else { Some(snapshot) }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn edac_snapshot() -> Option<EdacSnapshot> {
+    None
+}
@@ -0,0 +1,92 @@
+// Optional CUDA pinned host memory support (`pinned-gpu` feature).
+// The locked test region is registered with the CUDA runtime as portable, mapped host memory,
+// i.e. the same page-locked, DMA-accessible RAM path GPU workloads use, and then tested like any
+// other allocation. libcudart is loaded at runtime, so no CUDA toolkit is needed to build.
+
+#[cfg(target_os = "linux")]
+mod cuda {
+    use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
+
+    const CUDA_SUCCESS: c_int = 0;
+    const CUDA_HOST_REGISTER_PORTABLE: c_uint = 1;
+    const CUDA_HOST_REGISTER_MAPPED: c_uint = 2;
+
+    const LIBRARIES: [&CStr; 4] = [c"libcudart.so", c"libcudart.so.12", c"libcudart.so.11.0", c"libcudart.so.10.2"];
+
+    type HostRegister = unsafe extern "C" fn(*mut c_void, usize, c_uint) -> c_int;
+    type HostUnregister = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type HostGetDevicePointer = unsafe extern "C" fn(*mut *mut c_void, *mut c_void, c_uint) -> c_int;
+    type GetErrorString = unsafe extern "C" fn(c_int) -> *const c_char;
+
+    pub struct PinnedRegion {
+        ptr: *mut u8,
+        device_ptr: usize,
+        unregister: HostUnregister,
+    }
+
+    unsafe fn symbol<T>(lib: *mut c_void, name: &CStr) -> Result<T, String> {
+        let sym = libc::dlsym(lib, name.as_ptr());
+        if sym.is_null() {
+            return Err(format!("{} not found in the CUDA runtime", name.to_string_lossy()));
+        }
+        Ok(std::mem::transmute_copy(&sym))
+    }
+
+    impl PinnedRegion {
+        /// Register `ptr[..size]` as pinned, device-mapped host memory.
+        pub unsafe fn register(ptr: *mut u8, size: usize) -> Result<Self, String> {
+            let lib = LIBRARIES.iter()
+                .map(|name| libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL))
+                .find(|lib| !lib.is_null())
+                .ok_or("CUDA runtime (libcudart.so) not found")?;
+
+            let register: HostRegister = symbol(lib, c"cudaHostRegister")?;
+            let unregister: HostUnregister = symbol(lib, c"cudaHostUnregister")?;
+            let get_device_pointer: HostGetDevicePointer = symbol(lib, c"cudaHostGetDevicePointer")?;
+            let error_string: GetErrorString = symbol(lib, c"cudaGetErrorString")?;
+            let describe = |err: c_int| CStr::from_ptr(error_string(err)).to_string_lossy().into_owned();
+
+            let err = register(ptr as *mut c_void, size, CUDA_HOST_REGISTER_PORTABLE | CUDA_HOST_REGISTER_MAPPED);
+            if err != CUDA_SUCCESS {
+                return Err(format!("cudaHostRegister failed: {}", describe(err)));
+            }
+
+            // the device-side mapping proves the region is reachable by GPU DMA
+            let mut device_ptr = std::ptr::null_mut();
+            let err = get_device_pointer(&mut device_ptr, ptr as *mut c_void, 0);
+            if err != CUDA_SUCCESS {
+                unregister(ptr as *mut c_void);
+                return Err(format!("cudaHostGetDevicePointer failed: {}", describe(err)));
+            }
+
+            Ok(PinnedRegion { ptr, device_ptr: device_ptr as usize, unregister })
+        }
+
+        pub fn device_ptr(&self) -> usize {
+            self.device_ptr
+        }
+    }
+
+    impl Drop for PinnedRegion {
+        fn drop(&mut self) {
+            unsafe { (self.unregister)(self.ptr as *mut c_void); }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod cuda {
+    pub struct PinnedRegion;
+
+    impl PinnedRegion {
+        pub unsafe fn register(_ptr: *mut u8, _size: usize) -> Result<Self, String> {
+            Err("pinned GPU memory is only supported on Linux".into())
+        }
+
+        pub fn device_ptr(&self) -> usize {
+            0
+        }
+    }
+}
+
+pub use cuda::PinnedRegion;
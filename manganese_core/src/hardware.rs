@@ -6,6 +6,7 @@ pub enum InstructionSet {
     SSE,
     AVX2,
     AVX512,
+    NEON,
 }
 
 // CPUID feature bit definitions
@@ -56,7 +57,16 @@ pub fn hardware_instruction_set() -> InstructionSet {
             }
         }
     }
-    #[cfg(not(target_arch = "x86_64"))]
+    #[cfg(target_arch = "aarch64")]
+    {
+        // NEON (ASIMD) is mandatory for AArch64, but stay defensive
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            InstructionSet::NEON
+        } else {
+            InstructionSet::SSE
+        }
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
     {
         InstructionSet::SSE
     }
@@ -177,11 +187,65 @@ pub fn hardware_ram_speed(configured: bool) -> u64 {
 }
 
 
-#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+// system_profiler only reports one speed per module (the configured one on Intel Macs,
+// none for the unified memory of Apple Silicon)
+#[cfg(target_os = "macos")]
+pub fn hardware_ram_speed(_configured: bool) -> u64 {
+    system_profiler(&["SPMemoryDataType"])
+        .map_or(0, |text| parse_system_profiler(&text).memory_devices.iter().map(|m| m.speed as u64).max().unwrap_or(0))
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+pub fn hardware_ram_speed(configured: bool) -> u64 {
+    collect_system_info().memory_devices.iter()
+        .map(|m| if configured { m.configured_speed } else { m.speed } as u64)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos", target_os = "freebsd", target_os = "openbsd")))]
 pub fn hardware_ram_speed(_configured: bool) -> u64 {
     0
 }
 
+/// CPU and DIMM temperatures in °C as (sensor, reading), empty where no sensors are exposed.
+#[cfg(target_os = "linux")]
+pub fn hardware_temperatures() -> Vec<(String, f64)> {
+    use glob::glob;
+    use std::fs;
+
+    // hwmon drivers of CPU packages/dies and of DIMM thermal sensors (DDR4 jc42, DDR5 spd5118)
+    const CHIPS: [&str; 6] = ["coretemp", "k10temp", "zenpower", "cpu_thermal", "jc42", "spd5118"];
+
+    let mut readings = Vec::new();
+    let Ok(inputs) = glob("/sys/class/hwmon/hwmon*/temp*_input") else {
+        return readings;
+    };
+    for input in inputs.flatten() {
+        let Some(dir) = input.parent() else { continue };
+        let chip = fs::read_to_string(dir.join("name")).unwrap_or_default().trim().to_string();
+        if !CHIPS.contains(&chip.as_str()) {
+            continue;
+        }
+        let Some(millidegrees) = fs::read_to_string(&input).ok().and_then(|t| t.trim().parse::<i64>().ok()) else {
+            continue;
+        };
+        let sensor = input.file_name().unwrap_or_default().to_string_lossy().replace("_input", "");
+        let label = fs::read_to_string(dir.join(format!("{}_label", sensor)))
+            .map(|l| l.trim().to_string())
+            .unwrap_or(sensor);
+        let hwmon = dir.file_name().unwrap_or_default().to_string_lossy();
+        readings.push((format!("{} {} ({})", chip, label, hwmon), millidegrees as f64 / 1000.));
+    }
+    readings.sort_by(|a, b| a.0.cmp(&b.0));
+    readings
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn hardware_temperatures() -> Vec<(String, f64)> {
+    Vec::new()
+}
+
 pub fn hardware_cpu_count() -> usize {
     #[cfg(windows)]
     {
@@ -283,6 +347,8 @@ pub struct SystemInfo {
     pub memory_devices: Vec<MemoryInfo>, // includes recorded slots; populated flag indicates actual module
     /// Type 16 NumberOfDevices (if present and >0)
     pub memory_array_slots: Option<u8>,
+    /// Type 20 physical address ranges decoded by the memory devices
+    pub mapped_ranges: Vec<MappedRange>,
     pub hide_serials: bool,
 }
 
@@ -315,6 +381,7 @@ pub struct BoardInfo {
 
 #[derive(Debug, Default, Clone)]
 pub struct MemoryInfo {
+    pub handle: u16,
     pub speed: u16,
     pub configured_speed: u16,
     pub manufacturer: String,
@@ -328,6 +395,13 @@ pub struct MemoryInfo {
     pub populated: bool,
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct MappedRange {
+    pub start: u64,
+    pub end: u64,            // exclusive, in bytes
+    pub device_handle: u16,  // Type 17 handle
+}
+
 impl fmt::Display for SystemInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(cpu) = &self.cpu {
@@ -415,6 +489,10 @@ fn le_u32_at(buf: &[u8], idx: usize) -> u32 {
     u32::from_le_bytes([a, b, c, d])
 }
 
+fn le_u64_at(buf: &[u8], idx: usize) -> u64 {
+    le_u32_at(buf, idx) as u64 | (le_u32_at(buf, idx + 4) as u64) << 32
+}
+
 /// Find start index of next SMBIOS structure (skip formatted area + strings)
 fn smb_next_structure(buf: &[u8], offset: usize) -> Option<usize> {
     let len = *buf.get(offset + 1)? as usize;
@@ -577,6 +655,7 @@ fn parse_type17_memory(buf: &[u8], offset: usize) -> Option<MemoryInfo> {
     let populated = size_mb > 0;
 
     Some(MemoryInfo {
+        handle: le_u16_at(buf, offset + 2),
         speed,
         configured_speed: configured,
         manufacturer,
@@ -591,6 +670,29 @@ fn parse_type17_memory(buf: &[u8], offset: usize) -> Option<MemoryInfo> {
     })
 }
 
+fn parse_type20_mapped(buf: &[u8], offset: usize) -> Option<MappedRange> {
+    let struct_len = *buf.get(offset + 1)? as usize;
+    if offset + struct_len > buf.len() || struct_len < 0x13 { return None; }
+
+    // addresses are in KiB, the ending one is the last KiB of the range;
+    // 0xFFFFFFFF means the byte addresses are in the extended fields (SMBIOS 2.7+)
+    let start_kb = le_u32_at(buf, offset + 0x04);
+    let end_kb = le_u32_at(buf, offset + 0x08);
+    let (start, end) = if start_kb == 0xFFFF_FFFF {
+        if struct_len < 0x23 { return None; }
+        (le_u64_at(buf, offset + 0x13), le_u64_at(buf, offset + 0x1B).checked_add(1)?)
+    } else {
+        (start_kb as u64 * 1024, (end_kb as u64 + 1) * 1024)
+    };
+    if end <= start { return None; }
+
+    Some(MappedRange {
+        start,
+        end,
+        device_handle: le_u16_at(buf, offset + 0x0C),
+    })
+}
+
 fn parse_slot_index(locator: &str) -> Option<u8> {
     let s = locator.trim();
     let mut rev = String::new();
@@ -734,11 +836,189 @@ fn load_smbios_table() -> Option<Vec<u8>> {
     Some(buffer)
 }
 
+/// Structure table address and length from a SMBIOS 2.x ("_SM_") or 3.x ("_SM3_") entry point
+#[cfg(any(target_os = "freebsd", target_os = "openbsd", test))]
+fn smbios_entry_point(entry: &[u8]) -> Option<(u64, usize)> {
+    if entry.starts_with(b"_SM3_") {
+        Some((le_u32_at(entry, 0x10) as u64 | (le_u32_at(entry, 0x14) as u64) << 32, le_u32_at(entry, 0x0C) as usize))
+    } else if entry.starts_with(b"_SM_") {
+        Some((le_u32_at(entry, 0x18) as u64, le_u16_at(entry, 0x16) as usize))
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "freebsd")]
+fn kenv(name: &str) -> Option<String> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut value = [0u8; 128];
+    let len = unsafe { libc::kenv(libc::KENV_GET, name.as_ptr(), value.as_mut_ptr() as *mut _, value.len() as i32) };
+    (len > 0).then(|| String::from_utf8_lossy(&value[..len as usize - 1]).trim().to_string())
+}
+
+#[cfg(target_os = "openbsd")]
+fn kenv(_name: &str) -> Option<String> {
+    None
+}
+
+// The table is read from /dev/mem (root only), at the entry point FreeBSD's smbios(4) found or
+// the one in the legacy BIOS area
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+fn load_smbios_table() -> Option<Vec<u8>> {
+    use std::fs::File;
+    use std::os::unix::fs::FileExt;
+
+    let mem = File::open("/dev/mem").ok()?;
+    let mut entry = vec![0u8; 32];
+    match kenv("hint.smbios.0.mem").and_then(|a| u64::from_str_radix(a.trim_start_matches("0x"), 16).ok()) {
+        Some(address) => mem.read_exact_at(&mut entry, address).ok()?,
+        None => {
+            let mut bios = vec![0u8; 0x10000];
+            mem.read_exact_at(&mut bios, 0xF0000).ok()?;
+            let at = (0..bios.len() - 32).step_by(16).find(|&i| smbios_entry_point(&bios[i..]).is_some())?;
+            entry.copy_from_slice(&bios[at..at + 32]);
+        }
+    }
+    let (address, len) = smbios_entry_point(&entry)?;
+    let mut table = vec![0u8; len];
+    mem.read_exact_at(&mut table, address).ok()?;
+    Some(table)
+}
+
+// macOS doesn't expose the SMBIOS table to userspace (and Apple Silicon has none)
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "freebsd", target_os = "openbsd")))]
+fn load_smbios_table() -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn system_profiler(data_types: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("system_profiler").args(data_types).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Board, CPU and memory modules from the text output of
+// `system_profiler SPHardwareDataType SPMemoryDataType`
+#[cfg(any(target_os = "macos", test))]
+fn parse_system_profiler(text: &str) -> SystemInfo {
+    // "8 GB" / "512 MB" / "Empty"
+    fn size_mb(value: &str) -> u32 {
+        match value.split_once(' ') {
+            Some((n, "GB")) => n.parse::<u32>().unwrap_or(0) * 1024,
+            Some((n, "MB")) => n.parse().unwrap_or(0),
+            _ => 0,
+        }
+    }
+    fn leading_number(value: &str) -> u32 {
+        value.split_whitespace().next().and_then(|n| n.parse().ok()).unwrap_or(0)
+    }
+
+    let mut sys = SystemInfo::default();
+    let mut cpu = CpuInfo::default();
+    let mut board = BoardInfo { manufacturer: "Apple".to_string(), ..Default::default() };
+    let mut model = (String::new(), String::new());
+    let mut section = "";
+    let mut module: Option<MemoryInfo> = None;
+
+    for line in text.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let Some((key, value)) = line.trim().split_once(':') else { continue };
+        let value = value.trim();
+        if indent == 0 {
+            section = key;
+            continue;
+        }
+        match (section, key, value.is_empty()) {
+            // a slot of an Intel Mac ("BANK 0/ChannelA-DIMM0:")
+            ("Memory", "Memory Slots", true) => {}
+            ("Memory", _, true) => {
+                sys.memory_devices.extend(module.take());
+                module = Some(MemoryInfo { locator: key.to_string(), ..Default::default() });
+            }
+            (_, _, true) => {}
+            // Apple Silicon lists the unified memory without any slot
+            ("Memory", "Memory", false) => {
+                sys.memory_devices.extend(module.take());
+                module = Some(MemoryInfo { locator: "Unified Memory".to_string(), size_mb: size_mb(value), ..Default::default() });
+            }
+            ("Memory", _, false) => {
+                let Some(m) = module.as_mut() else { continue };
+                match key {
+                    "Size" => m.size_mb = size_mb(value),
+                    "Speed" => m.speed = leading_number(value) as u16,
+                    "Manufacturer" => m.manufacturer = value.to_string(),
+                    "Part Number" => m.part_number = value.to_string(),
+                    "Serial Number" => m.serial = value.to_string(),
+                    _ => {}
+                }
+            }
+            ("Hardware", "Model Name", false) => model.0 = value.to_string(),
+            ("Hardware", "Model Identifier", false) => model.1 = value.to_string(),
+            ("Hardware", "Chip" | "Processor Name", false) => cpu.name = value.to_string(),
+            ("Hardware", "Total Number of Cores", false) => cpu.cores = leading_number(value),
+            ("Hardware", "Serial Number (system)", false) => board.serial = value.to_string(),
+            _ => {}
+        }
+    }
+    sys.memory_devices.extend(module);
+
+    for m in sys.memory_devices.iter_mut() {
+        m.configured_speed = m.speed;
+        m.slot_index = parse_slot_index(&m.locator);
+        m.populated = m.size_mb > 0;
+    }
+    if !cpu.name.is_empty() {
+        cpu.manufacturer = if cpu.name.starts_with("Apple") { "Apple" } else { "Intel" }.to_string();
+        cpu.socket = "built-in".to_string();
+        sys.cpu = Some(cpu);
+    }
+    if !model.0.is_empty() {
+        board.product = format!("{} ({})", model.0, model.1);
+        sys.board = Some(board);
+    }
+    assign_memory_channels(&mut sys);
+    sys
+}
+
+#[cfg(target_os = "macos")]
+fn collect_system_info_fallback() -> SystemInfo {
+    use crate::platform::sysctl_value;
+
+    let Some(text) = system_profiler(&["SPHardwareDataType", "SPMemoryDataType"]) else {
+        return SystemInfo::default();
+    };
+    let mut sys = parse_system_profiler(&text);
+    if let Some(cpu) = sys.cpu.as_mut() {
+        cpu.threads = sysctl_value::<u32>("hw.logicalcpu").unwrap_or(0);
+        cpu.l1_kb = (sysctl_value::<u64>("hw.l1dcachesize").unwrap_or(0) / 1024) as u32;
+        cpu.l2_kb = (sysctl_value::<u64>("hw.l2cachesize").unwrap_or(0) / 1024) as u32;
+        cpu.l3_kb = (sysctl_value::<u64>("hw.l3cachesize").unwrap_or(0) / 1024) as u32;
+    }
+    sys
+}
+
+// Without /dev/mem the loader still exports the board's strings to the kernel environment
+#[cfg(target_os = "freebsd")]
+fn collect_system_info_fallback() -> SystemInfo {
+    let board = kenv("smbios.planar.maker").map(|manufacturer| BoardInfo {
+        manufacturer,
+        product: kenv("smbios.planar.product").unwrap_or_default(),
+        version: kenv("smbios.planar.version").unwrap_or_default(),
+        serial: kenv("smbios.planar.serial").unwrap_or_default(),
+    });
+    SystemInfo { board, ..Default::default() }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+fn collect_system_info_fallback() -> SystemInfo {
+    SystemInfo::default()
+}
+
 pub fn collect_system_info() -> SystemInfo {
     let mut sys = SystemInfo::default();
     let buf = match load_smbios_table() {
         Some(b) => b,
-        None => return sys,
+        None => return collect_system_info_fallback(),
     };
 
     // Build cache map first
@@ -780,6 +1060,11 @@ pub fn collect_system_info() -> SystemInfo {
                     sys.memory_devices.push(m);
                 }
             }
+            20 => {
+                if let Some(r) = parse_type20_mapped(&buf, offset) {
+                    sys.mapped_ranges.push(r);
+                }
+            }
             _ => {}
         }
 
@@ -843,6 +1128,19 @@ impl SystemInfo {
     pub fn total_slots(&self) -> usize {
         self.memory_devices.len()
     }
+
+    /// Populated memory devices whose Type 20 range contains the physical address `address`.
+    /// More than one means the range is interleaved across them (or the firmware reports every
+    /// DIMM with the whole range), so the error can't be pinned to a single module.
+    pub fn dimms_at(&self, address: u64) -> Vec<&MemoryInfo> {
+        let mut dimms: Vec<&MemoryInfo> = self.mapped_ranges.iter()
+            .filter(|r| (r.start..r.end).contains(&address))
+            .filter_map(|r| self.memory_devices.iter().find(|m| m.populated && m.handle == r.device_handle))
+            .collect();
+        dimms.sort_by_key(|m| m.handle);
+        dimms.dedup_by_key(|m| m.handle);
+        dimms
+    }
 }
 
 #[cfg(test)]
@@ -855,4 +1153,74 @@ mod tests {
         info!("{:#?}", info);
         info!("{}", info);
     }
+
+    #[test]
+    fn type20_dimm_lookup() {
+        // 4GiB..8GiB in KiB, then the same range through the extended byte addresses
+        let mut buf = vec![20u8, 0x23, 0x00, 0x01];
+        buf.extend_from_slice(&(4u32 << 20).to_le_bytes());
+        buf.extend_from_slice(&((8u32 << 20) - 1).to_le_bytes());
+        buf.extend_from_slice(&[0x11, 0x00, 0, 0, 1, 0, 0]);
+        buf.extend_from_slice(&[0; 16]);
+        buf.extend_from_slice(&[0, 0]);
+        let kb = parse_type20_mapped(&buf, 0).unwrap();
+        buf[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        buf[0x13..0x1B].copy_from_slice(&(4u64 << 30).to_le_bytes());
+        buf[0x1B..0x23].copy_from_slice(&((8u64 << 30) - 1).to_le_bytes());
+        let extended = parse_type20_mapped(&buf, 0).unwrap();
+        for r in [&kb, &extended] {
+            assert_eq!((r.start, r.end, r.device_handle), (4 << 30, 8 << 30, 0x11));
+        }
+
+        let sys = SystemInfo {
+            memory_devices: vec![
+                MemoryInfo { handle: 0x11, locator: "DIMM_A2".into(), populated: true, ..Default::default() },
+                MemoryInfo { handle: 0x12, locator: "DIMM_B2".into(), populated: true, ..Default::default() },
+            ],
+            mapped_ranges: vec![kb],
+            ..Default::default()
+        };
+        let dimms: Vec<&str> = sys.dimms_at(5 << 30).iter().map(|m| m.locator.as_str()).collect();
+        assert_eq!(dimms, ["DIMM_A2"]);
+        assert!(sys.dimms_at(8 << 30).is_empty());
+    }
+
+    #[test]
+    fn entry_points() {
+        let mut entry = [0u8; 32];
+        entry[..5].copy_from_slice(b"_SM3_");
+        entry[0x0C..0x10].copy_from_slice(&0x1234u32.to_le_bytes());
+        entry[0x10..0x18].copy_from_slice(&0x1_7FF0_0000u64.to_le_bytes());
+        assert_eq!(smbios_entry_point(&entry), Some((0x1_7FF0_0000, 0x1234)));
+
+        let mut entry = [0u8; 32];
+        entry[..4].copy_from_slice(b"_SM_");
+        entry[0x16..0x18].copy_from_slice(&0x0ABCu16.to_le_bytes());
+        entry[0x18..0x1C].copy_from_slice(&0x000F_0000u32.to_le_bytes());
+        assert_eq!(smbios_entry_point(&entry), Some((0xF_0000, 0xABC)));
+        assert_eq!(smbios_entry_point(b"_DMI_"), None);
+    }
+
+    #[test]
+    fn system_profiler_output() {
+        let intel = "Hardware:\n\n    Hardware Overview:\n\n      Model Name: iMac\n      Model Identifier: iMac19,1\n\
+            \x20     Processor Name: 8-Core Intel Core i9\n      Total Number of Cores: 8\n\n\
+            Memory:\n\n    Memory Slots:\n\n      ECC: Disabled\n\n        BANK 0/ChannelA-DIMM0:\n\n\
+            \x20         Size: 16 GB\n          Type: DDR4\n          Speed: 2667 MHz\n          Manufacturer: 0x802C\n\n\
+            \x20       BANK 1/ChannelB-DIMM0:\n\n          Size: Empty\n          Speed: Empty\n";
+        let sys = parse_system_profiler(intel);
+        assert_eq!(sys.cpu.as_ref().unwrap().name, "8-Core Intel Core i9");
+        assert_eq!(sys.board.as_ref().unwrap().product, "iMac (iMac19,1)");
+        assert_eq!(sys.memory_devices.len(), 2);
+        let dimm = &sys.memory_devices[0];
+        assert_eq!((dimm.locator.as_str(), dimm.size_mb, dimm.speed, dimm.populated), ("BANK 0/ChannelA-DIMM0", 16384, 2667, true));
+        assert!(!sys.memory_devices[1].populated);
+
+        let apple = "Hardware:\n\n    Hardware Overview:\n\n      Model Name: MacBook Pro\n      Chip: Apple M2 Max\n\
+            \x20     Memory: 32 GB\n\nMemory:\n\n      Memory: 32 GB\n      Type: LPDDR5\n      Manufacturer: Hynix\n";
+        let sys = parse_system_profiler(apple);
+        assert_eq!(sys.cpu.as_ref().unwrap().manufacturer, "Apple");
+        assert_eq!(sys.memory_devices.len(), 1);
+        assert_eq!((sys.memory_devices[0].size_mb, sys.memory_devices[0].manufacturer.as_str()), (32768, "Hynix"));
+    }
 }
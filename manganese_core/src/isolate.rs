@@ -0,0 +1,124 @@
+// Fault isolation: after a test reports miscompares, the test is rerun on the 64MiB window around
+// the first failing offset with escalating repetitions until it reproduces there, then the window
+// is bisected (the half holding the original offset first) down to the smallest range that still
+// reproduces. The final range is retested a fixed number of times for a repeatability figure.
+// Miscompares of the retests are the same fault again and are not added to the run's error count.
+use std::ops::Range;
+use std::sync::atomic::Ordering;
+use log::{error, info, warn};
+use crate::platform::getpagesize;
+use crate::tests::{stop_requested, TestContext, TestDefinition};
+
+const WINDOW: usize = 64 * 1024 * 1024;
+const MIN_RANGE: usize = 1024 * 1024;
+const INTENSITY: [usize; 3] = [1, 2, 4];
+const REPEATABILITY_RUNS: usize = 10;
+
+// Runs `test` up to `runs` times on `mem[start..start + len]`, returns how many runs miscompared.
+unsafe fn retest(test: &TestDefinition, mem: *mut u8, start: usize, len: usize, runs: usize, ctx: &TestContext) -> usize {
+    (0..runs)
+        .take_while(|_| !stop_requested(ctx))
+        .filter(|_| {
+            let before = ctx.run.errors.load(Ordering::Relaxed);
+            (test.run)(mem.add(start), len, ctx);
+            ctx.run.errors.load(Ordering::Relaxed) > before
+        })
+        .count()
+}
+
+/// Narrow down the fault `test` hit at `offset` of `mem[..size]` and report the isolated range.
+/// Returns the range and in how many of the repeatability runs it reproduced.
+pub unsafe fn isolate_fault(test: &TestDefinition, mem: *mut u8, size: usize, offset: usize, ctx: &TestContext) -> Option<(Range<usize>, usize)> {
+    let run = ctx.run;
+    let errors = run.errors.load(Ordering::Relaxed);
+    let flips = run.flip_totals();
+    let page = getpagesize();
+    let mut start = offset / WINDOW * WINDOW;
+    let mut len = WINDOW.min(size - start);
+
+    info!("Isolating {} fault at offset 0x{:016x} (retest offsets are relative to the retested range)", test.name, offset);
+    let Some(runs) = INTENSITY.into_iter().find(|&runs| retest(test, mem, start, len, runs, ctx) > 0) else {
+        warn!("{}: fault at offset 0x{:016x} did not reproduce in its {}MiB window",
+            test.name, offset, len / (1024 * 1024));
+        run.errors.store(errors, Ordering::Relaxed);
+        run.set_flip_totals(flips);
+        run.take_first_error();
+        run.records.discard();
+        return None;
+    };
+
+    while len / 2 >= MIN_RANGE && !stop_requested(ctx) {
+        let half = len / 2 / page * page;
+        let lower = (start, half);
+        let upper = (start + half, len - half);
+        let (first, second) = if offset < start + half { (lower, upper) } else { (upper, lower) };
+        if retest(test, mem, first.0, first.1, runs, ctx) > 0 {
+            (start, len) = first;
+        } else if retest(test, mem, second.0, second.1, runs, ctx) > 0 {
+            (start, len) = second;
+        } else {
+            // the fault needs more of the range than either half (e.g. coupling across it)
+            break;
+        }
+    }
+
+    let reproduced = retest(test, mem, start, len, REPEATABILITY_RUNS, ctx);
+    error!("{}: fault isolated to 0x{:016x}..0x{:016x} ({}KiB), reproduced in {}/{} runs ({:.0}%)",
+        test.name, start, start + len, len / 1024,
+        reproduced, REPEATABILITY_RUNS, 100. * reproduced as f64 / REPEATABILITY_RUNS as f64);
+
+    run.errors.store(errors, Ordering::Relaxed);
+    run.set_flip_totals(flips);
+    run.take_first_error();
+    run.records.discard();
+    Some((start..start + len, reproduced))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{alloc, dealloc, Layout};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use super::*;
+    use crate::tests::{Pause, RunState, TestOptions};
+
+    static BAD: AtomicUsize = AtomicUsize::new(0);
+
+    // Fails whenever the tested range covers the address in BAD
+    unsafe fn stuck_cell(mem: *mut u8, size: usize, ctx: &TestContext) {
+        if (mem as usize..mem as usize + size).contains(&BAD.load(Ordering::Relaxed)) {
+            ctx.run.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn bisects_to_failing_range() {
+        let test = TestDefinition {
+            name: "stuck_cell",
+            passes: 1,
+            iters: 1,
+            run: stuck_cell,
+            loops: 1,
+            options: TestOptions::default(),
+        };
+        let stop = AtomicBool::new(false);
+        let pause = Pause::default();
+        let run = RunState::new(1, 1, 0, &stop, &pause);
+        let ctx = TestContext::new(&run);
+        let size = 96 * 1024 * 1024;
+        let offset = 70 * 1024 * 1024 + 12345;
+        unsafe {
+            let layout = Layout::from_size_align(size, 4096).unwrap();
+            let mem = alloc(layout);
+            BAD.store(mem as usize + offset, Ordering::Relaxed);
+
+            let (range, reproduced) = isolate_fault(&test, mem, size, offset, &ctx).unwrap();
+            assert!(range.contains(&offset) && range.len() < 2 * MIN_RANGE, "{:x?}", range);
+            assert_eq!(reproduced, REPEATABILITY_RUNS);
+            assert_eq!(run.errors.load(Ordering::Relaxed), 0);
+
+            BAD.store(0, Ordering::Relaxed);
+            assert!(isolate_fault(&test, mem, size, offset, &ctx).is_none());
+            dealloc(mem, layout);
+        }
+    }
+}
@@ -1,80 +1,367 @@
+mod affinity;
+mod attribution;
+mod badram;
+mod benchmark;
+mod checkpoint;
+mod coherence;
+mod cycle;
+mod dump;
 mod hardware;
+mod isolate;
+mod metrics;
+mod numa;
+mod pagemap;
 mod platform;
+mod pointer_chase;
+mod progress;
+mod records;
+mod report;
+mod selftest;
+mod session;
+mod sgemm;
 mod simd_xorshift;
 mod tests;
 mod tests_avx2;
 mod tests_avx512;
+mod tests_neon;
+mod tests_scalar;
 mod config;
+mod edac;
+#[cfg(feature = "pinned-gpu")]
+mod gpu;
 
-use std::sync::atomic::{AtomicBool, Ordering, AtomicU64};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use log::{error, info, warn};
-use crate::config::{build_tests_from_config, load_custom_config};
+use rayon::ThreadPool;
+use crate::affinity::{domain_thread_pool, format_cpulist, l3_domains, pin_threads};
+use crate::attribution::DimmErrors;
+use crate::badram::BadPages;
+use crate::config::{build_tests_from_config, config_search_paths, describe_plan, select_tests, unsupported_tests, ConfigWatch};
+use crate::cycle::run_cycles;
+use crate::edac::edac_snapshot;
+use crate::checkpoint::Checkpoint;
+use crate::isolate::isolate_fault;
+use crate::numa::{bind_to_node, numa_nodes, NumaNode};
+use crate::pagemap::physical_address;
+use crate::metrics::Subscribers;
+use crate::progress::RunProgress;
+use crate::records::{log_pass_summary, FaultHistory};
+use crate::report::ReportBuilder;
+use crate::selftest::run_self_test;
+use crate::benchmark::run_benchmark;
+use crate::session::RunOptions;
+pub use crate::affinity::Affinity;
+pub use crate::benchmark::{Benchmark, BenchmarkResult};
+pub use crate::config::{default_config, find_config, load_custom_config, CustomConfig, Profile, TestConfigEntry};
+pub use crate::metrics::{ErrorDetail, Metric, TestEvent};
+pub use crate::records::ErrorRecord;
+pub use crate::tests::{Prefetch, TestKind};
+pub use crate::report::{RunReport, TestStats};
+pub use crate::session::{RamSpec, TestSession, TestSessionBuilder};
+pub use crate::progress::{format_duration, Progress};
 pub use crate::hardware::{hardware_cpu_count, hardware_instruction_set, hardware_is_needlessly_disabled, hardware_ram_speed, InstructionSet};
-pub use crate::platform::{aligned_alloc, aligned_free, getpagesize, mlock, sysinfo};
-use crate::tests::{tests_init};
+pub use crate::platform::{aligned_alloc, aligned_free, getpagesize, hugepage_alloc, drop_caches, hugepage_free, mlock, munlock, raise_memlock_limit, sysinfo, write_combining_alloc};
+use crate::tests::{calibrate_prefetch, get_test_definitions_for_isa, run_test, tests_configure, tests_init, vector_width, Partition, Pause, Phases, TestContext, TestDefinition};
 
-pub static ERRORS: AtomicU64 = AtomicU64::new(0);
 
-#[derive(Debug, Clone, Copy)]
-pub enum RamSpec {
-    Percent(f64, bool), // fraction, is_total
-    Bytes(usize),
+// Process exit statuses, so scripts can tell a failing machine from a failing setup
+/// No errors detected.
+pub const EXIT_PASS: i32 = 0;
+/// Miscompares or uncorrected ECC errors detected, or the self-test failed.
+pub const EXIT_ERRORS: i32 = 1;
+/// Invalid arguments or manganese.conf.
+pub const EXIT_CONFIG: i32 = 2;
+/// The memory to test couldn't be allocated, locked or mapped.
+pub const EXIT_ALLOCATION: i32 = 3;
+
+/// Why a run couldn't start, or was given up before testing.
+#[derive(Debug)]
+pub enum RunError {
+    /// Invalid arguments or manganese.conf.
+    Config(String),
+    /// The memory to test couldn't be allocated, locked or mapped.
+    Allocation(String),
+    /// The self-test showed that errors aren't reliably detected.
+    SelfTest,
+}
+
+impl RunError {
+    /// The process exit status for the error.
+    pub fn exit_status(&self) -> i32 {
+        match self {
+            RunError::Config(_) => EXIT_CONFIG,
+            RunError::Allocation(_) => EXIT_ALLOCATION,
+            RunError::SelfTest => EXIT_ERRORS,
+        }
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::Config(message) | RunError::Allocation(message) => f.write_str(message),
+            RunError::SelfTest => f.write_str("Self-test failed: errors are not reliably detected, aborting"),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+// Streaming tests rerun on the write-combining region after every loop
+const WRITE_COMBINING_TESTS: [&str; 4] = ["basic_tests", "march", "random_inversions", "checkerboard"];
+const WRITE_COMBINING_MAX: usize = 256 * 1024 * 1024;
+
+/// Conditions that end a run by themselves, before it is stopped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunLimits {
+    /// Complete passes (cycles with `--cycle`) to run.
+    pub passes: Option<usize>,
+    /// Time after which the running test is stopped and no further one is started.
+    pub duration: Option<Duration>,
+    /// Errors after which the run is aborted.
+    pub max_errors: Option<u64>,
+    /// Time every test is given: its loops are scaled to it after the first one, which is timed.
+    pub test_budget: Option<Duration>,
+}
+
+impl RunLimits {
+    /// These limits, the ones not set taken from `other`.
+    pub fn or(self, other: RunLimits) -> RunLimits {
+        RunLimits {
+            passes: self.passes.or(other.passes),
+            duration: self.duration.or(other.duration),
+            max_errors: self.max_errors.or(other.max_errors),
+            test_budget: self.test_budget.or(other.test_budget),
+        }
+    }
+
+    // --duration (or duration= in the config) as the end of a run started at `start`
+    pub(crate) fn deadline(&self, start: Instant) -> Option<Instant> {
+        self.duration.map(|duration| start + duration)
+    }
+
+    // --max-errors (or max_errors= in the config) reached with `errors` so far, checked between tests (`RunState::stop_requested` checks it mid-test)
+    pub(crate) fn errors_reached(&self, errors: u64) -> bool {
+        self.max_errors.is_some_and(|max| errors >= max)
+    }
+
+    // --duration (or duration= in the config) over for a run started at `start`
+    pub(crate) fn time_reached(&self, start: Instant) -> bool {
+        self.duration.is_some_and(|duration| start.elapsed() >= duration)
+    }
+
+    fn log(&self) {
+        if let Some(passes) = self.passes {
+            info!("Pass Limit        : {} passes", passes);
+        }
+        if let Some(duration) = self.duration {
+            info!("Time Limit        : {}s", duration.as_secs());
+        }
+        if let Some(max) = self.max_errors {
+            info!("Error Threshold   : aborting after {} errors", max);
+        }
+        if let Some(budget) = self.test_budget {
+            info!("Test Budget       : {} per test, in whole loops", format_duration(budget));
+        }
+    }
+
+    // Loops of a test whose first loop took `first_loop` that fit the test budget, at least one
+    fn budget_loops(&self, loops: usize, first_loop: Duration) -> usize {
+        match self.test_budget {
+            Some(budget) => (budget.as_secs_f64() / first_loop.as_secs_f64().max(1e-3)).round().max(1.) as usize,
+            None => loops,
+        }
+    }
 }
 
-pub fn parse_ram_spec(input: &str) -> Option<RamSpec> {
+/// A test as `--list-tests` shows it, with its defaults.
+pub struct TestSummary {
+    pub name: &'static str,
+    pub passes: usize,
+    pub iters: usize,
+    /// 0 for tests that are disabled by default.
+    pub loops: usize,
+}
+
+/// Every test available with this CPU's instruction set, by name.
+pub fn test_catalog() -> Vec<TestSummary> {
+    let mut tests: Vec<TestSummary> = get_test_definitions_for_isa(hardware_instruction_set()).into_values()
+        .map(|test| TestSummary { name: test.name, passes: test.passes, iters: test.iters, loops: test.loops })
+        .collect();
+    tests.sort_by_key(|test| test.name);
+    tests
+}
+
+/// "90s", "30m", "2h", "1d" or plain seconds, "1h30m" combined.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim().to_lowercase();
+    if let Ok(secs) = input.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let mut total = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return None,
+        };
+        total += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+    number.is_empty().then_some(Duration::from_secs(total)).filter(|d| !d.is_zero())
+}
+
+/// Bytes to test for `input`: a % of the `free` RAM ("50%"), %t of the `total` RAM ("80%t"), an
+/// SI size ("4GiB", "300M") or all of the free (total) RAM but a reserve: "free-2GiB" or "-2GiB"
+/// ("total-8GiB"). None if invalid or the reserve leaves nothing.
+pub fn parse_ram_spec(input: &str, total: usize, free: usize) -> Option<usize> {
     let input = input.trim().to_uppercase();
 
-    if input.ends_with("T") && input.contains('%') { // % of total
+    if let Some(reserve) = input.strip_prefix("FREE-").or_else(|| input.strip_prefix('-')) {
+        free.checked_sub(parse_size(reserve)?).filter(|bytes| *bytes > 0)
+    } else if let Some(reserve) = input.strip_prefix("TOTAL-") {
+        total.checked_sub(parse_size(reserve)?).filter(|bytes| *bytes > 0)
+    } else if input.ends_with("T") && input.contains('%') { // % of total
         let num = input.trim_end_matches("%T").parse::<f64>().ok()?;
-        Some(RamSpec::Percent(num / 100.0, true))
+        Some((total as f64 * num / 100.0) as usize)
     } else if input.ends_with('%') { // % of free
         let num = input.trim_end_matches('%').parse::<f64>().ok()?;
-        Some(RamSpec::Percent(num / 100.0, false))
-    } else { // SI suffix
-        let multipliers = [
-            ("KI", 1024),
-            ("K", 1000),
-            ("MI", 1024*1024),
-            ("M", 1000*1000),
-            ("GI", 1024*1024*1024),
-            ("G", 1000*1000*1000),
-        ];
-
-        let mut number_str = input.as_str();
-        let mut multiplier = 1;
-
-        for (suffix, mult) in &multipliers {
-            if let Some(s) = input.strip_suffix(suffix) {
-                number_str = s;
-                multiplier = *mult;
-                break;
-            }
+        Some((free as f64 * num / 100.0) as usize)
+    } else {
+        parse_size(&input)
+    }
+}
+
+// Upper case size with an SI suffix, "4GI", "4GIB" or "300M"
+fn parse_size(input: &str) -> Option<usize> {
+    let multipliers = [
+        ("KI", 1024),
+        ("K", 1000),
+        ("MI", 1024*1024),
+        ("M", 1000*1000),
+        ("GI", 1024*1024*1024),
+        ("G", 1000*1000*1000),
+    ];
+
+    let input = input.strip_suffix('B').unwrap_or(input);
+    let mut number_str = input;
+    let mut multiplier = 1;
+
+    for (suffix, mult) in &multipliers {
+        if let Some(s) = input.strip_suffix(suffix) {
+            number_str = s;
+            multiplier = *mult;
+            break;
         }
+    }
+
+    let bytes = number_str.parse::<f64>().ok().filter(|n| *n >= 0.0)? * (multiplier as f64);
+    Some(bytes as usize)
+}
 
-        let bytes = number_str.parse::<f64>().ok()? * (multiplier as f64);
-        Some(RamSpec::Bytes(bytes as usize))
+// Comma separated names of `kinds`
+fn test_names(kinds: &[TestKind]) -> String {
+    kinds.iter().map(|kind| kind.name()).collect::<Vec<_>>().join(", ")
+}
+
+// The tests of `entries` narrowed to `only` and without `skip`, logged as the test plan; None if
+// none of them runs. Tests the instruction set lacks are skipped with a warning.
+fn resolve_plan(entries: &[TestConfigEntry], isa: InstructionSet, only: &[TestKind], skip: &[TestKind]) -> Option<Vec<TestDefinition>> {
+    let unsupported = unsupported_tests(entries.iter().map(|entry| entry.kind), isa);
+    if !unsupported.is_empty() {
+        warn!("not available with the {:?} instruction set, skipping: {}", isa, test_names(&unsupported));
+    }
+    let tests = select_tests(build_tests_from_config(entries, isa), isa, only, skip);
+    if tests.iter().all(|test| test.loops == 0) {
+        return None;
+    }
+    let plan = describe_plan(&tests, isa);
+    info!("Test Plan         : {} tests", plan.len());
+    for line in plan {
+        info!("  {}", line);
     }
+    Some(tests)
 }
 
-// Placeholder for memory allocation and test loop
-pub fn run_tests(ram_bytes: usize, hide_serials: bool, stop_signal: &AtomicBool) {
+/// Load the config file at `path`, or else the first manganese.conf found; the defaults without
+/// one. RunError::Config if the file can't be read or is invalid.
+pub fn load_config(path: Option<&str>) -> Result<CustomConfig, RunError> {
+    let path = match path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => find_config(),
+    };
+    match &path {
+        Some(path) => match load_custom_config(path) {
+            Ok(config) => {
+                info!("Config File       : {}", path.display());
+                Ok(config)
+            }
+            Err(e) => Err(RunError::Config(format!("invalid config file {}: {}", path.display(), e))),
+        },
+        None => {
+            let searched: Vec<String> = config_search_paths().iter().map(|path| path.display().to_string()).collect();
+            warn!("no config file found ({}), using defaults...", searched.join(", "));
+            Ok(CustomConfig::default())
+        }
+    }
+}
+
+// Lock and test `ram_bytes` of memory until `stop_signal` is set, then release the memory; the
+// sweeps wait while `pause` is set, the progress and measurements go to `progress` and `metrics`. Returns the report of the run, or the error it couldn't start
+// (or test) with; the memory is released either way.
+pub(crate) fn run_tests(ram_bytes: usize, options: RunOptions, stop_signal: &AtomicBool, pause: &Pause, progress: Arc<RunProgress>, metrics: Arc<Subscribers>) -> Result<RunReport, RunError> {
+    let RunOptions {
+        hide_serials, pinned_gpu, shuffle, scrub_on_exit, write_combining, self_test, hugepages, numa, allow_unlocked, cycle,
+        pin, threads, output_json, output_junit, limits, config, profile, only_tests, skip_tests, dump_on_error, dump_window,
+        checkpoint: state_file, resume, seed, benchmark, l3_domains: by_domain, cross_domains, prefetch,
+    } = options;
+    #[cfg(not(feature = "pinned-gpu"))]
+    if pinned_gpu {
+        return Err(RunError::Config("pinned GPU memory support not compiled in; rebuild with --features pinned-gpu".to_string()));
+    }
+
     let sys = sysinfo();
     let mut smbios_info = hardware::collect_system_info();
     smbios_info.hide_serials = hide_serials;
 
-    let cpu_count = hardware_cpu_count();
+    // pinning has to set up the global thread pool before anything else starts it
+    let threads = threads.or(config.threads);
+    let prefetch = prefetch.or(config.prefetch).unwrap_or_default();
+    let pinned = pin.and_then(|affinity| pin_threads(affinity, threads));
+    let cpu_count = match (&pinned, threads) {
+        (Some(cpus), _) => cpus.len(),
+        (None, Some(threads)) => match rayon::ThreadPoolBuilder::new().num_threads(threads).build_global() {
+            Ok(()) => threads,
+            Err(e) => {
+                warn!("can't start a thread pool of {} threads: {}", threads, e);
+                hardware_cpu_count()
+            }
+        },
+        (None, None) => hardware_cpu_count(),
+    };
     let ram_speed = hardware_ram_speed(true);
     let actual_ram_speed = hardware_ram_speed(false);
     let isa = hardware_instruction_set();
 
     if matches!(isa, InstructionSet::SSE) {
-        error!("AVX2 or AVX-512 not available, aborting");
-        std::process::exit(-1);
+        warn!("AVX2, AVX-512 or NEON not available, falling back to scalar tests (expect greatly reduced bandwidth)");
     }
 
     info!("Hardware information:\n{}", smbios_info);
     info!("Available Threads : {}", cpu_count);
+    if let Some(cpus) = &pinned {
+        info!("Pinned Threads    : {} on CPUs {}", cpus.len(), format_cpulist(cpus));
+    }
     if ram_speed > 0 {
         if actual_ram_speed > 0 && actual_ram_speed != ram_speed {
             info!("Memory Bandwidth  : {}MB/s (maximum, theoretical)",
@@ -87,116 +374,540 @@ pub fn run_tests(ram_bytes: usize, hide_serials: bool, stop_signal: &AtomicBool)
     }
 
     let alignment = cpu_count * getpagesize();
-    let ram_bytes = ram_bytes - (ram_bytes % alignment);
 
-    const BACKOFF: usize = 256 * 1024 * 1024;
-    let mut mem: Option<*mut u8> = None;
-    let mut size = 0;
+    // lift the lock limit ourselves where we may, rather than fail and send the user off to do it
+    let lockable = ram_bytes + if write_combining { WRITE_COMBINING_MAX } else { 0 };
+    let memlock_limit = raise_memlock_limit(lockable);
+    match memlock_limit {
+        None => info!("Memlock Limit     : unlimited"),
+        Some(limit) if limit >= lockable => info!("Memlock Limit     : {}MiB", limit / (1024 * 1024)),
+        Some(limit) => warn!("Memlock Limit     : {}MiB, below the requested {}MiB; run as root (or with CAP_IPC_LOCK), raise `ulimit -l` or grant SeLockMemoryPrivilege",
+            limit / (1024 * 1024), lockable / (1024 * 1024)),
+    }
 
-    for i in 0..=(ram_bytes / BACKOFF) {
-        if stop_signal.load(Ordering::SeqCst) {
-            break;
+    let kinds = |names: &[String]| -> Result<Vec<TestKind>, RunError> {
+        names.iter()
+            .map(|name| TestKind::parse(name).ok_or_else(|| RunError::Config(format!("unknown test '{}' (see --list-tests)", name))))
+            .collect()
+    };
+    let entries = match profile {
+        Some(profile) => {
+            info!("Test Profile      : {:?}", profile);
+            profile.entries(isa)
         }
-        let alloc_size = ram_bytes - i * BACKOFF;
-        if alloc_size == 0 {
-            break;
+        None => config.entries,
+    };
+    let only = kinds(&only_tests)?;
+    let unsupported = unsupported_tests(only.iter().copied(), isa);
+    if !unsupported.is_empty() {
+        return Err(RunError::Config(format!("not available with the {:?} instruction set: {}", isa, test_names(&unsupported))));
+    }
+    let skip = kinds(&skip_tests)?;
+    let Some(mut test_config) = resolve_plan(&entries, isa, &only, &skip) else {
+        return Err(RunError::Config("no tests selected".to_string()));
+    };
+    // a profile replaces the tests of the config file, edits of them don't matter
+    let mut config_watch = config.path.as_deref().filter(|_| profile.is_none()).map(ConfigWatch::new);
+    let plan_name = config.plan.clone();
+    let limits = limits.or(config.limits);
+    limits.log();
+    if let Some(path) = dump_on_error.as_deref() {
+        info!("Error Dump        : {} bytes around the first miscompare to {}", dump_window, path);
+    }
+    if numa && by_domain {
+        return Err(RunError::Config("NUMA regions and L3 domain regions can't be combined".to_string()));
+    }
+    if benchmark && (resume || cycle) {
+        return Err(RunError::Config("a benchmark can't cycle or be resumed".to_string()));
+    }
+    // a benchmark has nothing to resume
+    let state_file = state_file.filter(|_| !benchmark);
+    let resumed = match (&state_file, resume) {
+        (Some(path), true) => Checkpoint::load(path).map_err(RunError::Config)?,
+        (None, true) => return Err(RunError::Config("resuming needs a state file".to_string())),
+        _ => None,
+    };
+    match &resumed {
+        Some(resumed) => {
+            info!("Resuming the run of {} after {} passes and {} tests of pass {}, {} errors so far",
+                state_file.as_deref().unwrap_or_default(), resumed.passes, resumed.tests_done.len(), resumed.passes + 1, resumed.errors);
         }
+        None if resume => warn!("no state file {}, starting a new run", state_file.as_deref().unwrap_or_default()),
+        None => {}
+    }
+    let run = tests_init(cpu_count, smbios_info.populated_channels(), stop_signal, pause, isa, resumed.as_ref().map(|resumed| resumed.seed).or(seed))
+        .observed_by(progress, metrics);
+    if let Some(path) = dump_on_error.as_deref() {
+        run.dump.arm(path, dump_window);
+    }
+    info!("Random Seed       : 0x{:016x}", run.seed);
+    if let Some(resumed) = &resumed {
+        run.errors.store(resumed.errors, Ordering::Relaxed);
+        run.set_flip_totals(resumed.flips);
+    }
+    let mut context = TestContext::new(&run);
+    let mut checkpoint = resumed.or_else(|| state_file.as_deref().map(|path| Checkpoint::new(path, run.seed)));
+    let mut report = ReportBuilder::new(output_json.as_deref(), output_junit.as_deref(), &smbios_info, isa, cpu_count, sys.totalram, &test_config);
+    report.seed(run.seed);
+    // a fixed distance from the start, auto once the memory to calibrate on is locked
+    match prefetch {
+        Prefetch::Distance(distance) => info!("Prefetch Distance : {} bytes", distance),
+        Prefetch::Off => info!("Prefetch Distance : off"),
+        Prefetch::Auto if cycle => info!("Prefetch Distance : off (not calibrated when cycling)"),
+        Prefetch::Auto => {}
+    }
+    run.set_prefetch_distance(match prefetch {
+        Prefetch::Distance(distance) => distance,
+        Prefetch::Off | Prefetch::Auto => 0,
+    });
 
-        unsafe {
-            //error!("Trying to alloc memory: {}", alloc_size);
-            let ptr = aligned_alloc(alignment, alloc_size);
-            if ptr.is_null() {
-                continue;
-            }
+    // borrows the free memory cycle by cycle instead of locking ram_bytes up front
+    if cycle {
+        let (errors, cycles) = run_cycles(&test_config, alignment, limits, &mut report, &mut context);
+        return Ok(report.finish(cycles, errors, run.flip_totals(), 0, 0, true));
+    }
 
-            if mlock(ptr, alloc_size) == 0 {
-                info!(
-                    "Locked Memory     : {}MiB of {}MiB ({:.0}%)",
-                    alloc_size / (1024 * 1024),
-                    sys.totalram / (1024 * 1024),
-                    100.0 * alloc_size as f64 / sys.totalram as f64
-                );
-                info!("Chunk Alignment   : {}K", alignment / 1024);
-                match isa {
-                    InstructionSet::AVX512 => info!("Instruction Set   : AVX-512"),
-                    InstructionSet::AVX2 => {
-                        if hardware_is_needlessly_disabled() {
-                            info!("Instruction Set   : AVX2 (lol)");
-                        } else {
-                            info!("Instruction Set   : AVX2");
-                        }
+    let lock_options = LockOptions { alignment, hugepages, allow_unlocked, node: None };
+    let nodes = if numa { numa_nodes() } else { Vec::new() };
+    if numa && nodes.len() < 2 {
+        warn!("NUMA Mode         : {} node(s) with CPUs and memory found, testing a single region", nodes.len());
+    }
+    let domains = if by_domain { l3_domains() } else { Vec::new() };
+    if by_domain && domains.len() < 2 {
+        warn!("L3 Domains        : {} L3 cache domain(s) found, testing a single region", domains.len());
+    }
+    let mut regions: Vec<Region> = if nodes.len() > 1 {
+        info!("NUMA Mode         : {} nodes, one region and pinned thread pool per node", nodes.len());
+        let node_bytes = ram_bytes / nodes.len() / alignment * alignment;
+        nodes.into_iter()
+            .flat_map(|node| {
+                let pool = match node.thread_pool() {
+                    Ok(pool) => Arc::new(pool),
+                    Err(e) => {
+                        error!("can't start the thread pool of NUMA node {}: {}", node.id, e);
+                        return Vec::new();
                     }
-                    _ => {}
-                }
+                };
+                // locking faults the pages in, from the node's own CPUs
+                let locked = pool.install(|| unsafe {
+                    lock_regions(node_bytes, LockOptions { node: Some(&node), ..lock_options }, memlock_limit, sys.totalram, stop_signal)
+                });
+                locked.into_iter()
+                    .map(|allocation| Region::new(allocation, Some(node.clone()), Some(pool.clone())))
+                    .collect()
+            })
+            .collect()
+    } else if domains.len() > 1 {
+        info!("L3 Domains        : {} domains ({}), one region and pinned thread pool per domain{}", domains.len(),
+            domains.iter().map(|cpus| format_cpulist(cpus)).collect::<Vec<_>>().join(" / "),
+            if cross_domains { ", each tested from the next domain's CPUs" } else { "" });
+        let domain_bytes = ram_bytes / domains.len() / alignment * alignment;
+        (0..domains.len())
+            .flat_map(|domain| {
+                let cpus = &domains[if cross_domains { (domain + 1) % domains.len() } else { domain }];
+                let pool = match domain_thread_pool(cpus) {
+                    Ok(pool) => Arc::new(pool),
+                    Err(e) => {
+                        error!("can't start the thread pool of L3 domain {}: {}", domain, e);
+                        return Vec::new();
+                    }
+                };
+                let locked = pool.install(|| unsafe {
+                    lock_regions(domain_bytes, lock_options, memlock_limit, sys.totalram, stop_signal)
+                });
+                locked.into_iter()
+                    .map(|allocation| {
+                        let mut region = Region::new(allocation, None, Some(pool.clone()));
+                        region.domain = Some(domain);
+                        region
+                    })
+                    .collect()
+            })
+            .collect()
+    } else {
+        unsafe { lock_regions(ram_bytes, lock_options, memlock_limit, sys.totalram, stop_signal) }
+            .into_iter()
+            .map(|allocation| Region::new(allocation, None, None))
+            .collect()
+    };
+    for (index, region) in regions.iter_mut().enumerate() {
+        region.index = index;
+    }
 
-                mem = Some(ptr);
-                size = alloc_size;
-                break;
+    if regions.is_empty() {
+        return Err(RunError::Allocation("can't lock any memory; try increasing memlock ulimit or running as root".to_string()));
+    }
+
+    let size: usize = regions.iter().map(|r| r.size).sum();
+    let unlocked: usize = regions.iter().filter(|r| !r.locked).map(|r| r.size).sum();
+    if unlocked > 0 {
+        error!("\x1b[1;91mMemory is NOT locked ({}MiB of {}MiB), pages may be swapped out during the test!\x1b[0m",
+            unlocked / (1024 * 1024), size / (1024 * 1024));
+    }
+    if regions.len() > 1 {
+        info!("Locked Memory     : {}MiB in {} regions, {:.0}% of the requested {}MiB",
+            size / (1024 * 1024), regions.len(), 100.0 * size as f64 / ram_bytes as f64, ram_bytes / (1024 * 1024));
+    }
+    info!("Chunk Alignment   : {}K", alignment / 1024);
+    match isa {
+        InstructionSet::AVX512 => info!("Instruction Set   : AVX-512"),
+        InstructionSet::AVX2 => {
+            if hardware_is_needlessly_disabled() {
+                info!("Instruction Set   : AVX2 (lol)");
             } else {
-                error!("Failed to mlock memory, try root (linux) or granting SeLockMemoryPrivilege (windows)!");
-                aligned_free(ptr);
+                info!("Instruction Set   : AVX2");
             }
         }
+        InstructionSet::NEON => info!("Instruction Set   : NEON"),
+        InstructionSet::SSE => info!("Instruction Set   : scalar (fallback)"),
     }
 
-    if mem.is_none() {
-        error!("can't lock any memory; try increasing memlock ulimit or running as root");
-        std::process::exit(-1);
+    // measures instead of testing, on the first region
+    if benchmark {
+        let results = regions[0].run(|mem, size| unsafe { run_benchmark(mem, size, cpu_count, run.seed, stop_signal) });
+        report.tested_bytes(size);
+        report.benchmark(results);
+        return Ok(report.finish(0, 0, (0, 0), 0, 0, unlocked == 0));
     }
 
-    let mem_ptr = mem.unwrap();
-    let entries = load_custom_config("manganese.conf").unwrap_or_else(|_| {
-        warn!("config file manganese.conf not found! using defaults...");
-        vec![]
-    });
-    let test_config = build_tests_from_config(&entries, isa);
-    tests_init(cpu_count, &ERRORS, isa);
+    // keeps the regions registered with the CUDA runtime until the run ends, dropped before them
+    #[cfg(feature = "pinned-gpu")]
+    let pinned: Vec<gpu::PinnedRegion> = if pinned_gpu {
+        regions.iter()
+            .map(|r| match unsafe { gpu::PinnedRegion::register(r.mem, r.size) } {
+                Ok(region) => {
+                    info!("Pinned GPU Memory : mapped at device address 0x{:x}", region.device_ptr());
+                    Ok(region)
+                }
+                Err(e) => Err(RunError::Allocation(format!("can't register memory with the GPU: {}", e))),
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        Vec::new()
+    };
+
+    // a separate mapping, the page attributes of the locked region can't be changed in place
+    let wc_size = ((size / 8).min(WRITE_COMBINING_MAX) / alignment).max(1) * alignment;
+    let wc_region = if write_combining {
+        let ptr = unsafe { write_combining_alloc(alignment, wc_size) };
+        if ptr.is_null() {
+            return Err(RunError::Allocation("can't map write-combining memory; only Windows exposes WC mappings of RAM to userspace".to_string()));
+        }
+        let locked = unsafe { mlock(ptr, wc_size) } == 0;
+        let region = Region::new(Allocation { mem: ptr, size: wc_size, huge_page: None, locked }, None, None);
+        if !locked {
+            if !allow_unlocked {
+                return Err(RunError::Allocation("Failed to mlock write-combining memory".to_string()));
+            }
+            error!("\x1b[1;91mWrite-combining memory is NOT locked, pages may be swapped!\x1b[0m");
+        }
+        info!("Write-Combining   : {}MiB (USWC), streaming tests rerun after every loop", wc_size / (1024 * 1024));
+        Some(region)
+    } else {
+        None
+    };
+    let wc_mem = wc_region.as_ref().map(|region| region.mem);
+
+    run.set_shuffle(shuffle);
+    if shuffle {
+        info!("Shuffle Mode      : random test order and chunk assignment");
+    }
+    for region in &regions {
+        let chunk_size = region.size / cpu_count / vector_width(isa) * vector_width(isa);
+        let label = if regions.len() > 1 { region.label() } else { String::new() };
+        info!("Test Coverage     : {} chunks of {}K + {} byte tail{} ({:.2}% of the requested memory)",
+            cpu_count, chunk_size / 1024, region.size - cpu_count * chunk_size, label, 100.0 * region.size as f64 / ram_bytes as f64);
+    }
+
+    // check that the detection path works before trusting what the run reports
+    if self_test {
+        let definitions = get_test_definitions_for_isa(isa);
+        let test = &definitions[&TestKind::BasicTests];
+        tests_configure(test, &mut context);
+        let passed = regions[0].run(|mem, size| unsafe { run_self_test(test, mem, size, cpu_count, vector_width(isa), &context) });
+        run.records.discard();
+        if !passed {
+            return Err(RunError::SelfTest);
+        }
+    }
+    if prefetch == Prefetch::Auto {
+        regions[0].run(|mem, size| unsafe { calibrate_prefetch(isa, mem, size, &context) });
+    }
+
+    // PFNs are only visible to root, check once instead of failing on every error
+    let physical_addresses = physical_address(regions[0].mem as usize).is_some();
+    if physical_addresses {
+        info!("Physical Address  : resolved via /proc/self/pagemap");
+        if smbios_info.mapped_ranges.is_empty() {
+            info!("DIMM Mapping      : unavailable (no SMBIOS Type 20 ranges)");
+        } else {
+            info!("DIMM Mapping      : {} SMBIOS Type 20 ranges", smbios_info.mapped_ranges.len());
+        }
+    } else {
+        info!("Physical Address  : unavailable (needs root and /proc/self/pagemap)");
+    }
+
+    let edac = edac_snapshot();
+    match &edac {
+        Some(snapshot) => info!("ECC Monitoring    : EDAC, {} DIMMs", snapshot.dimms.len()),
+        None => info!("ECC Monitoring    : unavailable (no EDAC counters)"),
+    }
+    let mut ecc_corrected = 0u64;
+    let mut ecc_uncorrected = 0u64;
+
     info!("Testing {:.2}MiB bytes of RAM...", ram_bytes as f64 / (1024. * 1024.));
     let start = Instant::now();
+    // a limit reached mid-test ends the test early, the checks between tests end the run
+    run.set_limits(limits.max_errors, limits.deadline(start));
+    let mut region_errors = vec![0u64; regions.len()];
+    let mut bad_pages = BadPages::default();
+    let mut dimm_errors = DimmErrors::new(&test_config);
+    // the write-combining region goes after the regular ones
+    let mut fault_history = FaultHistory::default();
+    let mut loops = checkpoint.as_ref().map_or(0, |checkpoint| checkpoint.passes);
+    // tests the interrupted pass of a resumed run already did
+    let mut tests_done = checkpoint.as_ref().map_or(Vec::new(), |checkpoint| checkpoint.tests_done.clone());
+    let mut aborted = false;
+    let mut time_up = false;
     loop {
+        // edits of a multi-day run's plan apply from the next pass on
+        if let Some(path) = config_watch.as_mut().and_then(|watch| watch.changed().then(|| watch.path.clone())) {
+            info!("Config file {} changed, reloading the test plan", path.display());
+            let reloaded = load_custom_config(&path).and_then(|config| match &plan_name {
+                Some(name) => config.use_plan(name).map_err(Into::into),
+                None => Ok(config),
+            });
+            match reloaded {
+                Ok(config) => match resolve_plan(&config.entries, isa, &only, &skip) {
+                    Some(tests) => test_config = tests,
+                    None => warn!("no tests selected, keeping the current test plan"),
+                },
+                Err(e) => warn!("invalid config file {}: {}, keeping the current test plan", path.display(), e),
+            }
+        }
         let loop_start = Instant::now();
+        let mut pass_records = Vec::new();
+        let mut pass_dropped = 0;
         let mut test_start: Instant;
-        for test in &test_config {
+        let mut test_order: Vec<&TestDefinition> = test_config.iter().collect();
+        if shuffle {
+            run.shuffle(&mut test_order);
+        }
+        run.progress.start_pass(loops, &test_order, size);
+        if let Some(eta) = run.progress.progress().and_then(|p| p.pass_eta) {
+            info!("Pass {} will take about {}", loops + 1, format_duration(eta));
+        }
+        for (index, test) in test_order.iter().copied().enumerate() {
             // check if we should stop before starting the next test
             if stop_signal.load(Ordering::SeqCst) {
                 break;
             }
-            if test.loops > 1 {
-                info!("Running: {} ({}x)", test.name, test.loops);
-            } else if test.loops == 0 {
+            if limits.time_reached(start) {
+                time_up = true;
+                break;
+            }
+            if tests_done.iter().any(|done| done == test.name) {
+                info!("Skipping: {} (done before the run was interrupted)", test.name);
+                continue;
+            }
+            if test.loops == 0 {
                 info!("Skipping: {}", test.name);
+            } else if let Some(budget) = limits.test_budget {
+                info!("Running: {} (for about {})", test.name, format_duration(budget));
+            } else if test.loops > 1 {
+                info!("Running: {} ({}x)", test.name, test.loops);
             } else {
                 info!("Running: {}", test.name);
             }
+            if test.options.stride > 1 {
+                info!("... sparse pass: 1 in {} cache lines of {}", test.options.stride, test.name);
+            }
+            if test.options.partition == Partition::Interleaved {
+                info!("... interleaved partition: {} threads taking turns by cache line in {}", run.test_threads(test), test.name);
+            }
+            if test.options.phases == Phases::Overlapped {
+                info!("... overlapped phases: both halves of the region tested at once, one sweep apart, in {}", test.name);
+            }
 
+            tests_configure(test, &mut context);
+            run.progress.start_test(index, test, test.loops * regions.len());
+            run.metrics.send_event(TestEvent::TestStarted { test: test.name, pass: loops + 1 });
+            let mut first_errors: Vec<Option<usize>> = vec![None; regions.len()];
+            let mut region_seconds = vec![0f64; regions.len()];
+            let edac_before = edac.as_ref().and_then(|_| edac_snapshot());
             test_start = Instant::now();
+            let test_errors = run.errors.load(Ordering::Relaxed);
             let mut bandwidth: f64;
-            for i in 1..(test.loops+1) {
+            // the loops of the plan, or as many as fit the test budget once the first one is timed
+            let mut test_loops = test.loops;
+            let mut i = 0;
+            while i < test_loops {
+                i += 1;
                 if stop_signal.load(Ordering::SeqCst) {
                     break;
                 }
-                unsafe {
-                    (test.run)(mem_ptr, size);
+                run.progress.start_loop(i);
+                let test_loop_start = Instant::now();
+                for (r, region) in regions.iter().enumerate() {
+                    run.take_first_error();
+                    run.take_error_offsets();
+                    let errors = run.errors.load(Ordering::Relaxed);
+                    run.progress.start_run();
+                    let region_start = Instant::now();
+                    region.run(|mem, size| unsafe {
+                        run.dump.buffer(mem, size);
+                        run_test(test, mem, size, &context)
+                    });
+                    region_seconds[r] += region_start.elapsed().as_secs_f64();
+                    run.progress.end_run();
+                    region_errors[r] += run.errors.load(Ordering::Relaxed) - errors;
+                    let (records, dropped) = run.records.take(test.name, loops + 1);
+                    fault_history.add(r, &records);
+                    run.metrics.send_errors(region.mem, &records);
+                    if physical_addresses {
+                        bad_pages.add_records(region.mem, &records);
+                        dimm_errors.add_records(region.mem, &records, dropped, &smbios_info);
+                    }
+                    report.records(&records, dropped);
+                    pass_records.extend(records);
+                    pass_dropped += dropped;
+                    if let Some(offset) = run.take_first_error() {
+                        first_errors[r].get_or_insert(offset);
+                    }
+                    if physical_addresses {
+                        report_physical(test, region, &run.take_error_offsets(), &smbios_info);
+                    }
+                }
+                // a stopped loop's bandwidth is meaningless
+                if !stop_signal.load(Ordering::SeqCst) {
+                    let bandwidth = (test.passes * test.iters) as f64 * (size as f64 / (1000. * 1000.)) / test_loop_start.elapsed().as_secs_f64();
+                    run.metrics.send_metric(Metric {
+                        elapsed: start.elapsed().as_secs_f64(),
+                        test: test.name,
+                        bandwidth,
+                        errors: run.errors.load(Ordering::Relaxed),
+                    });
+                    run.metrics.send_event(TestEvent::LoopCompleted { test: test.name, test_loop: i, bandwidth });
+                }
+                if i == 1 && limits.test_budget.is_some() && !stop_signal.load(Ordering::SeqCst) {
+                    test_loops = limits.budget_loops(test.loops, test_loop_start.elapsed());
+                    run.progress.scale_test(test_loops, test_loops * regions.len());
+                    info!("... {} loops of {:.1}s in the test budget", test_loops, test_loop_start.elapsed().as_secs_f64());
                 }
-                if i < test.loops {
+                if i < test_loops {
                     bandwidth = (test.passes * test.iters * i) as f64 * (size as f64 / (1000. * 1000.)) / test_start.elapsed().as_secs_f64();
-                    info!("... {} ({}/{}) [avg. BW {:.0}MB/s] ...",
+                    let eta = run.progress.progress().and_then(|p| Some(format!(", {} left, {} in the pass",
+                        format_duration(p.test_eta?), format_duration(p.pass_eta?)))).unwrap_or_default();
+                    info!("... {} ({}/{}) [avg. BW {:.0}MB/s{}] ...",
                         test.name,
-                        i, test.loops,
-                        bandwidth);
+                        i, test_loops,
+                        bandwidth, eta);
                 }
             }
-            bandwidth = (test.passes * test.iters * test.loops) as f64 * (size as f64 / (1000. * 1000.)) / test_start.elapsed().as_secs_f64();
+            run.progress.end_test(!stop_signal.load(Ordering::SeqCst));
+            bandwidth = (test.passes * test.iters * test_loops) as f64 * (size as f64 / (1000. * 1000.)) / test_start.elapsed().as_secs_f64();
             info!("{} completed in {:.2} sec [avg. BW {:.0}MB/s]", test.name, test_start.elapsed().as_secs_f64(), bandwidth);
+            run.log_unlogged_miscompares(test.name);
+            // regions are tested one after the other, so each one's bandwidth is its own
+            if regions.len() > 1 && !stop_signal.load(Ordering::SeqCst) {
+                let by_region: Vec<String> = regions.iter().zip(&region_seconds)
+                    .map(|(region, seconds)| format!("{:.0}MB/s{}",
+                        (test.passes * test.iters * test_loops) as f64 * (region.size as f64 / (1000. * 1000.)) / seconds, region.label()))
+                    .collect();
+                info!("... {} bandwidth: {}", test.name, by_region.join(", "));
+            }
+            report.test_result(test.name, loops + 1, test_start.elapsed().as_secs_f64(), bandwidth, run.errors.load(Ordering::Relaxed) - test_errors);
+            if let Some(checkpoint) = checkpoint.as_mut().filter(|_| !stop_signal.load(Ordering::SeqCst)) {
+                checkpoint.test_done(test.name, &run);
+            }
+
+            // attribute ECC events logged by the kernel during this test
+            if let (Some(before), Some(after)) = (&edac_before, edac_snapshot()) {
+                for dimm in after.since(before) {
+                    if dimm.uncorrected > 0 {
+                        error!("{}: {} corrected, {} uncorrected ECC errors on {}", test.name, dimm.corrected, dimm.uncorrected, dimm.label);
+                    } else {
+                        warn!("{}: {} corrected ECC errors on {}", test.name, dimm.corrected, dimm.label);
+                    }
+                    ecc_corrected += dimm.corrected;
+                    ecc_uncorrected += dimm.uncorrected;
+                }
+            }
+
+            // narrow down new miscompares; retention and thermal tests would take hours to bisect, and
+            // a run that is ending wouldn't let the bisection finish
+            if test.options.sleep == 0 && test.options.heat == 0 && !run.stop_requested() {
+                for (region, offset) in regions.iter().zip(first_errors) {
+                    if let Some(offset) = offset {
+                        region.run(|mem, size| unsafe { isolate_fault(test, mem, size, offset, &context) });
+                    }
+                }
+            }
+
+            if limits.errors_reached(run.errors.load(Ordering::Relaxed)) {
+                aborted = true;
+                break;
+            }
+            // the test was cut short at the deadline, the pass isn't complete
+            if limits.time_reached(start) {
+                time_up = true;
+                break;
+            }
         }
 
-        let errors = ERRORS.load(Ordering::Relaxed);
+        // WC stores drain through the fill buffers as full-line bursts, reads go uncached
+        if let Some(wc_ptr) = wc_mem {
+            for test in test_config.iter().filter(|t| t.loops > 0 && WRITE_COMBINING_TESTS.contains(&t.name)) {
+                if stop_signal.load(Ordering::SeqCst) {
+                    break;
+                }
+                if limits.errors_reached(run.errors.load(Ordering::Relaxed)) {
+                    aborted = true;
+                    break;
+                }
+                if limits.time_reached(start) {
+                    time_up = true;
+                    break;
+                }
+                info!("Running: {} (write-combining)", test.name);
+                tests_configure(test, &mut context);
+                test_start = Instant::now();
+                let test_errors = run.errors.load(Ordering::Relaxed);
+                run.dump.buffer(wc_ptr, wc_size);
+                unsafe {
+                    run_test(test, wc_ptr, wc_size, &context);
+                }
+                let (records, dropped) = run.records.take(test.name, loops + 1);
+                fault_history.add(regions.len(), &records);
+                run.metrics.send_errors(wc_ptr, &records);
+                report.records(&records, dropped);
+                pass_records.extend(records);
+                pass_dropped += dropped;
+                let bandwidth = (test.passes * test.iters) as f64 * (wc_size as f64 / (1000. * 1000.)) / test_start.elapsed().as_secs_f64();
+                info!("{} (write-combining) completed in {:.2} sec [avg. BW {:.0}MB/s]", test.name, test_start.elapsed().as_secs_f64(), bandwidth);
+                run.log_unlogged_miscompares(test.name);
+                report.test_result(&format!("{} (write-combining)", test.name), loops + 1, test_start.elapsed().as_secs_f64(), bandwidth, run.errors.load(Ordering::Relaxed) - test_errors);
+            }
+            // offsets are relative to the WC region, not something to isolate in the main one
+            run.take_first_error();
+        }
+
+        log_pass_summary(&format!("Pass {}", loops + 1), &pass_records, pass_dropped);
+        let errors = run.errors.load(Ordering::Relaxed);
         if errors > 0 {
             error!("\x1b[1;91m{} errors detected\x1b[0m", errors);
         }
+        for (region, errors) in regions.iter().zip(&region_errors) {
+            if regions.len() > 1 && *errors > 0 {
+                error!("\x1b[1;91m{} errors detected{}\x1b[0m", errors, region.label());
+            }
+        }
+        if ecc_corrected > 0 || ecc_uncorrected > 0 {
+            error!("\x1b[1;91m{} corrected, {} uncorrected ECC errors reported by EDAC\x1b[0m", ecc_corrected, ecc_uncorrected);
+        }
 
         // if we break in the loop, we need ot break the outer one too
-        if stop_signal.load(Ordering::SeqCst) {
+        if stop_signal.load(Ordering::SeqCst) || aborted || time_up {
             break;
         }
 
@@ -209,6 +920,321 @@ pub fn run_tests(ram_bytes: usize, hide_serials: bool, stop_signal: &AtomicBool)
 
         let bandwidth = (total_passes as f64 * (size as f64 / (1000.0 * 1000.0))) / total_time;
         info!("Tests completed in {:.2} sec [{:.0}MB/s]", total_time, bandwidth);
+        loops += 1;
+        tests_done.clear();
+        if let Some(checkpoint) = &mut checkpoint {
+            checkpoint.pass_done(loops, &run);
+        }
+        run.metrics.send_event(TestEvent::PassCompleted { pass: loops, errors });
+        if limits.passes.is_some_and(|passes| loops >= passes) {
+            info!("Pass limit of {} reached", loops);
+            break;
+        }
+        if limits.time_reached(start) {
+            time_up = true;
+            break;
+        }
+    }
+    if time_up {
+        info!("Time limit of {}s reached", limits.duration.unwrap_or_default().as_secs());
     }
     info!("Test stopped after {:.2}s", start.elapsed().as_secs_f64());
+    // a stopped run can go on with --resume
+    if let Some(checkpoint) = checkpoint.filter(|_| !stop_signal.load(Ordering::SeqCst)) {
+        checkpoint.remove();
+    }
+
+    let errors = run.errors.load(Ordering::Relaxed);
+    if aborted {
+        error!("\x1b[1;91mAborted due to error threshold: {} errors, limit {}\x1b[0m", errors, limits.max_errors.unwrap_or(0));
+    }
+    if errors > 0 || ecc_uncorrected > 0 {
+        let (flipped_bits, corrupted_lines) = run.flip_totals();
+        error!("\x1b[1;91mSummary: {} errors ({} flipped bits in {} cache lines), {} corrected and {} uncorrected ECC errors in {} complete loops over {}MiB\x1b[0m",
+            errors, flipped_bits, corrupted_lines, ecc_corrected, ecc_uncorrected, loops, size / (1024 * 1024));
+    } else {
+        info!("Summary: no errors ({} corrected ECC errors) in {} complete loops over {}MiB",
+            ecc_corrected, loops, size / (1024 * 1024));
+    }
+    let mut labels: Vec<String> = if regions.len() > 1 { regions.iter().map(Region::label).collect() } else { vec![String::new(); regions.len()] };
+    labels.push(" in the write-combining region".to_string());
+    fault_history.log(&labels);
+    dimm_errors.log(&smbios_info);
+    bad_pages.log();
+
+    if scrub_on_exit {
+        for region in &regions {
+            region.run(|mem, size| unsafe { scrub(mem, size, cpu_count) });
+        }
+        if let Some(wc_ptr) = wc_mem {
+            unsafe { scrub(wc_ptr, wc_size, cpu_count); }
+        }
+        info!("Scrubbed {}MiB of locked memory with zeros", (size + wc_mem.map_or(0, |_| wc_size)) / (1024 * 1024));
+    }
+
+    // the GPU mappings must go before the memory they map
+    #[cfg(feature = "pinned-gpu")]
+    drop(pinned);
+    drop(regions);
+    drop(wc_region);
+    info!("Released {}MiB of memory", (size + wc_mem.map_or(0, |_| wc_size)) / (1024 * 1024));
+
+    report.tested_bytes(size);
+    Ok(report.finish(loops, errors, run.flip_totals(), ecc_corrected, ecc_uncorrected, unlocked == 0))
+}
+
+pub(crate) fn exit_status(errors: u64, ecc_uncorrected: u64) -> i32 {
+    if errors > 0 || ecc_uncorrected > 0 { EXIT_ERRORS } else { EXIT_PASS }
+}
+
+// How lock_region allocates: chunk alignment, huge pages (--hugepages), the NUMA node to bind the
+// memory to and whether memory that can't be locked is kept anyway (--allow-unlocked)
+#[derive(Clone, Copy)]
+struct LockOptions<'a> {
+    alignment: usize,
+    hugepages: bool,
+    allow_unlocked: bool,
+    node: Option<&'a NumaNode>,
+}
+
+// Memory from lock_region; `locked` is only false with --allow-unlocked
+struct Allocation {
+    mem: *mut u8,
+    size: usize,
+    huge_page: Option<usize>,
+    locked: bool,
+}
+
+// handed back from the node's thread pool, nothing else touches it meanwhile
+unsafe impl Send for Allocation {}
+
+// A locked part of the tested memory; there are several if the requested size could only be
+// locked in pieces, and at least one per node with --numa
+struct Region {
+    index: usize,
+    mem: *mut u8,
+    size: usize,
+    huge_page: Option<usize>,
+    locked: bool,
+    node: Option<NumaNode>,
+    // L3 cache domain the region belongs to; with cross_domains the next domain's pool tests it
+    domain: Option<usize>,
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl Region {
+    fn new(allocation: Allocation, node: Option<NumaNode>, pool: Option<Arc<ThreadPool>>) -> Self {
+        let Allocation { mem, size, huge_page, locked } = allocation;
+        Region { index: 0, mem, size, huge_page, locked, node, domain: None, pool }
+    }
+
+    // Run `f` on the region, on the node's pinned thread pool if it belongs to one
+    fn run<R: Send>(&self, f: impl FnOnce(*mut u8, usize) -> R + Send) -> R {
+        let mem_usize = self.mem as usize;
+        let size = self.size;
+        match &self.pool {
+            Some(pool) => pool.install(move || f(mem_usize as *mut u8, size)),
+            None => f(self.mem, self.size),
+        }
+    }
+
+    fn label(&self) -> String {
+        match (&self.node, self.domain) {
+            (Some(node), _) => format!(" in region {} (NUMA node {})", self.index, node.id),
+            (None, Some(domain)) => format!(" in region {} (L3 domain {})", self.index, domain),
+            (None, None) => format!(" in region {}", self.index),
+        }
+    }
+}
+
+// Unlock and free the region's memory, also when a run gives up early
+impl Drop for Region {
+    fn drop(&mut self) {
+        unsafe {
+            if self.locked {
+                munlock(self.mem, self.size);
+            }
+            match self.huge_page {
+                Some(page) => hugepage_free(self.mem, self.size, page),
+                None => aligned_free(self.mem),
+            }
+        }
+    }
+}
+
+// Log the physical location of the miscompares `test` recorded at `offsets` of `region`, and the
+// DIMM(s) the SMBIOS Type 20 mappings place it on
+fn report_physical(test: &TestDefinition, region: &Region, offsets: &[usize], smbios_info: &hardware::SystemInfo) {
+    for &offset in offsets {
+        if let Some(phys) = physical_address(region.mem as usize + offset) {
+            let node = phys.node.map_or(String::new(), |node| format!(", NUMA node {}", node));
+            let dimms: Vec<String> = smbios_info.dimms_at(phys.address).iter()
+                .map(|m| match (&m.channel_name, m.slot_index) {
+                    (Some(channel), Some(slot)) => format!("{} ({}, Slot {})", m.locator, channel, slot),
+                    (Some(channel), None) => format!("{} ({})", m.locator, channel),
+                    _ => m.locator.clone(),
+                })
+                .collect();
+            let dimm = match dimms.len() {
+                0 => String::new(),
+                1 => format!(", error likely on {}", dimms[0]),
+                _ => format!(", error on one of {} (interleaved)", dimms.join(", ")),
+            };
+            error!("{}: offset 0x{:016x}{} is physical address 0x{:012x} (PFN 0x{:x}{}){}",
+                test.name, offset, region.label(), phys.address, phys.pfn, node, dimm);
+        }
+    }
+}
+
+// Why an attempt of lock_region failed
+enum AttemptFailure {
+    Allocation(std::io::Error),
+    LockLimit(usize),
+    Lock(std::io::Error),
+}
+
+impl std::fmt::Display for AttemptFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttemptFailure::Allocation(e) => write!(f, "allocation failed ({})", e),
+            AttemptFailure::LockLimit(limit) => write!(f, "above the memlock limit of {}MiB", limit / (1024 * 1024)),
+            AttemptFailure::Lock(e) => write!(f, "mlock failed ({})", e),
+        }
+    }
+}
+
+// Lock `bytes` in as few regions as possible: the largest that can be locked, then the largest
+// for the remainder and so on, for systems too fragmented for a single allocation.
+unsafe fn lock_regions(bytes: usize, options: LockOptions, memlock_limit: Option<usize>, total_ram: usize, stop_signal: &AtomicBool) -> Vec<Allocation> {
+    const MAX_REGIONS: usize = 64;
+    let alignment = options.alignment;
+    let mut locked = Vec::new();
+    let mut remaining = bytes;
+    let mut failures = Vec::new();
+
+    while remaining >= alignment && locked.len() < MAX_REGIONS {
+        // the limit covers everything the process has locked
+        let limit = memlock_limit.map(|limit| limit.saturating_sub(bytes - remaining));
+        if limit.is_some_and(|limit| limit < alignment) && !options.allow_unlocked {
+            break;
+        }
+        match lock_region(remaining, options, limit, total_ram, &mut failures, stop_signal) {
+            Some(allocation) => {
+                remaining -= allocation.size;
+                locked.push(allocation);
+            }
+            None => break,
+        }
+    }
+
+    if !failures.is_empty() {
+        let count = |f: fn(&AttemptFailure) -> bool| failures.iter().filter(|(_, failure)| f(failure)).count();
+        info!("Allocation Summary: requested {}MiB, got {}MiB in {} regions after {} attempts ({} allocation, {} memlock limit, {} mlock failures)",
+            bytes / (1024 * 1024), (bytes - remaining) / (1024 * 1024), locked.len(), locked.len() + failures.len(),
+            count(|f| matches!(f, AttemptFailure::Allocation(_))),
+            count(|f| matches!(f, AttemptFailure::LockLimit(_))),
+            count(|f| matches!(f, AttemptFailure::Lock(_))));
+    }
+    locked
+}
+
+// Allocate and lock up to `bytes` (bound to `node`, if any). Every failed attempt is logged
+// and recorded in `failures`, the next one is sized to the memlock limit or the free memory,
+// whichever is reached, else backs off by 256MiB, doubling on every further failure. The page
+// cache is dropped once when free memory runs short. With `allow_unlocked` the first allocation
+// is kept even if it can't be locked.
+unsafe fn lock_region(bytes: usize, options: LockOptions, memlock_limit: Option<usize>, total_ram: usize, failures: &mut Vec<(usize, AttemptFailure)>, stop_signal: &AtomicBool) -> Option<Allocation> {
+    let LockOptions { alignment, hugepages, allow_unlocked, node } = options;
+    const BACKOFF: usize = 256 * 1024 * 1024;
+    let mut backoff = BACKOFF;
+    let mut caches_dropped = false;
+    let mut alloc_size = bytes;
+
+    while alloc_size >= alignment && !stop_signal.load(Ordering::SeqCst) {
+        // huge pages only if they cover this size, before backing off any further
+        let huge_page = if hugepages { hugepage_alloc(alloc_size) } else { None };
+        let ptr = match huge_page {
+            Some((ptr, _)) => ptr,
+            None => aligned_alloc(alignment, alloc_size),
+        };
+
+        let failure = if ptr.is_null() {
+            AttemptFailure::Allocation(std::io::Error::last_os_error())
+        } else {
+            if let Some(node) = node {
+                if !bind_to_node(ptr, alloc_size, node) {
+                    warn!("can't bind memory to NUMA node {}, relying on first-touch placement", node.id);
+                }
+            }
+
+            let locked = mlock(ptr, alloc_size) == 0;
+            if locked || allow_unlocked {
+                info!(
+                    "{}: {}MiB of {}MiB ({:.0}%){}",
+                    if locked { "Locked Memory     " } else { "Unlocked Memory   " },
+                    alloc_size / (1024 * 1024),
+                    total_ram / (1024 * 1024),
+                    100.0 * alloc_size as f64 / total_ram as f64,
+                    node.map_or(String::new(), |node| format!(" on NUMA node {}", node.id))
+                );
+                match huge_page {
+                    Some((_, page)) if page >= 1 << 30 => info!("Page Size         : {}GiB huge pages", page >> 30),
+                    Some((_, page)) => info!("Page Size         : {}MiB huge pages", page >> 20),
+                    None if hugepages => warn!("Page Size         : {}K (no huge pages available)", getpagesize() / 1024),
+                    None => {}
+                }
+                return Some(Allocation { mem: ptr, size: alloc_size, huge_page: huge_page.map(|(_, page)| page), locked });
+            }
+
+            let error = std::io::Error::last_os_error();
+            match huge_page {
+                Some((_, page)) => hugepage_free(ptr, alloc_size, page),
+                None => aligned_free(ptr),
+            }
+            match memlock_limit {
+                Some(limit) if alloc_size > limit => AttemptFailure::LockLimit(limit),
+                _ => AttemptFailure::Lock(error),
+            }
+        };
+
+        let free = sysinfo().freeram;
+        warn!("Allocation attempt of {}MiB failed: {} ({}MiB free)", alloc_size / (1024 * 1024), failure, free / (1024 * 1024));
+
+        // reclaimable cache counts as free, but mlock can still fail while it's being reclaimed
+        let next = if !caches_dropped && free < alloc_size && drop_caches() {
+            caches_dropped = true;
+            info!("Dropped the page cache, {}MiB free now", sysinfo().freeram / (1024 * 1024));
+            alloc_size
+        } else {
+            let next = match failure {
+                AttemptFailure::LockLimit(limit) => limit,
+                // 0 where the free memory is unknown
+                _ if free > 0 && free < alloc_size => free,
+                _ => {
+                    backoff *= 2;
+                    alloc_size.saturating_sub(backoff / 2)
+                }
+            };
+            next.min(alloc_size - 1) / alignment * alignment
+        };
+        failures.push((alloc_size, failure));
+        alloc_size = next;
+    }
+    None
+}
+
+// Zero `mem[..size]` so neither test patterns nor prior contents of the region survive the run
+unsafe fn scrub(mem: *mut u8, size: usize, threads: usize) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size.div_ceil(threads);
+
+    (0..threads).into_par_iter().for_each(|i| {
+        let start = (i * chunk_size).min(size);
+        let end = (start + chunk_size).min(size);
+        let chunk = (mem_usize as *mut u8).add(start);
+        std::ptr::write_bytes(chunk, 0, end - start);
+        // the region is never read again, keep the stores from being treated as dead
+        std::hint::black_box(chunk);
+    });
 }
@@ -0,0 +1,123 @@
+// Measurements of a session's run for front ends to plot and list: after every loop of a test its
+// bandwidth and the error count so far, sent to the receiver of `TestSession::subscribe_metrics()`,
+// and every miscompare with the physical address of its word, sent to the receiver of
+// `subscribe_errors()`. The receiver of `subscribe_events()` gets all of it as one stream of
+// `TestEvent`s, in the order it happened, with the starts of the tests and the ends of the passes
+// in between. Without a receiver nothing is kept, or resolved.
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use crate::pagemap::physical_address;
+use crate::platform::getpagesize;
+use crate::records::ErrorRecord;
+
+/// One loop of a test.
+#[derive(Debug, Clone)]
+pub struct Metric {
+    /// Seconds since the tests started.
+    pub elapsed: f64,
+    pub test: &'static str,
+    /// Bandwidth of the loop in MB/s.
+    pub bandwidth: f64,
+    /// Errors of the run so far.
+    pub errors: u64,
+}
+
+/// One miscompare.
+#[derive(Debug, Clone)]
+pub struct ErrorDetail {
+    pub record: ErrorRecord,
+    /// Physical address of the word, None if it can't be resolved (not root, not Linux).
+    pub physical: Option<u64>,
+}
+
+/// Something that happened in the run, see `TestSession::subscribe_events()`.
+#[derive(Debug, Clone)]
+pub enum TestEvent {
+    /// `test` starts in pass `pass`, from 1.
+    TestStarted { test: &'static str, pass: usize },
+    /// Loop `test_loop` of `test` is done, at `bandwidth` MB/s.
+    LoopCompleted { test: &'static str, test_loop: usize, bandwidth: f64 },
+    /// A miscompare, with the physical address of its word if it can be resolved.
+    ErrorFound { record: ErrorRecord, physical: Option<u64> },
+    /// Pass `pass` is done, with `errors` errors of the run so far.
+    PassCompleted { pass: usize, errors: u64 },
+}
+
+struct Subscriber<T>(Mutex<Option<Sender<T>>>);
+
+impl<T> Default for Subscriber<T> {
+    fn default() -> Self {
+        Subscriber(Mutex::new(None))
+    }
+}
+
+impl<T> Subscriber<T> {
+    fn subscribe(&self) -> Receiver<T> {
+        let (sender, receiver) = channel();
+        *self.0.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    fn subscribed(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+
+    fn send(&self, items: impl IntoIterator<Item = T>) {
+        let mut subscriber = self.0.lock().unwrap();
+        let Some(sender) = subscriber.as_ref() else { return };
+        // a dropped receiver unsubscribes
+        if items.into_iter().any(|item| sender.send(item).is_err()) {
+            *subscriber = None;
+        }
+    }
+}
+
+/// The receivers of one session's measurements, see `TestSession::subscribe_metrics()`.
+#[derive(Default)]
+pub struct Subscribers {
+    metrics: Subscriber<Metric>,
+    errors: Subscriber<ErrorDetail>,
+    events: Subscriber<TestEvent>,
+}
+
+impl Subscribers {
+    /// Receive the metrics of the run from now on, in place of any earlier receiver.
+    pub fn subscribe_metrics(&self) -> Receiver<Metric> {
+        self.metrics.subscribe()
+    }
+
+    /// Receive the miscompares of the run from now on, in place of any earlier receiver.
+    pub fn subscribe_errors(&self) -> Receiver<ErrorDetail> {
+        self.errors.subscribe()
+    }
+
+    /// Receive the events of the run from now on, in place of any earlier receiver.
+    pub fn subscribe_events(&self) -> Receiver<TestEvent> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn send_event(&self, event: TestEvent) {
+        self.events.send([event]);
+    }
+
+    pub(crate) fn send_metric(&self, metric: Metric) {
+        self.metrics.send([metric]);
+    }
+
+    /// Send the `records` drained from the region at `mem`.
+    pub(crate) fn send_errors(&self, mem: *const u8, records: &[ErrorRecord]) {
+        if records.is_empty() || !(self.errors.subscribed() || self.events.subscribed()) {
+            return;
+        }
+        let page = getpagesize();
+        let mut pages: HashMap<usize, Option<u64>> = HashMap::new();
+        let details: Vec<ErrorDetail> = records.iter().map(|record| {
+            let virt = mem as usize + record.offset;
+            let base = *pages.entry(virt / page).or_insert_with(|| physical_address(virt / page * page).map(|phys| phys.address));
+            ErrorDetail { record: record.clone(), physical: base.map(|base| base + (virt % page) as u64) }
+        }).collect();
+        self.events.send(details.iter().map(|detail| TestEvent::ErrorFound { record: detail.record.clone(), physical: detail.physical }));
+        self.errors.send(details);
+    }
+}
@@ -0,0 +1,181 @@
+// NUMA support: on multi-socket (or sub-NUMA clustered) systems a single allocation tested by
+// the global thread pool mixes local and remote accesses arbitrarily. With `--numa` every node
+// gets its own region, bound to the node's memory and tested by a thread pool pinned to the
+// node's CPUs, so each node's DIMMs are stressed through their local memory controller and
+// miscompares can be attributed to a node.
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+#[derive(Debug, Clone)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+}
+
+impl NumaNode {
+    /// Thread pool with one worker per CPU of the node, every worker pinned to the node.
+    pub fn thread_pool(&self) -> Result<ThreadPool, rayon::ThreadPoolBuildError> {
+        let node = self.clone();
+        ThreadPoolBuilder::new()
+            .num_threads(self.cpus.len())
+            .thread_name(move |i| format!("node{}-{}", node.id, i))
+            .start_handler({
+                let node = self.clone();
+                move |_| pin_to_node(&node)
+            })
+            .build()
+    }
+}
+
+// "0-3,8-11" -> [0, 1, 2, 3, 8, 9, 10, 11]
+#[cfg(target_os = "linux")]
+pub(crate) fn parse_cpulist(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((first, last)) => Some((first.parse().ok()?..=last.parse().ok()?).collect::<Vec<usize>>()),
+            None => Some(vec![range.parse().ok()?]),
+        })
+        .flatten()
+        .collect()
+}
+
+/// NUMA nodes that have both CPUs and memory, in node order; empty if the topology is unknown.
+#[cfg(target_os = "linux")]
+pub fn numa_nodes() -> Vec<NumaNode> {
+    use glob::glob;
+    use std::fs;
+
+    let mut nodes: Vec<NumaNode> = glob("/sys/devices/system/node/node[0-9]*")
+        .map(|paths| paths.flatten()
+            .filter_map(|path| {
+                let id = path.file_name()?.to_str()?.strip_prefix("node")?.parse().ok()?;
+                let cpus = parse_cpulist(&fs::read_to_string(path.join("cpulist")).ok()?);
+                // "Node 0 MemTotal:       32768000 kB"
+                let mem_total: u64 = fs::read_to_string(path.join("meminfo")).ok()?
+                    .lines()
+                    .find(|line| line.contains("MemTotal:"))?
+                    .split_whitespace()
+                    .nth(3)?
+                    .parse()
+                    .ok()?;
+                (!cpus.is_empty() && mem_total > 0).then_some(NumaNode { id, cpus })
+            })
+            .collect())
+        .unwrap_or_default();
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+/// NUMA nodes that have both CPUs and memory, in node order; empty if the topology is unknown.
+/// Only the processors of the calling thread's processor group are seen.
+#[cfg(windows)]
+pub fn numa_nodes() -> Vec<NumaNode> {
+    use winapi::um::systemtopologyapi::GetNumaHighestNodeNumber;
+    use winapi::um::winbase::{GetNumaAvailableMemoryNodeEx, GetNumaNodeProcessorMask};
+
+    let mut highest = 0;
+    if unsafe { GetNumaHighestNodeNumber(&mut highest) } == 0 {
+        return Vec::new();
+    }
+    (0..=highest as u16)
+        .filter_map(|id| {
+            let mut mask = 0u64;
+            let mut available = 0u64;
+            unsafe {
+                if GetNumaNodeProcessorMask(id as u8, &mut mask) == 0 || GetNumaAvailableMemoryNodeEx(id, &mut available) == 0 {
+                    return None;
+                }
+            }
+            let cpus: Vec<usize> = (0..64).filter(|cpu| mask & (1 << cpu) != 0).collect();
+            (!cpus.is_empty() && available > 0).then_some(NumaNode { id: id as usize, cpus })
+        })
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn numa_nodes() -> Vec<NumaNode> {
+    Vec::new()
+}
+
+#[cfg(target_os = "linux")]
+fn pin_to_node(node: &NumaNode) {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in &node.cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        // best effort; an unpinned worker still tests the node's memory, just remotely
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(windows)]
+fn pin_to_node(node: &NumaNode) {
+    use winapi::um::processthreadsapi::GetCurrentThread;
+    use winapi::um::winbase::SetThreadAffinityMask;
+
+    let mask = node.cpus.iter().fold(0usize, |mask, cpu| mask | 1 << cpu);
+    unsafe { SetThreadAffinityMask(GetCurrentThread(), mask); }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn pin_to_node(_node: &NumaNode) {}
+
+/// Bind the not yet faulted pages of `mem[..size]` to `node`, so locking them from the node's
+/// threads can't silently fall back to another node's memory. Returns false if that failed.
+#[cfg(target_os = "linux")]
+pub unsafe fn bind_to_node(mem: *mut u8, size: usize, node: &NumaNode) -> bool {
+    const MPOL_BIND: libc::c_long = 2;
+    const MPOL_MF_MOVE: libc::c_long = 1 << 1;
+
+    let mut mask = vec![0u64; node.id / 64 + 1];
+    mask[node.id / 64] |= 1 << (node.id % 64);
+    // the allocation may start inside a page the allocator already touched
+    let page = crate::platform::getpagesize();
+    let start = mem as usize / page * page;
+    libc::syscall(
+        libc::SYS_mbind,
+        start,
+        size + (mem as usize - start),
+        MPOL_BIND,
+        mask.as_ptr(),
+        mask.len() * 64 + 1,
+        MPOL_MF_MOVE,
+    ) == 0
+}
+
+/// Elsewhere pages are placed on the node of the thread that first touches them (Windows'
+/// default policy), so locking the region from the node's pinned threads is all the binding.
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn bind_to_node(_mem: *mut u8, _size: usize, _node: &NumaNode) -> bool {
+    true
+}
+
+/// NUMA node holding the physical address `address`, from the node's memory block links.
+#[cfg(target_os = "linux")]
+pub fn physical_node(address: u64) -> Option<usize> {
+    use glob::glob;
+    use std::fs;
+
+    let block_size = fs::read_to_string("/sys/devices/system/memory/block_size_bytes").ok()?;
+    let block = address / u64::from_str_radix(block_size.trim(), 16).ok()?.max(1);
+    let path = glob(&format!("/sys/devices/system/node/node[0-9]*/memory{}", block)).ok()?.flatten().next()?;
+    path.parent()?.file_name()?.to_str()?.strip_prefix("node")?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn physical_node(_address: u64) -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::parse_cpulist;
+
+    #[test]
+    fn cpulist_ranges() {
+        assert_eq!(parse_cpulist("0-3,8-9,12\n"), vec![0, 1, 2, 3, 8, 9, 12]);
+        assert_eq!(parse_cpulist("\n"), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,74 @@
+// Physical address resolution through /proc/self/pagemap, which holds one 64-bit entry per virtual
+// page: bit 63 is set if the page is present, bits 0-54 are its page frame number. The kernel only
+// reveals PFNs to CAP_SYS_ADMIN (they read as zero otherwise), and the locked test memory never
+// moves, so the translation of a failing offset stays valid for the whole run.
+#[cfg(target_os = "linux")]
+use crate::numa::physical_node;
+#[cfg(target_os = "linux")]
+use crate::platform::getpagesize;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PhysicalAddress {
+    pub address: u64,
+    pub pfn: u64,
+    pub node: Option<usize>,
+}
+
+/// Physical address of the byte at virtual address `virt`, None if it can't be resolved.
+#[cfg(target_os = "linux")]
+pub fn physical_address(virt: usize) -> Option<PhysicalAddress> {
+    use std::fs::File;
+    use std::os::unix::fs::FileExt;
+
+    const PRESENT: u64 = 1 << 63;
+    const PFN_MASK: u64 = (1 << 55) - 1;
+
+    let page = getpagesize();
+    let mut entry = [0u8; 8];
+    File::open("/proc/self/pagemap").ok()?
+        .read_exact_at(&mut entry, (virt / page * 8) as u64)
+        .ok()?;
+    let entry = u64::from_ne_bytes(entry);
+    let pfn = entry & PFN_MASK;
+    if entry & PRESENT == 0 || pfn == 0 {
+        return None;
+    }
+
+    let address = pfn * page as u64 + (virt % page) as u64;
+    Some(PhysicalAddress { address, pfn, node: physical_node(address) })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn physical_address(_virt: usize) -> Option<PhysicalAddress> {
+    None
+}
+
+/// Page frame numbers of the present pages of `virt..virt + len`, empty if they can't be resolved.
+#[cfg(target_os = "linux")]
+pub fn page_frames(virt: usize, len: usize) -> Vec<u64> {
+    use std::fs::File;
+    use std::os::unix::fs::FileExt;
+
+    const PRESENT: u64 = 1 << 63;
+    const PFN_MASK: u64 = (1 << 55) - 1;
+
+    let page = getpagesize();
+    let first = virt / page;
+    let mut entries = vec![0u8; (virt + len).div_ceil(page).saturating_sub(first) * 8];
+    let Ok(pagemap) = File::open("/proc/self/pagemap") else {
+        return Vec::new();
+    };
+    if pagemap.read_exact_at(&mut entries, (first * 8) as u64).is_err() {
+        return Vec::new();
+    }
+    entries.chunks_exact(8)
+        .map(|entry| u64::from_ne_bytes(entry.try_into().unwrap()))
+        .filter(|entry| entry & PRESENT != 0 && entry & PFN_MASK != 0)
+        .map(|entry| entry & PFN_MASK)
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn page_frames(_virt: usize, _len: usize) -> Vec<u64> {
+    Vec::new()
+}
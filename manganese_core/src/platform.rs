@@ -1,7 +1,7 @@
 #[cfg(windows)]
 mod windows {
     use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, GetSystemInfo, MEMORYSTATUSEX, SYSTEM_INFO};
-    use winapi::um::memoryapi::VirtualLock;
+    use winapi::um::memoryapi::{VirtualLock, VirtualUnlock};
     use std::mem;
     use std::mem::zeroed;
 
@@ -68,22 +68,69 @@ mod windows {
             // memory locking worked
             0
         } else {
-            // memory locking failed
-            //FIXME: was -1; bypassed for now
-            0
+            // memory locking failed; --allow-unlocked proceeds anyway
+            -1
+        }
+    }
+
+    // the system file cache can only be trimmed with SeIncreaseQuotaPrivilege, leave it be
+    pub fn drop_caches() -> bool {
+        false
+    }
 
+    pub unsafe fn munlock(addr: *mut u8, len: usize) -> i32 {
+        if VirtualUnlock(addr as *mut _, len) != 0 { 0 } else { -1 }
+    }
+
+    /// Enable SeLockMemoryPrivilege (held by admins once granted in the local security policy,
+    /// but disabled in the token) and grow the working set, which caps what VirtualLock can lock,
+    /// to `bytes`. Returns how much can be locked now, None if there's no limit.
+    pub fn raise_memlock_limit(bytes: usize) -> Option<usize> {
+        use std::ptr::null_mut;
+        use winapi::um::handleapi::CloseHandle;
+        use winapi::um::processthreadsapi::{GetCurrentProcess, OpenProcessToken};
+        use winapi::um::securitybaseapi::AdjustTokenPrivileges;
+        use winapi::um::winbase::{GetProcessWorkingSetSize, LookupPrivilegeValueW, SetProcessWorkingSetSize};
+        use winapi::um::winnt::{SE_PRIVILEGE_ENABLED, TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY};
+
+        unsafe {
+            let mut token = null_mut();
+            if OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token) != 0 {
+                let name: Vec<u16> = "SeLockMemoryPrivilege\0".encode_utf16().collect();
+                let mut privileges: TOKEN_PRIVILEGES = zeroed();
+                privileges.PrivilegeCount = 1;
+                privileges.Privileges[0].Attributes = SE_PRIVILEGE_ENABLED;
+                if LookupPrivilegeValueW(null_mut(), name.as_ptr(), &mut privileges.Privileges[0].Luid) != 0 {
+                    // fails (ERROR_NOT_ALL_ASSIGNED) unless the account holds the privilege
+                    AdjustTokenPrivileges(token, 0, &mut privileges, 0, null_mut(), null_mut());
+                }
+                CloseHandle(token);
+            }
+
+            // leave room for the image and the thread stacks on top of the locked pages
+            let slack = 64 * 1024 * 1024;
+            let (mut minimum, mut maximum) = (0, 0);
+            let process = GetCurrentProcess();
+            if SetProcessWorkingSetSize(process, bytes + slack, bytes + 2 * slack) == 0 {
+                GetProcessWorkingSetSize(process, &mut minimum, &mut maximum);
+                return Some(minimum.saturating_sub(slack));
+            }
+            Some(bytes)
         }
     }
 
-    /// Allocate a contiguous memory block with at least `size` bytes and alignment `alignment`.
+    /// Allocate a contiguous memory block with at least `size` bytes and alignment `alignment`,
+    /// with inaccessible guard pages right before and after it.
     /// Returns a pointer to the aligned memory, or null on failure.
     pub unsafe fn aligned_alloc(alignment: usize, size: usize) -> *mut u8 {
         use std::ptr::null_mut;
-        use winapi::um::memoryapi::VirtualAlloc;
-        use winapi::um::winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_READWRITE};
+        use winapi::um::memoryapi::{VirtualAlloc, VirtualProtect};
+        use winapi::um::winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_NOACCESS, PAGE_READWRITE};
 
         // VirtualAlloc always returns page-aligned memory
-        let alloc_size = size + alignment; // over-allocate to allow manual alignment
+        let page = getpagesize();
+        let size = size.next_multiple_of(page);
+        let alloc_size = size + alignment + 2 * page; // over-allocate to allow manual alignment and the guards
 
         let raw_ptr = VirtualAlloc(
             null_mut(),
@@ -97,11 +144,68 @@ mod windows {
             return null_mut();
         }
 
-        // align manually
-        let aligned_ptr = ((raw_ptr + alignment - 1) & !(alignment - 1)) as *mut u8;
-        //error!("Trying to alloc memory (in aligned_alloc), aligned: {:?}", aligned_ptr);
+        // align manually, VirtualFree needs the base back
+        let aligned_ptr = super::align_up(raw_ptr + page, alignment);
+        super::ALLOCATIONS.insert(aligned_ptr, raw_ptr, alloc_size);
+
+        // everything around the block is a guard
+        let mut old = 0;
+        VirtualProtect(raw_ptr as *mut _, aligned_ptr - raw_ptr, PAGE_NOACCESS, &mut old);
+        VirtualProtect((aligned_ptr + size) as *mut _, raw_ptr + alloc_size - aligned_ptr - size, PAGE_NOACCESS, &mut old);
+
+        aligned_ptr as *mut u8
+    }
+
+    /// Allocate `size` bytes mapped write-combining (USWC), aligned to `alignment`.
+    /// Returns null if the mapping can't be created.
+    pub unsafe fn write_combining_alloc(alignment: usize, size: usize) -> *mut u8 {
+        use std::ptr::null_mut;
+        use winapi::um::memoryapi::VirtualAlloc;
+        use winapi::um::winnt::{MEM_COMMIT, MEM_RESERVE, PAGE_READWRITE, PAGE_WRITECOMBINE};
+
+        // the memory manager programs the PAT entry for these pages, MTRRs still cover RAM as WB
+        let raw_ptr = VirtualAlloc(
+            null_mut(),
+            size + alignment,
+            MEM_COMMIT | MEM_RESERVE,
+            PAGE_READWRITE | PAGE_WRITECOMBINE,
+        ) as usize;
+
+        if raw_ptr == 0 {
+            return null_mut();
+        }
+
+        let aligned_ptr = super::align_up(raw_ptr, alignment);
+        super::ALLOCATIONS.insert(aligned_ptr, raw_ptr, size + alignment);
+        aligned_ptr as *mut u8
+    }
+
+    /// Allocate at least `size` bytes backed by large pages (requires SeLockMemoryPrivilege).
+    /// Returns the allocation and its page size, or None if no large pages could be allocated.
+    pub unsafe fn hugepage_alloc(size: usize) -> Option<(*mut u8, usize)> {
+        use std::ptr::null_mut;
+        use winapi::um::memoryapi::{GetLargePageMinimum, VirtualAlloc};
+        use winapi::um::winnt::{MEM_COMMIT, MEM_LARGE_PAGES, MEM_RESERVE, PAGE_READWRITE};
+
+        let page = GetLargePageMinimum();
+        if page == 0 {
+            return None;
+        }
+        // large pages are always resident, no VirtualLock needed
+        let ptr = VirtualAlloc(
+            null_mut(),
+            size.next_multiple_of(page),
+            MEM_COMMIT | MEM_RESERVE | MEM_LARGE_PAGES,
+            PAGE_READWRITE,
+        ) as *mut u8;
+        (!ptr.is_null()).then_some((ptr, page))
+    }
+
+    pub unsafe fn hugepage_free(ptr: *mut u8, _size: usize, _page: usize) {
+        use winapi::um::memoryapi::VirtualFree;
+        use winapi::um::winnt::MEM_RELEASE;
 
-        aligned_ptr
+        VirtualFree(ptr as *mut _, 0, MEM_RELEASE);
     }
 
     pub unsafe fn aligned_free(ptr: *mut u8) {
@@ -109,11 +213,9 @@ mod windows {
         use winapi::um::winnt::MEM_RELEASE;
         
         if !ptr.is_null() {
-            // VirtualFree with MEM_RELEASE requires the base address
-            // For page-aligned allocations, we need to free the original address
-            // Since we can't track the original with this API, we'll use a simpler approach:
-            // Just allocate naturally aligned memory from VirtualAlloc
-            VirtualFree(ptr as *mut _, 0, MEM_RELEASE);
+            // VirtualFree with MEM_RELEASE requires the base address, not the aligned one
+            let (base, _) = super::ALLOCATIONS.remove(ptr as usize).unwrap_or((ptr as usize, 0));
+            VirtualFree(base as *mut _, 0, MEM_RELEASE);
         }
     }
 }
@@ -155,9 +257,105 @@ mod unix {
         }
     }
 
-    #[cfg(not(target_os = "linux"))]
+    /// Read the sysctl `name` as a `T` (an integer or a plain C struct).
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    pub(crate) fn sysctl_value<T: Copy>(name: &str) -> Option<T> {
+        let name = std::ffi::CString::new(name).ok()?;
+        let mut value = std::mem::MaybeUninit::<T>::zeroed();
+        let mut len = size_of::<T>();
+        let ret = unsafe { libc::sysctlbyname(name.as_ptr(), value.as_mut_ptr() as *mut _, &mut len, std::ptr::null_mut(), 0) };
+        (ret == 0 && len == size_of::<T>()).then(|| unsafe { value.assume_init() })
+    }
+
+    #[cfg(target_os = "macos")]
     pub fn sysinfo() -> SysInfo {
-        // Fallback for non-Linux Unix systems
+        let page = getpagesize();
+        // free plus inactive pages, which macOS reclaims on demand (its "available" memory)
+        let freeram = unsafe {
+            let mut stats: libc::vm_statistics64 = std::mem::zeroed();
+            let mut count = libc::HOST_VM_INFO64_COUNT;
+            #[allow(deprecated)]
+            let host = libc::mach_host_self();
+            if libc::host_statistics64(host, libc::HOST_VM_INFO64, &mut stats as *mut _ as libc::host_info64_t, &mut count) == libc::KERN_SUCCESS {
+                (stats.free_count as usize + stats.inactive_count as usize) * page
+            } else {
+                0
+            }
+        };
+        let swap = sysctl_value::<libc::xsw_usage>("vm.swapusage");
+
+        SysInfo {
+            totalram: sysctl_value::<u64>("hw.memsize").unwrap_or(0) as usize,
+            freeram,
+            sharedram: 0,
+            bufferram: 0,
+            totalswap: swap.map_or(0, |s| s.xsu_total as usize),
+            freeswap: swap.map_or(0, |s| s.xsu_avail as usize),
+            procs: num_cpus::get() as u16,
+            totalhigh: 0,
+            freehigh: 0,
+            mem_unit: 1,
+        }
+    }
+
+    #[cfg(target_os = "freebsd")]
+    pub fn sysinfo() -> SysInfo {
+        let page = getpagesize();
+        // free plus inactive pages, like the "available" memory of the other platforms
+        let pages = |name| sysctl_value::<u32>(name).unwrap_or(0) as usize * page;
+
+        SysInfo {
+            totalram: sysctl_value::<libc::c_ulong>("hw.physmem").unwrap_or(0) as usize,
+            freeram: pages("vm.stats.vm.v_free_count") + pages("vm.stats.vm.v_inactive_count"),
+            sharedram: 0,
+            bufferram: 0,
+            totalswap: 0,
+            freeswap: 0,
+            procs: num_cpus::get() as u16,
+            totalhigh: 0,
+            freehigh: 0,
+            mem_unit: 1,
+        }
+    }
+
+    #[cfg(target_os = "openbsd")]
+    pub fn sysinfo() -> SysInfo {
+        const HW_PHYSMEM64: libc::c_int = 19;
+        const VM_UVMEXP: libc::c_int = 4;
+
+        // Read the sysctl `mib` into `buf`, which may be larger than the value
+        unsafe fn sysctl_mib<T>(mib: [libc::c_int; 2], buf: &mut T) -> bool {
+            let mut len = size_of::<T>();
+            libc::sysctl(mib.as_ptr(), 2, buf as *mut T as *mut _, &mut len, std::ptr::null_mut(), 0) == 0
+        }
+
+        let mut physmem = 0i64;
+        // struct uvmexp starts with pagesize, pagemask, pageshift, npages, free, active, inactive
+        let mut uvmexp = [0 as libc::c_int; 512];
+        unsafe {
+            sysctl_mib([libc::CTL_HW, HW_PHYSMEM64], &mut physmem);
+            if !sysctl_mib([libc::CTL_VM, VM_UVMEXP], &mut uvmexp) {
+                uvmexp = [0; 512];
+            }
+        }
+
+        SysInfo {
+            totalram: physmem as usize,
+            freeram: (uvmexp[4] as usize + uvmexp[6] as usize) * getpagesize(),
+            sharedram: 0,
+            bufferram: 0,
+            totalswap: 0,
+            freeswap: 0,
+            procs: num_cpus::get() as u16,
+            totalhigh: 0,
+            freehigh: 0,
+            mem_unit: 1,
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd", target_os = "openbsd")))]
+    pub fn sysinfo() -> SysInfo {
+        // Fallback for other Unix systems
         SysInfo {
             totalram: 0,
             freeram: 0,
@@ -176,22 +374,229 @@ mod unix {
         unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
     }
 
+    /// # Safety
+    /// `addr` must point to `len` bytes mapped by this process.
     pub unsafe fn mlock(addr: *mut u8, len: usize) -> i32 {
         libc::mlock(addr as *const _, len)
     }
 
+    /// # Safety
+    /// `addr` must point to `len` bytes mapped by this process.
+    pub unsafe fn munlock(addr: *mut u8, len: usize) -> i32 {
+        libc::munlock(addr as *const _, len)
+    }
+
+    /// Drop the clean page cache (root only). Returns false if that wasn't possible.
+    #[cfg(target_os = "linux")]
+    pub fn drop_caches() -> bool {
+        std::fs::write("/proc/sys/vm/drop_caches", "1").is_ok()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn drop_caches() -> bool {
+        false
+    }
+
+    // CAP_IPC_LOCK lets mlock ignore RLIMIT_MEMLOCK entirely (bit 14 of the effective set)
+    #[cfg(target_os = "linux")]
+    fn has_cap_ipc_lock() -> bool {
+        std::fs::read_to_string("/proc/self/status").ok()
+            .and_then(|status| {
+                let caps = status.lines().find_map(|line| line.strip_prefix("CapEff:"))?;
+                u64::from_str_radix(caps.trim(), 16).ok()
+            })
+            .is_some_and(|caps| caps & (1 << 14) != 0)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn has_cap_ipc_lock() -> bool {
+        false
+    }
+
+    /// Raise RLIMIT_MEMLOCK to at least `bytes`: the soft limit up to the hard limit, and the hard
+    /// limit too if permitted (CAP_SYS_RESOURCE). Returns how much can be locked now, None if
+    /// there's no limit.
+    pub fn raise_memlock_limit(bytes: usize) -> Option<usize> {
+        if has_cap_ipc_lock() {
+            return None;
+        }
+        unsafe {
+            let mut limit: libc::rlimit = std::mem::zeroed();
+            if libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) != 0 {
+                return Some(0);
+            }
+            if limit.rlim_cur == libc::RLIM_INFINITY {
+                return None;
+            }
+            if (limit.rlim_cur as usize) < bytes {
+                let raised = libc::rlimit { rlim_cur: bytes as libc::rlim_t, rlim_max: limit.rlim_max.max(bytes as libc::rlim_t) };
+                if libc::setrlimit(libc::RLIMIT_MEMLOCK, &raised) == 0 {
+                    return Some(bytes);
+                }
+                limit.rlim_cur = limit.rlim_max;
+                if libc::setrlimit(libc::RLIMIT_MEMLOCK, &limit) != 0 {
+                    libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit);
+                }
+            }
+            (limit.rlim_cur != libc::RLIM_INFINITY).then_some(limit.rlim_cur as usize)
+        }
+    }
+
+    /// Allocate a contiguous memory block with at least `size` bytes and alignment `alignment`,
+    /// with inaccessible guard pages right before and after it.
+    /// Returns a pointer to the aligned memory, or null on failure.
+    ///
+    /// # Safety
+    /// `alignment` must be a power of two. The block must be released with `aligned_free`.
     pub unsafe fn aligned_alloc(alignment: usize, size: usize) -> *mut u8 {
-        libc::aligned_alloc(alignment, size) as *mut u8
+        let page = getpagesize();
+        let size = size.next_multiple_of(page);
+        let alloc_size = size + alignment + 2 * page;
+
+        // map everything inaccessible, then open up the aligned block between the guards
+        let base = libc::mmap(std::ptr::null_mut(), alloc_size, libc::PROT_NONE, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS, -1, 0);
+        if base == libc::MAP_FAILED {
+            return std::ptr::null_mut();
+        }
+        let aligned_ptr = super::align_up(base as usize + page, alignment);
+        if libc::mprotect(aligned_ptr as *mut _, size, libc::PROT_READ | libc::PROT_WRITE) != 0 {
+            libc::munmap(base, alloc_size);
+            return std::ptr::null_mut();
+        }
+        super::ALLOCATIONS.insert(aligned_ptr, base as usize, alloc_size);
+        aligned_ptr as *mut u8
     }
 
+    /// # Safety
+    /// `ptr` must come from `aligned_alloc` and must not be used afterwards.
     pub unsafe fn aligned_free(ptr: *mut u8) {
-        libc::free(ptr as *mut _);
+        if let Some((base, len)) = super::ALLOCATIONS.remove(ptr as usize) {
+            libc::munmap(base as *mut _, len);
+        }
+    }
+
+    /// Map at least `size` bytes backed by the largest huge pages available (1GiB, then 2MiB;
+    /// reserved through /proc/sys/vm/nr_hugepages or the hugepages= boot parameter).
+    /// Returns the mapping and its page size, or None if no huge pages could be mapped.
+    ///
+    /// # Safety
+    /// The mapping must be released with `hugepage_free` and the same `size`.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn hugepage_alloc(size: usize) -> Option<(*mut u8, usize)> {
+        for (page, flag) in [(1usize << 30, libc::MAP_HUGE_1GB), (1 << 21, libc::MAP_HUGE_2MB)] {
+            let ptr = libc::mmap(
+                std::ptr::null_mut(),
+                size.next_multiple_of(page),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB | flag,
+                -1,
+                0,
+            );
+            if ptr != libc::MAP_FAILED {
+                return Some((ptr as *mut u8, page));
+            }
+        }
+        None
+    }
+
+    /// # Safety
+    /// `ptr` and `page` must come from `hugepage_alloc(size)`, the mapping must not be used afterwards.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn hugepage_free(ptr: *mut u8, size: usize, page: usize) {
+        libc::munmap(ptr as *mut _, size.next_multiple_of(page));
+    }
+
+    /// # Safety
+    /// See the Linux version.
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn hugepage_alloc(_size: usize) -> Option<(*mut u8, usize)> {
+        None
+    }
+
+    /// # Safety
+    /// See the Linux version.
+    #[cfg(not(target_os = "linux"))]
+    pub unsafe fn hugepage_free(_ptr: *mut u8, _size: usize, _page: usize) {}
+
+    // Normal RAM can only be mapped WC by the kernel (ioremap_wc, or a driver's mmap with
+    // pgprot_writecombine); /dev/mem refuses System RAM, so there's no userspace path.
+    /// # Safety
+    /// As `aligned_alloc`, this one always returns null.
+    pub unsafe fn write_combining_alloc(_alignment: usize, _size: usize) -> *mut u8 {
+        std::ptr::null_mut()
+    }
+}
+
+// Base address and length of the over-allocated blocks that aligned pointers were carved out of
+struct AllocationRegistry(std::sync::Mutex<std::collections::BTreeMap<usize, (usize, usize)>>);
+
+impl AllocationRegistry {
+    const fn new() -> Self {
+        Self(std::sync::Mutex::new(std::collections::BTreeMap::new()))
+    }
+
+    fn insert(&self, aligned: usize, base: usize, len: usize) {
+        self.0.lock().unwrap().insert(aligned, (base, len));
+    }
+
+    fn remove(&self, aligned: usize) -> Option<(usize, usize)> {
+        self.0.lock().unwrap().remove(&aligned)
     }
 }
 
+static ALLOCATIONS: AllocationRegistry = AllocationRegistry::new();
+
+fn align_up(address: usize, alignment: usize) -> usize {
+    address.next_multiple_of(alignment)
+}
+
 #[cfg(windows)]
 pub use windows::*;
 
 #[cfg(not(windows))]
 pub use unix::*;
 
+#[cfg(test)]
+mod tests {
+    use super::{align_up, aligned_alloc, aligned_free, getpagesize, AllocationRegistry};
+
+    #[test]
+    fn registry_returns_base() {
+        let registry = AllocationRegistry::new();
+        let alignment = 16 * 4096;
+        let base = 0x7f00_0000_3000;
+        let aligned = align_up(base, alignment);
+        assert_eq!(aligned % alignment, 0);
+        assert!(aligned >= base && aligned - base < alignment);
+        assert_eq!(align_up(aligned, alignment), aligned);
+
+        registry.insert(aligned, base, 1 << 20);
+        assert_eq!(registry.remove(aligned), Some((base, 1 << 20)));
+        assert_eq!(registry.remove(aligned), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn guard_pages() {
+        let page = getpagesize();
+        let (alignment, size) = (4 * page, 8 * page);
+        unsafe {
+            let ptr = aligned_alloc(alignment, size);
+            assert!(!ptr.is_null() && (ptr as usize).is_multiple_of(alignment));
+            std::ptr::write_bytes(ptr, 0xA5, size);
+
+            // the pages on either side must be mapped without any access
+            let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+            let protection = |address: usize| maps.lines().find_map(|line| {
+                let (range, rest) = line.split_once(' ')?;
+                let (start, end) = range.split_once('-')?;
+                let range = usize::from_str_radix(start, 16).ok()?..usize::from_str_radix(end, 16).ok()?;
+                range.contains(&address).then(|| rest[..4].to_string())
+            });
+            assert_eq!(protection(ptr as usize - page).as_deref(), Some("---p"));
+            assert_eq!(protection(ptr as usize + size).as_deref(), Some("---p"));
+            assert_eq!(protection(ptr as usize).as_deref(), Some("rw-p"));
+            aligned_free(ptr);
+        }
+    }
+}
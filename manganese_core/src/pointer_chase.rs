@@ -0,0 +1,108 @@
+// Pointer-chase test, shared by all instruction set backends.
+// Every chunk is turned into a single random cycle over its cache lines (Sattolo's shuffle, done
+// in place): the first word of a line holds the index of the next line, the second its complement.
+// Chasing the chain serializes every load behind the previous one, so the test runs at memory
+// latency instead of bandwidth and catches timing-marginal modules that bandwidth-bound patterns
+// miss. The first chunk is chased alone (idle latency), then all chunks at once (loaded latency);
+// a flipped pointer shows up as a complement mismatch or as a cycle of the wrong length.
+use std::time::{Duration, Instant};
+use log::{error, info};
+use crate::tests::{log_miscompare, miscompared_bytes, record_error, TestContext};
+
+const LINE: usize = 64;
+
+unsafe fn node(mem: *mut u8, start: usize, line: usize) -> *mut u64 {
+    mem.add(start + line * LINE) as *mut u64
+}
+
+// Builds the cycle over the lines of `mem[start..start + chunk_size]`.
+pub(crate) unsafe fn build_chain(mem: *mut u8, start: usize, chunk_size: usize, seed: u64) {
+    let lines = chunk_size / LINE;
+    for line in 0..lines {
+        node(mem, start, line).write(line as u64);
+    }
+
+    // Sattolo: swapping only with strictly lower indices yields exactly one cycle
+    let mut state = seed | 1;
+    for i in (1..lines).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = (state % i as u64) as usize;
+        let (a, b) = (node(mem, start, i), node(mem, start, j));
+        let next = a.read();
+        a.write(b.read());
+        b.write(next);
+    }
+
+    for line in 0..lines {
+        let p = node(mem, start, line);
+        p.add(1).write(!p.read());
+    }
+}
+
+// Follows the cycle from line 0, returns the miscompared bytes and the time taken per step.
+unsafe fn chase_chain(mem: *mut u8, start: usize, chunk_size: usize, ctx: &TestContext) -> (u64, Duration) {
+    let lines = chunk_size / LINE;
+    if lines == 0 {
+        return (0, Duration::ZERO);
+    }
+
+    let timer = Instant::now();
+    let mut line = 0;
+    let mut steps = 0;
+    loop {
+        let p = node(mem, start, line);
+        let next = std::ptr::read_volatile(p);
+        let check = std::ptr::read_volatile(p.add(1));
+        if check != !next || next >= lines as u64 {
+            if log_miscompare(ctx) {
+                error!("errors detected at offset 0x{:016x}", start + line * LINE);
+            }
+            // the link as its complement says it should be
+            record_error(start + line * LINE, !check, next, ctx);
+            // a link out of range can match its complement, it's still wrong
+            return (miscompared_bytes(!check, next).max(1), Duration::ZERO);
+        }
+        line = next as usize;
+        steps += 1;
+        if line == 0 || steps > lines {
+            break;
+        }
+    }
+    let per_step = timer.elapsed() / steps as u32;
+
+    if steps != lines {
+        if log_miscompare(ctx) {
+            error!("errors detected in pointer chain at offset 0x{:016x}: cycle of {} lines, expected {}", start, steps, lines);
+        }
+        // there is no single bad word, record the chain lengths instead and count one byte
+        record_error(start, lines as u64, steps as u64, ctx);
+        return (1, per_step);
+    }
+    (0, per_step)
+}
+
+/// Builds and chases a pointer cycle in each of the `ctx.cpus` chunks of `mem[..size]`,
+/// returns the miscompared bytes of the broken chains.
+pub unsafe fn pointer_chase(mem: *mut u8, size: usize, seed: u64, ctx: &TestContext) -> u64 {
+    use rayon::prelude::*;
+    let threads = ctx.cpus;
+    let mem_usize = mem as usize;
+    let chunk_size = size / threads;
+
+    (0..threads).into_par_iter().for_each(|i| {
+        build_chain(mem_usize as *mut u8, i * chunk_size, chunk_size, seed ^ i as u64);
+    });
+
+    let (mut errors, idle) = chase_chain(mem, 0, chunk_size, ctx);
+    let results: Vec<(u64, Duration)> = (0..threads).into_par_iter()
+        .map(|i| chase_chain(mem_usize as *mut u8, i * chunk_size, chunk_size, ctx))
+        .collect();
+    errors += results.iter().map(|r| r.0).sum::<u64>();
+    let loaded = results.iter().map(|r| r.1).sum::<Duration>() / threads as u32;
+
+    info!("Memory latency: {:.1}ns idle, {:.1}ns loaded ({} threads)",
+        idle.as_secs_f64() * 1e9, loaded.as_secs_f64() * 1e9, threads);
+    errors
+}
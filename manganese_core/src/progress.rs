@@ -0,0 +1,199 @@
+// Progress of a session's run for front ends to poll: the tests of the current pass and how far the running
+// one got. Within a run of a test over a region progress is counted in sweeps (every parallel
+// pass over the region hands out its chunks through `chunk_order()`), against the sweeps its last
+// run took, or `passes * iters` before it ran once. The time left is the workload left (`passes *
+// iters * loops` times the tested size for every test) over the bandwidth the test had the last
+// time it ran to the end, or the average bandwidth of the run before. A session's progress callback
+// gets the snapshot whenever a pass, test or loop starts, and a test ends.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::tests::TestDefinition;
+
+/// Snapshot of the run's progress, see `TestSession::progress()`.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    /// Complete passes so far.
+    pub passes: usize,
+    /// Tests of the current pass in running order.
+    pub tests: Vec<&'static str>,
+    /// Index of the running test in `tests`.
+    pub test: usize,
+    /// Loop of the running test, from 1, and its loops.
+    pub test_loop: usize,
+    pub loops: usize,
+    /// Done of the running test (all loops), 0 to 1.
+    pub test_fraction: f64,
+    /// Done of the pass, every test weighted the same.
+    pub pass_fraction: f64,
+    /// Time left of the running test and of the pass, None before any test ran to the end.
+    pub test_eta: Option<Duration>,
+    pub pass_eta: Option<Duration>,
+}
+
+struct State {
+    progress: Progress,
+    // runs (one loop over one region) of the running test done and in total
+    runs_done: usize,
+    runs: usize,
+    // sweeps of the last run of every test
+    sweeps: HashMap<&'static str, usize>,
+    expected: usize,
+    // bytes of every test of the pass, in running order
+    workloads: Vec<f64>,
+    // bytes per second of the last complete run of every test, and of all of them
+    rates: HashMap<&'static str, f64>,
+    tested: (f64, f64),
+    test_start: Instant,
+}
+
+impl State {
+    fn rate(&self, test: &str) -> Option<f64> {
+        self.rates.get(test).copied().or((self.tested.1 > 0.0).then(|| self.tested.0 / self.tested.1))
+    }
+
+    fn time_left(&self, index: usize, fraction: f64) -> Option<Duration> {
+        let rate = self.rate(self.progress.tests[index]).filter(|rate| *rate > 0.0)?;
+        Some(Duration::from_secs_f64(self.workloads[index] * (1.0 - fraction) / rate))
+    }
+}
+
+/// The progress of one run, shared by the session and the run's threads, see `TestSession::progress()`.
+#[derive(Default)]
+pub struct RunProgress {
+    state: Mutex<Option<State>>,
+    sweeps: AtomicUsize,
+    // the session's, see `TestSessionBuilder::progress()`
+    callback: Mutex<Option<ProgressCallback>>,
+}
+
+pub(crate) type ProgressCallback = Box<dyn FnMut(&Progress) + Send>;
+
+impl RunProgress {
+    /// Call `callback` with the progress of the run from now on, in place of any earlier one.
+    pub(crate) fn set_callback(&self, callback: Option<ProgressCallback>) {
+        *self.callback.lock().unwrap() = callback;
+    }
+
+    /// The progress of the run, None before the first test started.
+    pub fn progress(&self) -> Option<Progress> {
+        let state = self.state.lock().unwrap();
+        let state = state.as_ref()?;
+        let mut progress = state.progress.clone();
+        if state.runs > 0 {
+            let run = (self.sweeps.load(Ordering::Relaxed) as f64 / state.expected.max(1) as f64).min(1.0);
+            progress.test_fraction = ((state.runs_done as f64 + run) / state.runs as f64).min(1.0);
+        }
+        progress.pass_fraction = (progress.test as f64 + progress.test_fraction) / progress.tests.len().max(1) as f64;
+        if progress.test < progress.tests.len() {
+            progress.test_eta = state.time_left(progress.test, progress.test_fraction);
+            progress.pass_eta = (progress.test + 1..progress.tests.len())
+                .try_fold(progress.test_eta?, |eta, i| Some(eta + state.time_left(i, 0.0)?));
+        }
+        Some(progress)
+    }
+
+    // Hand the progress to the callback, outside of the state's lock
+    fn notify(&self) {
+        if let Some(callback) = self.callback.lock().unwrap().as_mut() {
+            if let Some(progress) = self.progress() {
+                callback(&progress);
+            }
+        }
+    }
+
+    /// Pass `passes` + 1 starts and runs `tests` in this order over `size` bytes.
+    pub(crate) fn start_pass(&self, passes: usize, tests: &[&TestDefinition], size: usize) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let state = state.get_or_insert_with(|| State {
+                progress: Progress::default(), runs_done: 0, runs: 0, sweeps: HashMap::new(), expected: 0,
+                workloads: Vec::new(), rates: HashMap::new(), tested: (0.0, 0.0), test_start: Instant::now(),
+            });
+            state.progress = Progress { passes, tests: tests.iter().map(|test| test.name).collect(), ..Progress::default() };
+            state.workloads = tests.iter().map(|test| (test.passes * test.iters * test.loops * size) as f64).collect();
+            state.runs_done = 0;
+            state.runs = 0;
+        }
+        self.notify();
+    }
+
+    /// The test at `index` of the pass starts, `runs` times (loops * regions).
+    pub(crate) fn start_test(&self, index: usize, test: &TestDefinition, runs: usize) {
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            state.progress.test = index;
+            state.progress.test_loop = 0;
+            state.progress.loops = test.loops;
+            state.progress.test_fraction = 0.0;
+            state.runs_done = 0;
+            state.runs = runs;
+            state.expected = state.sweeps.get(test.name).copied().unwrap_or(test.passes * test.iters);
+            state.test_start = Instant::now();
+        }
+        self.notify();
+    }
+
+    /// The running test was scaled to `loops` loops, `runs` times in all (loops * regions).
+    pub(crate) fn scale_test(&self, loops: usize, runs: usize) {
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            let test = state.progress.test;
+            state.workloads[test] *= loops as f64 / state.progress.loops.max(1) as f64;
+            state.progress.loops = loops;
+            state.runs = runs;
+        }
+        self.notify();
+    }
+
+    /// Loop `i` of the running test starts.
+    pub(crate) fn start_loop(&self, i: usize) {
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            state.progress.test_loop = i;
+        }
+        self.notify();
+    }
+
+    /// A run of the test over one region starts.
+    pub(crate) fn start_run(&self) {
+        self.sweeps.store(0, Ordering::Relaxed);
+    }
+
+    /// The run of the test over one region is done.
+    pub(crate) fn end_run(&self) {
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            let test = state.progress.tests[state.progress.test];
+            state.expected = self.sweeps.swap(0, Ordering::Relaxed);
+            state.sweeps.insert(test, state.expected);
+            state.runs_done += 1;
+        }
+    }
+
+    /// The running test is done, `complete` unless it was stopped.
+    pub(crate) fn end_test(&self, complete: bool) {
+        if let Some(state) = self.state.lock().unwrap().as_mut() {
+            // a stopped test's bandwidth says nothing
+            if complete && state.runs > 0 {
+                let (bytes, secs) = (state.workloads[state.progress.test], state.test_start.elapsed().as_secs_f64());
+                state.rates.insert(state.progress.tests[state.progress.test], bytes / secs);
+                state.tested = (state.tested.0 + bytes, state.tested.1 + secs);
+            }
+            state.runs_done = state.runs;
+        }
+        self.notify();
+    }
+
+    /// One sweep over the region is handed out to the threads.
+    pub(crate) fn sweep(&self) {
+        self.sweeps.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// "1h05m", "4m30s" or "12s".
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -0,0 +1,276 @@
+// Error records: every miscompare is pushed with its data (offset, expected and actual 64-bit
+// word, worker thread, time) onto the run's lock-free stack, shared across all its workers. The
+// kernels don't know which test or pass they are part of; the main loop drains the stack after
+// every test run, stamps the records with both, and summarizes them with repeated reads of the
+// same fault merged at the end of every pass. Across the run, words that fail in more than one
+// test run are told apart from one-off flips: a stuck or weak cell keeps failing whatever the
+// pattern, a transient (cosmic ray, marginal timing) doesn't come back.
+use std::collections::{BTreeSet, HashMap};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::time::SystemTime;
+use log::error;
+use serde::Serialize;
+use crate::report::{hex, unix_time};
+
+// records waiting to be drained; a failing DIMM can produce millions of miscompares per pass
+const QUEUED_MAX: usize = 65536;
+// distinct faults listed per pass summary
+const SUMMARY_MAX: usize = 16;
+// failing words tracked across the run
+const HISTORY_MAX: usize = 65536;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    pub test: &'static str,
+    pub pass: usize,
+    /// Offset of the miscomparing 64-bit word in the tested region.
+    pub offset: usize,
+    #[serde(serialize_with = "hex")]
+    pub expected: u64,
+    #[serde(serialize_with = "hex")]
+    pub actual: u64,
+    /// Flipped bits, `expected ^ actual`.
+    #[serde(serialize_with = "hex")]
+    pub bitmask: u64,
+    /// Index of the worker in its thread pool, None outside of one.
+    pub thread: Option<usize>,
+    #[serde(serialize_with = "unix_time")]
+    pub timestamp: SystemTime,
+}
+
+struct Node {
+    record: ErrorRecord,
+    next: *mut Node,
+}
+
+/// The records of one run waiting to be drained, see `RunState::records`.
+pub struct RecordQueue {
+    head: AtomicPtr<Node>,
+    queued: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl Default for RecordQueue {
+    fn default() -> Self {
+        RecordQueue { head: AtomicPtr::new(null_mut()), queued: AtomicUsize::new(0), dropped: AtomicUsize::new(0) }
+    }
+}
+
+impl Drop for RecordQueue {
+    fn drop(&mut self) {
+        self.discard();
+    }
+}
+
+impl RecordQueue {
+    /// Queue a miscompare of the word at `offset`, from any thread.
+    pub fn push(&self, offset: usize, expected: u64, actual: u64) {
+        // a full queue only counts, without allocating or reading the clock
+        if self.queued.load(Ordering::Relaxed) >= QUEUED_MAX {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if self.queued.fetch_add(1, Ordering::Relaxed) >= QUEUED_MAX {
+            self.queued.fetch_sub(1, Ordering::Relaxed);
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let node = Box::into_raw(Box::new(Node {
+            record: ErrorRecord {
+                test: "",
+                pass: 0,
+                offset,
+                expected,
+                actual,
+                bitmask: expected ^ actual,
+                thread: rayon::current_thread_index(),
+                timestamp: SystemTime::now(),
+            },
+            next: null_mut(),
+        }));
+        // nodes are only ever removed all at once, so there is no ABA problem
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).next = head; }
+            match self.head.compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Drain the records queued since the last call in the order they were pushed, stamped with
+    /// `test` and `pass`. The second value counts miscompares that didn't fit the queue.
+    pub fn take(&self, test: &'static str, pass: usize) -> (Vec<ErrorRecord>, usize) {
+        let mut node = self.head.swap(null_mut(), Ordering::Acquire);
+        let mut records = Vec::new();
+        while !node.is_null() {
+            let Node { mut record, next } = *unsafe { Box::from_raw(node) };
+            record.test = test;
+            record.pass = pass;
+            records.push(record);
+            node = next;
+        }
+        self.queued.fetch_sub(records.len(), Ordering::Relaxed);
+        records.reverse();
+        (records, self.dropped.swap(0, Ordering::Relaxed))
+    }
+
+    /// Forget the queued records, for runs whose miscompares are reported elsewhere.
+    pub fn discard(&self) {
+        self.take("", 0);
+    }
+}
+
+/// The first record of every distinct fault (test, offset and flipped bits) with its number of
+/// occurrences, in the order the faults were first seen.
+pub fn dedup(records: &[ErrorRecord]) -> Vec<(&ErrorRecord, usize)> {
+    let mut seen: HashMap<(&str, usize, u64), usize> = HashMap::new();
+    let mut faults: Vec<(&ErrorRecord, usize)> = Vec::new();
+    for record in records {
+        match seen.get(&(record.test, record.offset, record.bitmask)) {
+            Some(&i) => faults[i].1 += 1,
+            None => {
+                seen.insert((record.test, record.offset, record.bitmask), faults.len());
+                faults.push((record, 1));
+            }
+        }
+    }
+    faults
+}
+
+/// Log the miscompares of a pass, merged by fault; `dropped` didn't fit the queue.
+pub fn log_pass_summary(label: &str, records: &[ErrorRecord], dropped: usize) {
+    if records.is_empty() && dropped == 0 {
+        return;
+    }
+    let faults = dedup(records);
+    let bits = records.iter().fold(0, |bits, record| bits | record.bitmask);
+    error!("{}: {} miscompares at {} distinct faults, flipped bits 0x{:016x}{}", label, records.len() + dropped,
+        faults.len(), bits, if dropped > 0 { format!(" ({} not recorded)", dropped) } else { String::new() });
+    for (record, count) in faults.iter().take(SUMMARY_MAX) {
+        let thread = record.thread.map_or(String::new(), |thread| format!(", thread {}", thread));
+        error!("  {} at offset 0x{:016x}: expected 0x{:016x}, actual 0x{:016x} [xor 0x{:016x}] {}x{}",
+            record.test, record.offset, record.expected, record.actual, record.bitmask, count, thread);
+    }
+    if faults.len() > SUMMARY_MAX {
+        error!("  ... and {} more faults", faults.len() - SUMMARY_MAX);
+    }
+}
+
+// One failing word over the whole run
+struct Fault {
+    region: usize,
+    first: ErrorRecord,
+    count: usize,
+    // (test, pass) runs that saw it fail
+    runs: BTreeSet<(&'static str, usize)>,
+    bits: u64,
+}
+
+/// The miscompares of the whole run by failing word (region and offset), to separate hard
+/// errors, which several test runs reproduce, from one-off transient flips.
+#[derive(Default)]
+pub struct FaultHistory {
+    faults: HashMap<(usize, usize), Fault>,
+    // keys in the order the words first failed
+    order: Vec<(usize, usize)>,
+    untracked: usize,
+}
+
+impl FaultHistory {
+    /// Add the records drained from `region`, stamped with their test and pass.
+    pub fn add(&mut self, region: usize, records: &[ErrorRecord]) {
+        for record in records {
+            let key = (region, record.offset);
+            if !self.faults.contains_key(&key) {
+                if self.order.len() >= HISTORY_MAX {
+                    self.untracked += 1;
+                    continue;
+                }
+                self.order.push(key);
+            }
+            let fault = self.faults.entry(key).or_insert_with(|| Fault {
+                region,
+                first: record.clone(),
+                count: 0,
+                runs: BTreeSet::new(),
+                bits: 0,
+            });
+            fault.count += 1;
+            fault.runs.insert((record.test, record.pass));
+            fault.bits |= record.bitmask;
+        }
+    }
+
+    // (hard, transient) faults, in the order they first failed
+    fn classify(&self) -> (Vec<&Fault>, Vec<&Fault>) {
+        self.order.iter().map(|key| &self.faults[key]).partition(|fault| fault.runs.len() > 1)
+    }
+
+    /// Log the hard errors with the tests reproducing them, then the transient ones; `labels`
+    /// names the regions (empty for a single region).
+    pub fn log(&self, labels: &[String]) {
+        if self.order.is_empty() {
+            return;
+        }
+        let label = |region: usize| labels.get(region).map_or("", String::as_str);
+        let (hard, transient) = self.classify();
+        error!("Fault Analysis    : {} hard errors (reproduced by several test runs), {} transient miscompares{}",
+            hard.len(), transient.len(),
+            if self.untracked > 0 { format!(", {} more not tracked", self.untracked) } else { String::new() });
+        for fault in hard.iter().take(SUMMARY_MAX) {
+            let tests: BTreeSet<&str> = fault.runs.iter().map(|&(test, _)| test).collect();
+            error!("  hard error at offset 0x{:016x}{}, flipped bits 0x{:016x}, reproduced {} times in {} test runs [{}]",
+                fault.first.offset, label(fault.region), fault.bits, fault.count, fault.runs.len(),
+                tests.into_iter().collect::<Vec<_>>().join(", "));
+        }
+        if hard.len() > SUMMARY_MAX {
+            error!("  ... and {} more hard errors", hard.len() - SUMMARY_MAX);
+        }
+        for fault in transient.iter().take(SUMMARY_MAX) {
+            error!("  transient at offset 0x{:016x}{}, flipped bits 0x{:016x}, {} in pass {}{}",
+                fault.first.offset, label(fault.region), fault.bits, fault.first.test, fault.first.pass,
+                if fault.count > 1 { format!(" ({} reads)", fault.count) } else { String::new() });
+        }
+        if transient.len() > SUMMARY_MAX {
+            error!("  ... and {} more transient miscompares", transient.len() - SUMMARY_MAX);
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drain_in_order_and_merge() {
+        let queue = RecordQueue::default();
+        std::thread::scope(|scope| {
+            for t in 0..4u64 {
+                let queue = &queue;
+                scope.spawn(move || (0..100).for_each(|i| queue.push(i as usize % 10 * 8, t, t ^ 1)));
+            }
+        });
+        queue.push(4096, 0, 0x80);
+        let (records, dropped) = queue.take("basic_tests", 1);
+        assert_eq!((records.len(), dropped), (401, 0));
+        assert_eq!(records.last().unwrap().offset, 4096);
+        assert!(records.iter().all(|r| r.test == "basic_tests" && r.pass == 1));
+        // 10 offsets with the same flipped bit from every thread, plus the last one
+        assert_eq!(dedup(&records).len(), 11);
+        assert!(queue.take("basic_tests", 1).0.is_empty());
+
+        // offset 0 keeps failing in another pass, 4096 only ever failed once
+        let mut history = FaultHistory::default();
+        history.add(0, &records);
+        queue.push(0, 0, 1);
+        history.add(0, &queue.take("march", 2).0);
+        let (hard, transient) = history.classify();
+        assert_eq!(hard.iter().map(|fault| fault.first.offset).collect::<Vec<_>>(), [0]);
+        assert_eq!(transient.len(), 10);
+        assert_eq!(hard[0].runs.len(), 2);
+    }
+}
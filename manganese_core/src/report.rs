@@ -0,0 +1,405 @@
+// Machine-readable run report (`--output-json`): hardware, test plan, per-test results, error
+// records, duration and exit status, written as JSON when the run ends so burn-in racks can
+// collect results without scraping the log. The same results can be written as JUnit XML
+// (`--output-junit`), one test case per test and pass, for CI dashboards that already read it.
+use std::fmt::Write as _;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use log::{error, info};
+use serde::{Serialize, Serializer};
+use time::OffsetDateTime;
+use time::macros::format_description;
+use crate::exit_status;
+use crate::benchmark::Benchmark;
+use crate::hardware::{InstructionSet, SystemInfo};
+use crate::records::ErrorRecord;
+use crate::tests::{CacheMode, Partition, TestDefinition};
+
+// error records kept for the report; a failing DIMM can produce millions
+const RECORDS_MAX: usize = 65536;
+// error records listed in the failure of a JUnit test case
+const FAILURE_RECORDS_MAX: usize = 16;
+
+// 64-bit data words as hex strings, JSON numbers lose precision above 2^53
+pub(crate) fn hex<S: Serializer>(v: &u64, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(&format_args!("0x{:016x}", v))
+}
+
+pub(crate) fn unix_time<S: Serializer>(t: &SystemTime, s: S) -> Result<S::Ok, S::Error> {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => s.serialize_f64(d.as_secs_f64()),
+        Err(_) => s.serialize_none(),
+    }
+}
+
+// "2024-03-01T12:00:00" in UTC, the JUnit timestamp format
+fn iso_time(t: SystemTime) -> String {
+    OffsetDateTime::from(t).format(format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]")).unwrap_or_default()
+}
+
+#[derive(Serialize)]
+struct Cpu {
+    manufacturer: String,
+    name: String,
+    socket: String,
+    cores: u32,
+    threads: u32,
+    l1_kb: u32,
+    l2_kb: u32,
+    l3_kb: u32,
+}
+
+#[derive(Serialize)]
+struct Board {
+    manufacturer: String,
+    product: String,
+    version: String,
+    serial: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MemoryDevice {
+    locator: String,
+    channel: Option<String>,
+    slot: Option<u8>,
+    size_mb: u32,
+    speed: u16,
+    configured_speed: u16,
+    manufacturer: String,
+    part_number: String,
+    serial: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Hardware {
+    cpu: Option<Cpu>,
+    board: Option<Board>,
+    memory_devices: Vec<MemoryDevice>,
+    instruction_set: &'static str,
+    threads: usize,
+    total_ram: usize,
+}
+
+impl Hardware {
+    fn new(info: &SystemInfo, isa: InstructionSet, threads: usize, total_ram: usize) -> Self {
+        let serial = |serial: &str| (!info.hide_serials).then(|| serial.to_string());
+        Hardware {
+            cpu: info.cpu.as_ref().map(|cpu| Cpu {
+                manufacturer: cpu.manufacturer.clone(),
+                name: cpu.name.clone(),
+                socket: cpu.socket.clone(),
+                cores: cpu.cores,
+                threads: cpu.threads,
+                l1_kb: cpu.l1_kb,
+                l2_kb: cpu.l2_kb,
+                l3_kb: cpu.l3_kb,
+            }),
+            board: info.board.as_ref().map(|board| Board {
+                manufacturer: board.manufacturer.clone(),
+                product: board.product.clone(),
+                version: board.version.clone(),
+                serial: serial(&board.serial),
+            }),
+            memory_devices: info.memory_devices.iter().filter(|m| m.populated).map(|m| MemoryDevice {
+                locator: m.locator.clone(),
+                channel: m.channel_name.clone(),
+                slot: m.slot_index,
+                size_mb: m.size_mb,
+                speed: m.speed,
+                configured_speed: m.configured_speed,
+                manufacturer: m.manufacturer.clone(),
+                part_number: m.part_number.clone(),
+                serial: serial(&m.serial),
+            }).collect(),
+            instruction_set: match isa {
+                InstructionSet::AVX512 => "AVX-512",
+                InstructionSet::AVX2 => "AVX2",
+                InstructionSet::NEON => "NEON",
+                InstructionSet::SSE => "scalar",
+            },
+            threads,
+            total_ram,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PlannedTest {
+    name: &'static str,
+    loops: usize,
+    passes: usize,
+    sleep: u64,
+    cache: &'static str,
+    stride: usize,
+    partition: &'static str,
+}
+
+impl PlannedTest {
+    fn new(test: &TestDefinition) -> Self {
+        PlannedTest {
+            name: test.name,
+            loops: test.loops,
+            passes: test.passes * test.iters,
+            sleep: test.options.sleep,
+            cache: match test.options.cache {
+                CacheMode::Bypass => "bypass",
+                CacheMode::Cached => "cached",
+            },
+            stride: test.options.stride.max(1),
+            partition: match test.options.partition {
+                Partition::Contiguous => "contiguous",
+                Partition::Interleaved => "interleaved",
+            },
+        }
+    }
+}
+
+// The `--output-json` document
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(serialize_with = "unix_time")]
+    started: SystemTime,
+    duration_seconds: f64,
+    hardware: &'a Hardware,
+    plan: &'a [PlannedTest],
+    tested_bytes: usize,
+    #[serde(serialize_with = "hex")]
+    seed: u64,
+    complete_passes: usize,
+    results: &'a [TestStats],
+    errors: u64,
+    flipped_bits: u64,
+    corrupted_lines: u64,
+    ecc_corrected: u64,
+    ecc_uncorrected: u64,
+    error_records: &'a [ErrorRecord],
+    error_records_dropped: usize,
+    exit_status: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    benchmark: Option<&'a Benchmark>,
+}
+
+// XML attribute and text escaping
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' | '\t' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// One test's run in one pass.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestStats {
+    pub test: String,
+    pub pass: usize,
+    pub seconds: f64,
+    /// Average bandwidth in MB/s.
+    #[serde(rename = "bandwidth_mbs")]
+    pub bandwidth: f64,
+    /// Miscompared bytes.
+    pub errors: u64,
+}
+
+/// Outcome of a finished run, see `run_tests()`.
+#[derive(Debug, Clone)]
+pub struct RunReport {
+    /// Bytes tested, the largest block with `--cycle`.
+    pub allocated_bytes: usize,
+    /// Whether all of the tested memory was locked.
+    pub locked: bool,
+    /// Complete passes (cycles).
+    pub passes: usize,
+    pub tests: Vec<TestStats>,
+    /// Miscompared bytes in total.
+    pub errors: u64,
+    /// Bits that read back flipped, in total.
+    pub flipped_bits: u64,
+    /// Cache lines that read back wrong, once per sweep that read them.
+    pub corrupted_lines: u64,
+    pub ecc_corrected: u64,
+    pub ecc_uncorrected: u64,
+    /// The first miscompares, the rest are counted in `records_dropped`.
+    pub records: Vec<ErrorRecord>,
+    pub records_dropped: usize,
+    pub duration: Duration,
+    /// Seed of the random patterns, `seed()` of the session replays them.
+    pub seed: u64,
+    /// The measurements of a `benchmark()` session, None for test runs.
+    pub benchmark: Option<Benchmark>,
+}
+
+impl RunReport {
+    /// EXIT_ERRORS if errors were detected, EXIT_PASS otherwise.
+    pub fn exit_status(&self) -> i32 {
+        exit_status(self.errors, self.ecc_uncorrected)
+    }
+}
+
+/// Everything the reports need, collected while the run goes on.
+pub struct ReportBuilder {
+    json: Option<String>,
+    junit: Option<String>,
+    started: SystemTime,
+    timer: Instant,
+    hardware: Hardware,
+    plan: Vec<PlannedTest>,
+    tested_bytes: usize,
+    seed: u64,
+    benchmark: Option<Benchmark>,
+    results: Vec<TestStats>,
+    records: Vec<ErrorRecord>,
+    dropped: usize,
+}
+
+impl ReportBuilder {
+    /// A report written as JSON to `json` and/or as JUnit XML to `junit` when the run ends.
+    pub fn new(json: Option<&str>, junit: Option<&str>, info: &SystemInfo, isa: InstructionSet, threads: usize, total_ram: usize, tests: &[TestDefinition]) -> Self {
+        ReportBuilder {
+            json: json.map(str::to_string),
+            junit: junit.map(str::to_string),
+            started: SystemTime::now(),
+            timer: Instant::now(),
+            hardware: Hardware::new(info, isa, threads, total_ram),
+            plan: tests.iter().map(PlannedTest::new).collect(),
+            tested_bytes: 0,
+            seed: 0,
+            benchmark: None,
+            results: Vec::new(),
+            records: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Size of the tested memory, the largest block for runs that test varying sizes.
+    pub fn tested_bytes(&mut self, bytes: usize) {
+        self.tested_bytes = self.tested_bytes.max(bytes);
+    }
+
+    /// Seed of the run's random patterns.
+    pub fn seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// The measurements of a benchmark run.
+    pub fn benchmark(&mut self, benchmark: Benchmark) {
+        self.benchmark = Some(benchmark);
+    }
+
+    /// One test's run in `pass`: its duration, bandwidth in MB/s and miscompared bytes.
+    pub fn test_result(&mut self, test: &str, pass: usize, seconds: f64, bandwidth: f64, errors: u64) {
+        self.results.push(TestStats { test: test.to_string(), pass, seconds, bandwidth, errors });
+    }
+
+    pub fn records(&mut self, records: &[ErrorRecord], dropped: usize) {
+        let kept = records.len().min(RECORDS_MAX - self.records.len());
+        self.records.extend_from_slice(&records[..kept]);
+        self.dropped += dropped + records.len() - kept;
+    }
+
+    /// The report of the finished run, with `passes` complete passes and `errors` in total in
+    /// `flips` flipped bits and corrupted lines, written to the files it was created with.
+    pub fn finish(self, passes: usize, errors: u64, flips: (u64, u64), ecc_corrected: u64, ecc_uncorrected: u64, locked: bool) -> RunReport {
+        if let Some(path) = &self.junit {
+            write_report(path, &self.junit_xml());
+        }
+        let duration = self.timer.elapsed();
+        let (flipped_bits, corrupted_lines) = flips;
+        if let Some(path) = &self.json {
+            let report = JsonReport {
+                started: self.started,
+                duration_seconds: duration.as_secs_f64(),
+                hardware: &self.hardware,
+                plan: &self.plan,
+                tested_bytes: self.tested_bytes,
+                seed: self.seed,
+                complete_passes: passes,
+                results: &self.results,
+                errors,
+                flipped_bits,
+                corrupted_lines,
+                ecc_corrected,
+                ecc_uncorrected,
+                error_records: &self.records,
+                error_records_dropped: self.dropped,
+                exit_status: exit_status(errors, ecc_uncorrected),
+                benchmark: self.benchmark.as_ref(),
+            };
+            match serde_json::to_string(&report) {
+                Ok(json) => write_report(path, &format!("{}\n", json)),
+                Err(e) => error!("can't serialize the report: {}", e),
+            }
+        }
+        RunReport {
+            allocated_bytes: self.tested_bytes,
+            locked,
+            passes,
+            tests: self.results,
+            errors,
+            flipped_bits,
+            corrupted_lines,
+            ecc_corrected,
+            ecc_uncorrected,
+            records: self.records,
+            records_dropped: self.dropped,
+            duration,
+            seed: self.seed,
+            benchmark: self.benchmark,
+        }
+    }
+
+    // One <testcase> per test and pass, miscompares are its <failure> with the first records
+    fn junit_xml(&self) -> String {
+        let failures = self.results.iter().filter(|result| result.errors > 0).count();
+        let seconds: f64 = self.results.iter().map(|result| result.seconds).sum();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(xml, "<testsuites name=\"manganese\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            self.results.len(), failures, seconds);
+        let _ = writeln!(xml, "  <testsuite name=\"manganese\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\" timestamp=\"{}\">",
+            self.results.len(), failures, seconds, iso_time(self.started));
+        for result in &self.results {
+            let _ = write!(xml, "    <testcase name=\"{}\" classname=\"manganese.pass{}\" time=\"{:.3}\"",
+                xml_escape(&result.test), result.pass, result.seconds);
+            if result.errors == 0 {
+                xml.push_str("/>\n");
+                continue;
+            }
+            let _ = writeln!(xml, ">\n      <failure message=\"{} miscompared bytes\" type=\"miscompare\">", result.errors);
+            let records = self.records.iter().filter(|record| record.test == result.test && record.pass == result.pass);
+            for record in records.take(FAILURE_RECORDS_MAX) {
+                let _ = writeln!(xml, "offset 0x{:016x}: expected 0x{:016x}, actual 0x{:016x} [xor 0x{:016x}]",
+                    record.offset, record.expected, record.actual, record.bitmask);
+            }
+            xml.push_str("      </failure>\n    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+fn write_report(path: &str, contents: &str) {
+    match std::fs::write(path, contents) {
+        Ok(()) => info!("Report written to {}", path),
+        Err(e) => error!("can't write the report to {}: {}", path, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn junit_timestamp_and_escaping() {
+        assert_eq!(iso_time(UNIX_EPOCH), "1970-01-01T00:00:00");
+        assert_eq!(iso_time(UNIX_EPOCH + Duration::from_secs(1709294400 + 3661)), "2024-03-01T13:01:01");
+        assert_eq!(iso_time(UNIX_EPOCH + Duration::from_secs(951782400)), "2000-02-29T00:00:00");
+        assert_eq!(xml_escape("a<b & \"c\">\u{1}"), "a&lt;b &amp; &quot;c&quot;&gt;");
+    }
+}
@@ -0,0 +1,48 @@
+// Self-test of the error detection and reporting path: a test is run with a bit flipped at known
+// offsets right before each of its verify passes (see `inject_faults()`) and has to report
+// miscompares at exactly those offsets, so a "0 errors" result of the real run can be trusted.
+// The injected miscompares are not added to the run's error count.
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering;
+use log::{error, info};
+use crate::tests::{TestContext, TestDefinition};
+
+const FAULTS: usize = 8;
+
+/// Runs `test` on `mem[..size]` with faults injected across the `threads` chunks of whole
+/// `width`-byte vectors, returns whether exactly the injected offsets were reported.
+pub unsafe fn run_self_test(test: &TestDefinition, mem: *mut u8, size: usize, threads: usize, width: usize, ctx: &TestContext) -> bool {
+    // one fault per span of the chunked part of the region, cache line aligned so that it is
+    // the start of a vector of every backend
+    let span = size / threads / width * width * threads / FAULTS / 64 * 64;
+    if span == 0 {
+        error!("Self-test: region too small to inject {} faults", FAULTS);
+        return false;
+    }
+    let offsets: Vec<usize> = (0..FAULTS).map(|k| k * span + span / 2 / 64 * 64).collect();
+
+    info!("Self-test: running {} with {} injected faults, expect them to be reported", test.name, FAULTS);
+    let run = ctx.run;
+    let errors = run.errors.load(Ordering::Relaxed);
+    let flips = run.flip_totals();
+    run.arm_faults(offsets.clone());
+    (test.run)(mem, size, ctx);
+    let detected = run.detected_faults();
+    run.arm_faults(Vec::new());
+    run.errors.store(errors, Ordering::Relaxed);
+    run.set_flip_totals(flips);
+    run.take_first_error();
+
+    let injected: BTreeSet<usize> = offsets.into_iter().collect();
+    for offset in injected.difference(&detected) {
+        error!("Self-test: fault injected at offset 0x{:016x} was not reported", offset);
+    }
+    for offset in detected.difference(&injected) {
+        error!("Self-test: miscompare reported at offset 0x{:016x} where no fault was injected", offset);
+    }
+    if detected != injected {
+        return false;
+    }
+    info!("Self-test passed: all {} injected faults reported at their offsets", FAULTS);
+    true
+}
@@ -0,0 +1,364 @@
+// The embedding API: a `TestSession` is configured with `TestSession::builder()`, started on a
+// thread of its own and then paused, stopped and joined for its report, so front ends (the CLI,
+// the GUI, anything else) drive a run the same way without blocking on it.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use crate::metrics::Subscribers;
+use crate::progress::{ProgressCallback, RunProgress};
+use crate::tests::Pause;
+use crate::{parse_ram_spec, run_tests, sysinfo, Affinity, CustomConfig, ErrorDetail, Metric, Prefetch, Profile, Progress, RunError, RunLimits, RunReport, TestEvent};
+
+/// How much memory to test.
+#[derive(Debug, Clone)]
+pub enum RamSpec {
+    Bytes(usize),
+    /// As on the command line, see `parse_ram_spec()`: "80%", "4GiB", "free-2GiB" ...
+    Spec(String),
+}
+
+impl From<usize> for RamSpec {
+    fn from(bytes: usize) -> Self { RamSpec::Bytes(bytes) }
+}
+
+impl From<&str> for RamSpec {
+    fn from(spec: &str) -> Self { RamSpec::Spec(spec.to_string()) }
+}
+
+impl From<String> for RamSpec {
+    fn from(spec: String) -> Self { RamSpec::Spec(spec) }
+}
+
+/// Everything a run is configured with but the memory size.
+#[derive(Default)]
+pub(crate) struct RunOptions {
+    pub hide_serials: bool,
+    pub pinned_gpu: bool,
+    pub shuffle: bool,
+    pub scrub_on_exit: bool,
+    pub write_combining: bool,
+    pub self_test: bool,
+    pub hugepages: bool,
+    pub numa: bool,
+    pub allow_unlocked: bool,
+    pub cycle: bool,
+    pub pin: Option<Affinity>,
+    pub threads: Option<usize>,
+    pub output_json: Option<String>,
+    pub output_junit: Option<String>,
+    pub limits: RunLimits,
+    pub config: CustomConfig,
+    pub profile: Option<Profile>,
+    pub only_tests: Vec<String>,
+    pub skip_tests: Vec<String>,
+    pub dump_on_error: Option<String>,
+    pub dump_window: usize,
+    pub checkpoint: Option<String>,
+    pub resume: bool,
+    pub seed: Option<u64>,
+    pub benchmark: bool,
+    pub l3_domains: bool,
+    pub cross_domains: bool,
+    pub prefetch: Option<Prefetch>,
+}
+
+/// Configuration of a `TestSession`, see `TestSession::builder()`.
+pub struct TestSessionBuilder {
+    ram: RamSpec,
+    options: RunOptions,
+    progress: Option<ProgressCallback>,
+}
+
+impl TestSessionBuilder {
+    /// Memory to test, 80% of the free RAM by default.
+    pub fn ram(mut self, ram: impl Into<RamSpec>) -> Self {
+        self.ram = ram.into();
+        self
+    }
+
+    /// Worker threads, one per logical CPU by default (or `threads=` of the config).
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.options.threads = Some(threads);
+        self
+    }
+
+    /// The test plan (and defaults such as the limits) to run, the default config otherwise.
+    pub fn tests(mut self, plan: CustomConfig) -> Self {
+        self.options.config = plan;
+        self
+    }
+
+    /// A built-in profile in place of the tests of the plan.
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.options.profile = Some(profile);
+        self
+    }
+
+    /// Run only these tests of the plan, by name.
+    pub fn only_tests(mut self, names: &[String]) -> Self {
+        self.options.only_tests = names.to_vec();
+        self
+    }
+
+    /// Leave these tests of the plan out, by name.
+    pub fn skip_tests(mut self, names: &[String]) -> Self {
+        self.options.skip_tests = names.to_vec();
+        self
+    }
+
+    /// Called from the run's thread whenever a pass, test or loop starts, and a test ends.
+    pub fn progress(mut self, callback: impl FnMut(&Progress) + Send + 'static) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Passes, duration and error threshold that end the run, before the ones of the plan.
+    pub fn limits(mut self, limits: RunLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    pub fn hide_serials(mut self, hide: bool) -> Self {
+        self.options.hide_serials = hide;
+        self
+    }
+
+    /// Register the memory with the CUDA runtime, needs the `pinned-gpu` feature.
+    pub fn pinned_gpu(mut self, pinned_gpu: bool) -> Self {
+        self.options.pinned_gpu = pinned_gpu;
+        self
+    }
+
+    /// Random test order and chunk assignment.
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.options.shuffle = shuffle;
+        self
+    }
+
+    /// Zero the memory before releasing it.
+    pub fn scrub_on_exit(mut self, scrub: bool) -> Self {
+        self.options.scrub_on_exit = scrub;
+        self
+    }
+
+    /// Rerun the streaming tests on a write-combining mapping after every loop.
+    pub fn write_combining(mut self, write_combining: bool) -> Self {
+        self.options.write_combining = write_combining;
+        self
+    }
+
+    /// Check that errors are detected before testing.
+    pub fn self_test(mut self, self_test: bool) -> Self {
+        self.options.self_test = self_test;
+        self
+    }
+
+    pub fn hugepages(mut self, hugepages: bool) -> Self {
+        self.options.hugepages = hugepages;
+        self
+    }
+
+    /// One region and pinned thread pool per NUMA node.
+    pub fn numa(mut self, numa: bool) -> Self {
+        self.options.numa = numa;
+        self
+    }
+
+    /// Test memory that couldn't be locked rather than fail.
+    pub fn allow_unlocked(mut self, allow: bool) -> Self {
+        self.options.allow_unlocked = allow;
+        self
+    }
+
+    /// One region and pinned thread pool per L3 cache domain (CCD), with the bandwidth of each.
+    pub fn l3_domains(mut self, l3_domains: bool) -> Self {
+        self.options.l3_domains = l3_domains;
+        self
+    }
+
+    /// Test every L3 domain's region from the CPUs of the next domain, see `l3_domains()`.
+    pub fn cross_domains(mut self, cross_domains: bool) -> Self {
+        self.options.cross_domains = cross_domains;
+        self
+    }
+
+    /// Borrow the free memory cycle by cycle instead of locking the RAM up front.
+    pub fn cycle(mut self, cycle: bool) -> Self {
+        self.options.cycle = cycle;
+        self
+    }
+
+    /// Software prefetching in the verify loops, calibrated when the run starts by default (or
+    /// `prefetch=` of the config).
+    pub fn prefetch(mut self, prefetch: Prefetch) -> Self {
+        self.options.prefetch = Some(prefetch);
+        self
+    }
+
+    pub fn pin(mut self, affinity: Affinity) -> Self {
+        self.options.pin = Some(affinity);
+        self
+    }
+
+    /// Write a JSON report to `path` when the run ends.
+    pub fn output_json(mut self, path: &str) -> Self {
+        self.options.output_json = Some(path.to_string());
+        self
+    }
+
+    /// Write a JUnit XML report to `path` when the run ends.
+    pub fn output_junit(mut self, path: &str) -> Self {
+        self.options.output_junit = Some(path.to_string());
+        self
+    }
+
+    /// Write the memory within `window` bytes of the first miscompare to `path`.
+    pub fn dump_on_error(mut self, path: &str, window: usize) -> Self {
+        self.options.dump_on_error = Some(path.to_string());
+        self.options.dump_window = window;
+        self
+    }
+
+    /// Seed the random patterns with `seed` instead of a random one, to replay the patterns of an
+    /// earlier run (see `RunReport::seed`).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    /// Write the progress of the run to the state file at `path` after every test, for `resume()`.
+    pub fn checkpoint(mut self, path: &str) -> Self {
+        self.options.checkpoint = Some(path.to_string());
+        self
+    }
+
+    /// Go on with the run checkpointed to the state file, or start a new one if there is none.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.options.resume = resume;
+        self
+    }
+
+    /// Measure the bandwidth and latency of the memory instead of testing it, see `RunReport::benchmark`.
+    pub fn benchmark(mut self, benchmark: bool) -> Self {
+        self.options.benchmark = benchmark;
+        self
+    }
+
+    pub fn build(self) -> TestSession {
+        TestSession {
+            setup: Some(self),
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            pause: Arc::new(Pause::default()),
+            progress: Arc::default(),
+            metrics: Arc::default(),
+            handle: None,
+        }
+    }
+}
+
+/// A run of the tests on a thread of its own.
+pub struct TestSession {
+    // taken by start()
+    setup: Option<TestSessionBuilder>,
+    stop_signal: Arc<AtomicBool>,
+    pause: Arc<Pause>,
+    progress: Arc<RunProgress>,
+    metrics: Arc<Subscribers>,
+    handle: Option<JoinHandle<Result<RunReport, RunError>>>,
+}
+
+impl TestSession {
+    pub fn builder() -> TestSessionBuilder {
+        TestSessionBuilder {
+            ram: RamSpec::Spec("80%".to_string()),
+            options: RunOptions { dump_window: 4096, ..RunOptions::default() },
+            progress: None,
+        }
+    }
+
+    /// Start the run; RunError::Config if the RAM spec is invalid or it was started before.
+    pub fn start(&mut self) -> Result<(), RunError> {
+        let Some(setup) = self.setup.take() else {
+            return Err(RunError::Config("the test session was started before".to_string()));
+        };
+        let sys = sysinfo();
+        let ram_bytes = match &setup.ram {
+            RamSpec::Bytes(bytes) => *bytes,
+            RamSpec::Spec(spec) => parse_ram_spec(spec, sys.totalram, sys.freeram)
+                .ok_or_else(|| RunError::Config(format!("Invalid RAM specification: \"{}\"", spec)))?,
+        };
+        let stop_signal = self.stop_signal.clone();
+        let pause = self.pause.clone();
+        let (progress, metrics) = (self.progress.clone(), self.metrics.clone());
+        self.handle = Some(std::thread::spawn(move || {
+            progress.set_callback(setup.progress);
+            let result = run_tests(ram_bytes, setup.options, &stop_signal, &pause, progress.clone(), metrics);
+            progress.set_callback(None);
+            pause.set(false);
+            result
+        }));
+        Ok(())
+    }
+
+    /// Hold the run after the running sweep, the memory stays locked.
+    pub fn pause(&self) {
+        self.pause.set(true);
+    }
+
+    pub fn resume(&self) {
+        self.pause.set(false);
+    }
+
+    pub fn paused(&self) -> bool {
+        self.pause.is_set()
+    }
+
+    /// End the run after the running sweep, without waiting for it.
+    pub fn stop(&self) {
+        self.stop_signal.store(true, Ordering::SeqCst);
+    }
+
+    pub fn stopping(&self) -> bool {
+        self.stop_signal.load(Ordering::SeqCst)
+    }
+
+    /// The flag `stop()` sets, for signal handlers.
+    pub fn stop_signal(&self) -> Arc<AtomicBool> {
+        self.stop_signal.clone()
+    }
+
+    /// The progress of the run, None before its first test started.
+    pub fn progress(&self) -> Option<Progress> {
+        self.progress.progress()
+    }
+
+    /// Receive the metrics of the run from now on, in place of any earlier receiver.
+    pub fn subscribe_metrics(&self) -> Receiver<Metric> {
+        self.metrics.subscribe_metrics()
+    }
+
+    /// Receive the miscompares of the run from now on, in place of any earlier receiver.
+    pub fn subscribe_errors(&self) -> Receiver<ErrorDetail> {
+        self.metrics.subscribe_errors()
+    }
+
+    /// Receive the events of the run from now on, in place of any earlier receiver.
+    pub fn subscribe_events(&self) -> Receiver<TestEvent> {
+        self.metrics.subscribe_events()
+    }
+
+    /// Whether the run has ended and `join()` returns right away.
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_some_and(JoinHandle::is_finished)
+    }
+
+    /// Wait for the run to end and return its report, a panic of the run goes on here. A session
+    /// that was never started runs now.
+    pub fn join(mut self) -> Result<RunReport, RunError> {
+        if self.handle.is_none() {
+            self.start()?;
+        }
+        self.handle.take().unwrap().join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
@@ -0,0 +1,68 @@
+// SGEMM compute + memory stress test, shared by all instruction set backends.
+// Every worker carves its chunk into tiles of three N×N f32 matrices, fills A and B with small
+// integers and computes C = A·B with matrixmultiply's runtime-dispatched SIMD kernels. Small
+// integer inputs keep every product and partial sum exactly representable, so the row sums of C
+// can be checked exactly against A·(B·1) without a second multiplication.
+use log::error;
+use crate::tests::{log_miscompare, miscompared_bytes, record_error, stop_requested, TestContext};
+
+const N: usize = 512;
+const TILE_SIZE: usize = 3 * N * N * size_of::<f32>();
+
+pub const SGEMM_ROUNDS: usize = 4;
+
+// Deterministic matrix element in -2.0..=2.0 (splitmix64 finalizer over seed and index)
+fn element(seed: u64, i: usize) -> f32 {
+    let mut x = seed ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^= x >> 31;
+    (x % 5) as f32 - 2.0
+}
+
+// Runs one round over the chunk starting at `start`, returns the bytes of the checksums of the
+// rows of C that did not match, at least one per row. Chunks smaller than one tile are left untouched.
+pub unsafe fn sgemm_chunk(mem: *mut u8, start: usize, chunk_size: usize, seed: u64, ctx: &TestContext) -> u64 {
+    let mut errors = 0;
+    let mut b_row_sums = vec![0f32; N];
+
+    // a tile takes long enough to look at the stop signal before every one
+    for tile in (0..chunk_size / TILE_SIZE).take_while(|_| !stop_requested(ctx)) {
+        let base = start + tile * TILE_SIZE;
+        let a = mem.add(base) as *mut f32;
+        let b = a.add(N * N);
+        let c = b.add(N * N);
+        let seed = seed ^ (tile as u64).rotate_left(32);
+
+        for i in 0..N * N {
+            a.add(i).write(element(seed, i));
+            b.add(i).write(element(!seed, i));
+        }
+
+        matrixmultiply::sgemm(
+            N, N, N,
+            1.0,
+            a, N as isize, 1,
+            b, N as isize, 1,
+            0.0,
+            c, N as isize, 1,
+        );
+
+        for (k, sum) in b_row_sums.iter_mut().enumerate() {
+            *sum = (0..N).map(|j| *b.add(k * N + j)).sum();
+        }
+        for i in 0..N {
+            let expected: f32 = (0..N).map(|k| *a.add(i * N + k) * b_row_sums[k]).sum();
+            let actual: f32 = (0..N).map(|j| *c.add(i * N + j)).sum();
+            if actual != expected {
+                if log_miscompare(ctx) {
+                    error!("errors detected at offset 0x{:016x}", base + 2 * N * N * size_of::<f32>() + i * N * size_of::<f32>());
+                }
+                record_error(base + 2 * N * N * size_of::<f32>() + i * N * size_of::<f32>(), expected.to_bits() as u64, actual.to_bits() as u64, ctx);
+                errors += miscompared_bytes(expected.to_bits() as u64, actual.to_bits() as u64).max(1);
+            }
+        }
+    }
+
+    errors
+}
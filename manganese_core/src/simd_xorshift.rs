@@ -13,7 +13,33 @@ pub struct Avx512Xorshift128PlusKey {
     pub part2: __m512i,
 }
 
-#[cfg(target_arch = "x86_64")]
+pub struct Xorshift128PlusKey {
+    pub part1: u64,
+    pub part2: u64,
+}
+
+/// Generator state of up to 8 lanes in memory, every lane a jump ahead of the one before, so a
+/// run's generator can be shared between the kernels of any instruction set.
+pub struct Xorshift128PlusLanes {
+    pub part1: [u64; 8],
+    pub part2: [u64; 8],
+}
+
+impl Xorshift128PlusLanes {
+    pub fn new(key1: u64, key2: u64) -> Self {
+        let mut lanes = Xorshift128PlusLanes { part1: [0; 8], part2: [0; 8] };
+        lanes.part1[0] = key1;
+        lanes.part2[0] = key2;
+        for i in 1..8 {
+            let (mut s0, mut s1) = (0, 0);
+            xorshift128plus_jump_onkeys(lanes.part1[i - 1], lanes.part2[i - 1], &mut s0, &mut s1);
+            lanes.part1[i] = s0;
+            lanes.part2[i] = s1;
+        }
+        lanes
+    }
+}
+
 fn xorshift128plus_onkeys(s0: &mut u64, s1: &mut u64) {
     let s1_val = *s0;
     let s0_val = *s1;
@@ -21,7 +47,6 @@ fn xorshift128plus_onkeys(s0: &mut u64, s1: &mut u64) {
     *s1 = s1_val ^ (s1_val << 23) ^ s0_val ^ (s1_val >> 18) ^ (s0_val >> 5);
 }
 
-#[cfg(target_arch = "x86_64")]
 fn xorshift128plus_jump_onkeys(in1: u64, in2: u64, output1: &mut u64, output2: &mut u64) {
     const JUMP: [u64; 2] = [0x8a5cd789635d2dff, 0x121fd2155c472f96];
     let mut s0 = 0u64;
@@ -74,6 +99,19 @@ pub unsafe fn avx_xorshift128plus(key: *mut AvxXorshift128PlusKey) -> __m256i {
     _mm256_add_epi64((*key).part2, s0)
 }
 
+/// `avx_xorshift128plus()` on the first 4 lanes of `lanes`.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx_xorshift128plus_lanes(lanes: &mut Xorshift128PlusLanes) -> __m256i {
+    let mut key = AvxXorshift128PlusKey {
+        part1: _mm256_loadu_si256(lanes.part1.as_ptr() as *const __m256i),
+        part2: _mm256_loadu_si256(lanes.part2.as_ptr() as *const __m256i),
+    };
+    let value = avx_xorshift128plus(&mut key);
+    _mm256_storeu_si256(lanes.part1.as_mut_ptr() as *mut __m256i, key.part1);
+    _mm256_storeu_si256(lanes.part2.as_mut_ptr() as *mut __m256i, key.part2);
+    value
+}
+
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
 pub unsafe fn avx512_xorshift128plus_init(key1: u64, key2: u64, key: *mut Avx512Xorshift128PlusKey) {
     let mut s0 = [0u64; 8];
@@ -108,3 +146,40 @@ pub unsafe fn avx512_xorshift128plus(key: *mut Avx512Xorshift128PlusKey) -> __m5
     _mm512_add_epi64((*key).part2, s0)
 }
 
+/// `avx512_xorshift128plus()` on all 8 lanes of `lanes`.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_xorshift128plus_lanes(lanes: &mut Xorshift128PlusLanes) -> __m512i {
+    let mut key = Avx512Xorshift128PlusKey {
+        part1: _mm512_loadu_si512(lanes.part1.as_ptr() as *const __m512i),
+        part2: _mm512_loadu_si512(lanes.part2.as_ptr() as *const __m512i),
+    };
+    let value = avx512_xorshift128plus(&mut key);
+    _mm512_storeu_si512(lanes.part1.as_mut_ptr() as *mut __m512i, key.part1);
+    _mm512_storeu_si512(lanes.part2.as_mut_ptr() as *mut __m512i, key.part2);
+    value
+}
+
+pub unsafe fn xorshift128plus_init(key1: u64, key2: u64, key: *mut Xorshift128PlusKey) {
+    (*key).part1 = key1;
+    (*key).part2 = key2;
+}
+
+pub unsafe fn xorshift128plus(key: *mut Xorshift128PlusKey) -> u64 {
+    let s1 = (*key).part1;
+    let s0 = (*key).part2;
+    (*key).part1 = s0;
+
+    let s1_new = s1 ^ (s1 << 23);
+    (*key).part2 = s1_new ^ s0 ^ (s1_new >> 18) ^ (s0 >> 5);
+
+    (*key).part2.wrapping_add(s0)
+}
+
+/// `xorshift128plus()` on the first lane of `lanes`.
+pub fn xorshift128plus_lanes(lanes: &mut Xorshift128PlusLanes) -> u64 {
+    let mut key = Xorshift128PlusKey { part1: lanes.part1[0], part2: lanes.part2[0] };
+    let value = unsafe { xorshift128plus(&mut key) };
+    lanes.part1[0] = key.part1;
+    lanes.part2[0] = key.part2;
+    value
+}
@@ -1,22 +1,394 @@
-use std::collections::HashMap;
-use std::sync::atomic::AtomicU64;
-use log::error;
-use crate::hardware::InstructionSet;
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use log::{error, info, warn};
+use crate::affinity::sibling_thread_pool;
+use crate::dump::ErrorDump;
+use crate::metrics::Subscribers;
+use crate::progress::RunProgress;
+use crate::hardware::{hardware_temperatures, InstructionSet};
+use crate::records::RecordQueue;
+use crate::sgemm::SGEMM_ROUNDS;
+use crate::simd_xorshift::{xorshift128plus_lanes, Xorshift128PlusLanes};
 use crate::tests_avx2::*;
 use crate::tests_avx512::*;
+use crate::tests_neon::*;
+use crate::tests_scalar::*;
 
 #[derive(Clone)]
 pub struct TestDefinition {
     pub name: &'static str,
     pub passes: usize,
     pub iters: usize,
-    pub run: unsafe fn(*mut u8, usize),
+    pub run: unsafe fn(*mut u8, usize, &TestContext),
     pub loops: usize,
+    pub options: TestOptions,
+}
+
+/// What the test kernels see of the test they run, handed to every `TestDefinition::run`.
+pub struct TestContext<'a> {
+    /// Chunks the kernels split the memory into, one per thread, see `tests_configure()`.
+    pub cpus: usize,
+    /// Offset of the memory the kernels run on in the tested region, added to the offsets they
+    /// record, see `run_test()`.
+    pub base: usize,
+    /// Options of the running test, see `tests_configure()`.
+    pub options: TestOptions,
+    /// The run the test is part of.
+    pub run: &'a RunState<'a>,
+}
+
+impl<'a> TestContext<'a> {
+    /// A context for `run`, on all of its threads and with the default options.
+    pub fn new(run: &'a RunState<'a>) -> Self {
+        TestContext { cpus: run.threads, base: 0, options: TestOptions::default(), run }
+    }
+}
+
+/// State of one run, shared by its tests and the threads running them. Every run has its own, so
+/// runs side by side don't see each other's errors, records, progress, pauses or stop requests.
+pub struct RunState<'a> {
+    /// Miscompared bytes are counted here.
+    pub errors: AtomicU64,
+    /// Records of the miscompares, drained after every test run.
+    pub records: RecordQueue,
+    /// Dump of the first miscompare, if armed.
+    pub dump: ErrorDump,
+    /// Progress and measurements of the run, held by its session too, see `observed_by()`.
+    pub progress: Arc<RunProgress>,
+    pub metrics: Arc<Subscribers>,
+    /// The run's random patterns, one generator per vector lane.
+    pub rng: Mutex<Xorshift128PlusLanes>,
+    /// What the generator was seeded with, the same patterns for the same seed.
+    pub seed: u64,
+    /// Set when the run is asked to stop, see `stop_requested()`.
+    pub stop_signal: &'a AtomicBool,
+    // held by the session, see `wait_while_paused()`
+    pause: &'a Pause,
+    // threads of the run and populated memory channels
+    threads: usize,
+    channels: usize,
+    // state of the generator of `shuffle()`, 0 without shuffle mode
+    shuffle: AtomicU64,
+    // bytes the verify loops prefetch ahead, 0 for none
+    prefetch: AtomicUsize,
+    // sweeps handed out so far, see `chunk_order()`
+    sweep: AtomicUsize,
+    // miscompares of the current test so far, the first LOGGED_MISCOMPARES of them logged
+    miscompares: AtomicUsize,
+    flipped_bits: AtomicU64,
+    bad_lines: AtomicU64,
+    first_error: AtomicUsize,
+    // slots of `error_offsets` handed out since the last take, checked before locking it
+    error_offset_slots: AtomicUsize,
+    error_offsets: Mutex<Vec<usize>>,
+    // whether `injected` has offsets, so miscompares don't lock it outside of the self-test
+    faults_armed: AtomicBool,
+    injected: Mutex<Vec<usize>>,
+    detected: Mutex<BTreeSet<usize>>,
+    // limits of the run that end a test early like a stop request, see `set_limits()`; u64::MAX
+    // without one, the deadline in nanoseconds after `created`
+    max_errors: AtomicU64,
+    deadline: AtomicU64,
+    created: Instant,
+}
+
+impl<'a> RunState<'a> {
+    /// A run on `threads` threads of a machine with `channels` populated memory channels, with
+    /// the generator seeded from `seed`.
+    pub fn new(threads: usize, channels: usize, seed: u64, stop_signal: &'a AtomicBool, pause: &'a Pause) -> Self {
+        // splitmix64 spreads the seed over both halves of the state; it is a bijection, so the
+        // two halves can't both be zero
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let z = (state ^ (state >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            let z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        let (r1, r2) = (next(), next());
+        RunState {
+            errors: AtomicU64::new(0),
+            records: RecordQueue::default(),
+            dump: ErrorDump::default(),
+            progress: Arc::default(),
+            metrics: Arc::default(),
+            rng: Mutex::new(Xorshift128PlusLanes::new(r1, r2)),
+            seed,
+            stop_signal,
+            pause,
+            threads,
+            channels: channels.max(1),
+            shuffle: AtomicU64::new(0),
+            prefetch: AtomicUsize::new(0),
+            sweep: AtomicUsize::new(0),
+            miscompares: AtomicUsize::new(0),
+            flipped_bits: AtomicU64::new(0),
+            bad_lines: AtomicU64::new(0),
+            first_error: AtomicUsize::new(usize::MAX),
+            error_offset_slots: AtomicUsize::new(0),
+            error_offsets: Mutex::new(Vec::new()),
+            faults_armed: AtomicBool::new(false),
+            injected: Mutex::new(Vec::new()),
+            detected: Mutex::new(BTreeSet::new()),
+            max_errors: AtomicU64::new(u64::MAX),
+            deadline: AtomicU64::new(u64::MAX),
+            created: Instant::now(),
+        }
+    }
+
+    /// Report the progress and measurements of the run to `progress` and `metrics`, a session's.
+    pub fn observed_by(self, progress: Arc<RunProgress>, metrics: Arc<Subscribers>) -> Self {
+        RunState { progress, metrics, ..self }
+    }
+
+    /// End the running test early, as a stop request does, once the run has `max_errors` errors
+    /// or at `deadline`.
+    pub fn set_limits(&self, max_errors: Option<u64>, deadline: Option<Instant>) {
+        let deadline = deadline.map_or(u64::MAX, |deadline| deadline.saturating_duration_since(self.created).as_nanos() as u64);
+        self.max_errors.store(max_errors.unwrap_or(u64::MAX), Ordering::Relaxed);
+        self.deadline.store(deadline, Ordering::Relaxed);
+    }
+
+    /// Whether the run has been asked to stop or reached one of the limits of `set_limits()`.
+    pub fn stop_requested(&self) -> bool {
+        self.stop_signal.load(Ordering::SeqCst)
+            || self.errors.load(Ordering::Relaxed) >= self.max_errors.load(Ordering::Relaxed)
+            || self.created.elapsed().as_nanos() as u64 >= self.deadline.load(Ordering::Relaxed)
+    }
+
+    /// Enable shuffle mode: randomized test order (see `shuffle()`) and a fresh random
+    /// thread-to-chunk assignment for every sweep (see `chunk_order()`).
+    pub fn set_shuffle(&self, enabled: bool) {
+        use std::hash::{BuildHasher, RandomState};
+        let state = if enabled { RandomState::new().hash_one(Instant::now()) | 1 } else { 0 };
+        self.shuffle.store(state, Ordering::Relaxed);
+    }
+
+    /// Fisher-Yates shuffle of `items` in place.
+    pub fn shuffle<T>(&self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let mut x = self.shuffle.load(Ordering::Relaxed);
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.shuffle.store(x, Ordering::Relaxed);
+            items.swap(i, (x % (i as u64 + 1)) as usize);
+        }
+    }
+
+    /// Make the verify loops prefetch `distance` bytes ahead, none with 0.
+    pub fn set_prefetch_distance(&self, distance: usize) {
+        self.prefetch.store(distance, Ordering::Relaxed);
+    }
+
+    /// Threads `test` runs on: its `threads=` option, capped at the threads of the run.
+    pub fn test_threads(&self, test: &TestDefinition) -> usize {
+        match test.options.threads {
+            0 => self.threads,
+            n => n.min(self.threads),
+        }
+    }
+
+    /// Log how many miscompares of `test` weren't logged one by one, if any.
+    pub fn log_unlogged_miscompares(&self, test: &str) {
+        let unlogged = self.miscompares.load(Ordering::Relaxed).saturating_sub(LOGGED_MISCOMPARES);
+        if unlogged > 0 {
+            error!("... {} more miscompares in {} not logged, the error records have them all", unlogged, test);
+        }
+    }
+
+    /// Flipped bits and corrupted cache lines of the run so far, in the same units whatever the
+    /// instruction set (the error count is in miscompared bytes). A line is counted once per sweep
+    /// that reads it wrong.
+    pub fn flip_totals(&self) -> (u64, u64) {
+        (self.flipped_bits.load(Ordering::Relaxed), self.bad_lines.load(Ordering::Relaxed))
+    }
+
+    /// Set the totals of `flip_totals()`, to leave out miscompares that aren't the run's own.
+    pub fn set_flip_totals(&self, (bits, lines): (u64, u64)) {
+        self.flipped_bits.store(bits, Ordering::Relaxed);
+        self.bad_lines.store(lines, Ordering::Relaxed);
+    }
+
+    /// Offsets of the first miscompares recorded since the last call.
+    pub fn take_error_offsets(&self) -> Vec<usize> {
+        let mut offsets = self.error_offsets.lock().unwrap();
+        self.error_offset_slots.store(0, Ordering::Relaxed);
+        std::mem::take(&mut *offsets)
+    }
+
+    /// Offset of the first miscompare recorded since the last call, if any.
+    pub fn take_first_error(&self) -> Option<usize> {
+        Some(self.first_error.swap(usize::MAX, Ordering::Relaxed)).filter(|&offset| offset != usize::MAX)
+    }
+
+    /// Arm the self-test: every verify pass corrupts `offsets` right before reading (see
+    /// `inject_faults()`), and all miscompare offsets are collected until it is disarmed again
+    /// with an empty list.
+    pub fn arm_faults(&self, offsets: Vec<usize>) {
+        self.faults_armed.store(!offsets.is_empty(), Ordering::Relaxed);
+        *self.injected.lock().unwrap() = offsets;
+        self.detected.lock().unwrap().clear();
+    }
+
+    /// Miscompare offsets recorded since the self-test was armed.
+    pub fn detected_faults(&self) -> BTreeSet<usize> {
+        self.detected.lock().unwrap().clone()
+    }
+}
+
+/// The pause switch of a run, shared between the session and the run's tests: while it is set,
+/// every sweep over the memory waits before it starts, the memory staying allocated and locked.
+#[derive(Default)]
+pub struct Pause {
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl Pause {
+    /// Hold the run before its next sweep, or let it go on with `false`.
+    pub fn set(&self, paused: bool) {
+        *self.paused.lock().unwrap() = paused;
+        self.resumed.notify_all();
+    }
+
+    /// Whether the run is paused.
+    pub fn is_set(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+}
+
+// RDRAND where the kernels need AVX2 anyway, otherwise RandomState, which is seeded from the OS
+fn random_seed(isa: InstructionSet) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    if matches!(isa, InstructionSet::AVX2 | InstructionSet::AVX512) {
+        use std::arch::x86_64::_rdrand64_step;
+        let mut seed = 0u64;
+        unsafe { _rdrand64_step(&mut seed); }
+        return seed;
+    }
+    let _ = isa;
+    use std::hash::{BuildHasher, RandomState};
+    RandomState::new().hash_one(1u64)
+}
+
+/// Per-test knobs that can be overridden from the config file.
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    /// Idle time in seconds between writing and verifying (retention tests only).
+    pub sleep: u64,
+    /// How the pattern writes reach DRAM.
+    pub cache: CacheMode,
+    /// 64-bit patterns to test (custom patterns test only).
+    pub patterns: Vec<u64>,
+    /// Time in milliseconds spent hammering each window (refresh stress test only).
+    pub dwell: u64,
+    /// Channel interleave granularity in bytes (channel stripes test only).
+    pub interleave: usize,
+    /// Random reads issued per run of sequential writes, as (reads, writes) (mixed read/write test only).
+    pub ratio: (usize, usize),
+    /// Length in seconds of every full-power phase (thermal cycle test only).
+    pub heat: u64,
+    /// Fill and verify only every Nth cache line, 0 and 1 cover all lines (pattern fill/verify passes only).
+    pub stride: usize,
+    /// How the fill and verify passes split the region among the threads.
+    pub partition: Partition,
+    /// Threads running the test, at most those of the run; 0 for all of them.
+    pub threads: usize,
+    /// Whether the test's write and verify sweeps may overlap, see `run_test()`.
+    pub phases: Phases,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Non-temporal streaming stores that bypass the cache hierarchy.
+    #[default]
+    Bypass,
+    /// Regular cached stores, written back with explicit cache line flush sweeps
+    /// after every full-region write.
+    Cached,
+}
+
+impl CacheMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "bypass" => Some(CacheMode::Bypass),
+            "cached" => Some(CacheMode::Cached),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Partition {
+    /// Every thread walks one contiguous chunk of the region.
+    #[default]
+    Contiguous,
+    /// Thread i takes every Nth cache line starting at line i, N being the thread count,
+    /// so all threads stream through the same rows and banks at once.
+    Interleaved,
+}
+
+impl Partition {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "contiguous" => Some(Partition::Contiguous),
+            "interleaved" => Some(Partition::Interleaved),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Phases {
+    /// Every sweep over the region ends before the next one starts.
+    #[default]
+    Strict,
+    /// Double buffering: the two halves of the region are tested at once by half of the threads
+    /// each, the second half one sweep behind, so that one half is written while the other one
+    /// is verified.
+    Overlapped,
+}
+
+impl Phases {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "strict" => Some(Phases::Strict),
+            "overlapped" => Some(Phases::Overlapped),
+            _ => None,
+        }
+    }
+}
+
+/// Software prefetching in the verify loops, see `prefetch_ahead()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Prefetch {
+    /// The distance `calibrate_prefetch()` finds fastest when the run starts.
+    #[default]
+    Auto,
+    /// Demand loads only.
+    Off,
+    /// Prefetch this many bytes ahead, a multiple of the cache line.
+    Distance(usize),
+}
+
+impl Prefetch {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(Prefetch::Auto),
+            "off" | "0" => Some(Prefetch::Off),
+            _ => s.parse::<usize>().ok().filter(|v| v.is_multiple_of(64) && *v <= PREFETCH_MAX).map(Prefetch::Distance),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TestKind {
     BasicTests,
+    March,
     RandomInversions,
     MovingInversionsLeft64,
     MovingInversionsRight32,
@@ -30,6 +402,29 @@ pub enum TestKind {
     Checkerboard,
     AntiPatterns,
     InverseDataPatterns,
+    RowHammer,
+    BitFade,
+    Modulo20,
+    BlockMove,
+    RandomAddress,
+    PrimeStride,
+    Addressing,
+    AddressLine,
+    Sgemm,
+    CustomPatterns,
+    Walking1Line,
+    Walking0Line,
+    RefreshStress,
+    LfsrFill,
+    ChannelStripes,
+    CoherencePingPong,
+    PointerChase,
+    MixedRw,
+    ThermalCycle,
+    ByteLanes,
+    Butterfly,
+    GatherScatter,
+    CrossCheck,
 }
 
 impl TestKind {
@@ -37,6 +432,7 @@ impl TestKind {
         use TestKind::*;
         Some(match s {
             "basic_tests" => BasicTests,
+            "march" => March,
             "random_inversions" => RandomInversions,
             "moving_inversions_left_64" => MovingInversionsLeft64,
             "moving_inversions_right_32" => MovingInversionsRight32,
@@ -50,16 +446,42 @@ impl TestKind {
             "checkerboard" => Checkerboard,
             "anti_patterns" => AntiPatterns,
             "inverse_data_patterns" => InverseDataPatterns,
+            "rowhammer" => RowHammer,
+            "bit_fade" => BitFade,
+            "modulo_20" => Modulo20,
+            "block_move" => BlockMove,
+            "random_address" => RandomAddress,
+            "prime_stride" => PrimeStride,
+            "addressing" => Addressing,
+            "address_line" => AddressLine,
+            "sgemm" => Sgemm,
+            "custom_patterns" => CustomPatterns,
+            "walking_1_line" => Walking1Line,
+            "walking_0_line" => Walking0Line,
+            "refresh_stress" => RefreshStress,
+            "lfsr_fill" => LfsrFill,
+            "channel_stripes" => ChannelStripes,
+            "coherence_ping_pong" => CoherencePingPong,
+            "pointer_chase" => PointerChase,
+            "mixed_rw" => MixedRw,
+            "thermal_cycle" => ThermalCycle,
+            "byte_lanes" => ByteLanes,
+            "butterfly" => Butterfly,
+            "gather_scatter" => GatherScatter,
+            "cross_check" => CrossCheck,
             _ => return None,
         })
     }
+
+    /// The config name of the test, whichever instruction sets implement it.
+    pub fn name(self) -> &'static str {
+        [avx512_definitions(), avx2_definitions(), neon_definitions(), scalar_definitions()].iter()
+            .find_map(|defs| defs.get(&self).map(|def| def.name))
+            .unwrap_or("unknown")
+    }
 }
 
 pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
-    //FIXME: segfaultsTest: Test { name: "March", passes: 17, iters: 2, run: avx2_march },
-    //FIXME: segfaults Test { name: "Addressing", passes: 2, iters: 16, run: avx2_addressing },
-    //FIXME: no openBLAS / other BLAS framework integration Test { name: "SGEMM", passes: 1, iters: 32, run: avx2_sgemm },
-    //FIXME: segfaults Test { name: "Address Line Test", passes: 2, iters: 1, run: avx2_address_line_test },
     use TestKind::*;
     HashMap::from([
         (BasicTests, TestDefinition {
@@ -68,6 +490,15 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 6,
             run: avx2_basic_tests,
             loops: 1,
+            options: TestOptions::default(),
+        }),
+        (March, TestDefinition {
+            name: "march",
+            passes: 10,
+            iters: 2,
+            run: avx2_march,
+            loops: 1,
+            options: TestOptions::default(),
         }),
         (RandomInversions, TestDefinition {
             name: "random_inversions",
@@ -75,6 +506,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 16,
             run: avx2_random_inversions,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsLeft64, TestDefinition {
             name: "moving_inversions_left_64",
@@ -82,6 +514,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 64,
             run: avx2_moving_inversions_left_64,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsRight32, TestDefinition {
             name: "moving_inversions_right_32",
@@ -89,6 +522,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 32,
             run: avx2_moving_inversions_right_32,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsLeft16, TestDefinition {
             name: "moving_inversions_left_16",
@@ -96,6 +530,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 16,
             run: avx2_moving_inversions_left_16,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsRight8, TestDefinition {
             name: "moving_inversions_right_8",
@@ -103,6 +538,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 8,
             run: avx2_moving_inversions_right_8,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsLeft4, TestDefinition {
             name: "moving_inversions_left_4",
@@ -110,6 +546,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 4,
             run: avx2_moving_inversions_left_4,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingSaturationsRight16, TestDefinition {
             name: "moving_saturations_right_16",
@@ -117,6 +554,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 16,
             run: avx2_moving_saturations_right_16,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingSaturationsLeft8, TestDefinition {
             name: "moving_saturations_left_8",
@@ -124,6 +562,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 8,
             run: avx2_moving_saturations_left_8,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (Walking1, TestDefinition {
             name: "walking1",
@@ -131,6 +570,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 64,
             run: avx2_walking_1,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (Walking0, TestDefinition {
             name: "walking0",
@@ -138,6 +578,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 64,
             run: avx2_walking_0,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (Checkerboard, TestDefinition {
             name: "checkerboard",
@@ -145,6 +586,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 1,
             run: avx2_checkerboard,
             loops: 8,
+            options: TestOptions::default(),
         }),
         (AntiPatterns, TestDefinition {
             name: "anti_patterns",
@@ -152,6 +594,7 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 34,
             run: avx2_anti_patterns,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (InverseDataPatterns, TestDefinition {
              name: "inverse_data_patterns",
@@ -159,15 +602,180 @@ pub fn avx2_definitions() -> HashMap<TestKind, TestDefinition> {
              iters: 14,
              run: avx2_inverse_data_patterns,
              loops: 1,
+             options: TestOptions::default(),
          }),
+        (RowHammer, TestDefinition {
+            name: "rowhammer",
+            passes: 2,
+            iters: 2,
+            run: avx2_rowhammer,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BitFade, TestDefinition {
+            name: "bit_fade",
+            passes: 2,
+            iters: 2,
+            run: avx2_bit_fade,
+            loops: 0,
+            options: TestOptions { sleep: 300, ..TestOptions::default() },
+        }),
+        (Modulo20, TestDefinition {
+            name: "modulo_20",
+            passes: 2,
+            iters: 20,
+            run: avx2_modulo_20,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BlockMove, TestDefinition {
+            name: "block_move",
+            passes: 6,
+            iters: 4,
+            run: avx2_block_move,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RandomAddress, TestDefinition {
+            name: "random_address",
+            passes: 2,
+            iters: 1,
+            run: avx2_random_address,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PrimeStride, TestDefinition {
+            name: "prime_stride",
+            passes: 2,
+            iters: 4,
+            run: avx2_prime_stride,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Addressing, TestDefinition {
+            name: "addressing",
+            passes: 2,
+            iters: 16,
+            run: avx2_addressing,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (AddressLine, TestDefinition {
+            name: "address_line",
+            passes: 2,
+            iters: 1,
+            run: avx2_address_line_test,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Sgemm, TestDefinition {
+            name: "sgemm",
+            passes: 1,
+            iters: SGEMM_ROUNDS,
+            run: avx2_sgemm,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (CustomPatterns, TestDefinition {
+            name: "custom_patterns",
+            passes: 4,
+            iters: 1,
+            run: avx2_custom_patterns,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking1Line, TestDefinition {
+            name: "walking_1_line",
+            passes: 2,
+            iters: 512,
+            run: avx2_walking_1_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking0Line, TestDefinition {
+            name: "walking_0_line",
+            passes: 2,
+            iters: 512,
+            run: avx2_walking_0_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (RefreshStress, TestDefinition {
+            name: "refresh_stress",
+            passes: 1,
+            iters: 1,
+            run: avx2_refresh_stress,
+            loops: 1,
+            options: TestOptions { dwell: 64, ..TestOptions::default() },
+        }),
+        (LfsrFill, TestDefinition {
+            name: "lfsr_fill",
+            passes: 2,
+            iters: 2,
+            run: avx2_lfsr_fill,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (ChannelStripes, TestDefinition {
+            name: "channel_stripes",
+            passes: 2,
+            iters: 4,
+            run: avx2_channel_stripes,
+            loops: 1,
+            options: TestOptions { interleave: 256, ..TestOptions::default() },
+        }),
+        (CoherencePingPong, TestDefinition {
+            name: "coherence_ping_pong",
+            passes: 1,
+            iters: 1,
+            run: avx2_coherence_ping_pong,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PointerChase, TestDefinition {
+            name: "pointer_chase",
+            passes: 1,
+            iters: 1,
+            run: avx2_pointer_chase,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MixedRw, TestDefinition {
+            name: "mixed_rw",
+            passes: 2,
+            iters: 4,
+            run: avx2_mixed_rw,
+            loops: 1,
+            options: TestOptions { ratio: (2, 1), ..TestOptions::default() },
+        }),
+        (ThermalCycle, TestDefinition {
+            name: "thermal_cycle",
+            passes: 2,
+            iters: 1,
+            run: avx2_thermal_cycle,
+            loops: 0,
+            options: TestOptions { sleep: 60, heat: 60, ..TestOptions::default() },
+        }),
+        (ByteLanes, TestDefinition {
+            name: "byte_lanes",
+            passes: 4,
+            iters: 64,
+            run: avx2_byte_lanes,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Butterfly, TestDefinition {
+            name: "butterfly",
+            passes: 4,
+            iters: 4,
+            run: avx2_butterfly,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
     ])
 }
 
 pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
-    //FIXME: segfaults Test: Test { name: "March", passes: 17, iters: 2, run: avx512_march },
-    //FIXME: segfaults Test: Test { name: "Addressing", passes: 4, iters: 16, run: avx512_addressing },
-    //FIXME: no openBLAS / other BLAS framework integration Test { name: "SGEMM", passes: 1, iters: 32, run: avx512_sgemm },
-    //FIXME: segfaults Test: Test { name: "Address Line Test", passes: 2, iters: 1, run: avx512_address_line_test },
     use TestKind::*;
     HashMap::from([
         (BasicTests, TestDefinition {
@@ -176,6 +784,15 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 6,
             run: avx512_basic_tests,
             loops: 1,
+            options: TestOptions::default(),
+        }),
+        (March, TestDefinition {
+            name: "march",
+            passes: 10,
+            iters: 2,
+            run: avx512_march,
+            loops: 1,
+            options: TestOptions::default(),
         }),
         (RandomInversions, TestDefinition {
             name: "random_inversions",
@@ -183,6 +800,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 16,
             run: avx512_random_inversions,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsLeft64, TestDefinition {
             name: "moving_inversions_left_64",
@@ -190,6 +808,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 64,
             run: avx512_moving_inversions_left_64,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsRight32, TestDefinition {
             name: "moving_inversions_right_32",
@@ -197,6 +816,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 32,
             run: avx512_moving_inversions_right_32,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsLeft16, TestDefinition {
             name: "moving_inversions_left_16",
@@ -204,6 +824,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 16,
             run: avx512_moving_inversions_left_16,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsRight8, TestDefinition {
             name: "moving_inversions_right_8",
@@ -211,6 +832,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 8,
             run: avx512_moving_inversions_right_8,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingInversionsLeft4, TestDefinition {
             name: "moving_inversions_left_4",
@@ -218,6 +840,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 4,
             run: avx512_moving_inversions_left_4,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingSaturationsRight16, TestDefinition {
             name: "moving_saturations_right_16",
@@ -225,6 +848,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 16,
             run: avx512_moving_saturations_right_16,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (MovingSaturationsLeft8, TestDefinition {
             name: "moving_saturations_left_8",
@@ -232,6 +856,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 8,
             run: avx512_moving_saturations_left_8,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (Walking1, TestDefinition {
             name: "walking1",
@@ -239,6 +864,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 64,
             run: avx512_walking_1,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (Walking0, TestDefinition {
             name: "walking0",
@@ -246,6 +872,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 64,
             run: avx512_walking_0,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (Checkerboard, TestDefinition {
             name: "checkerboard",
@@ -253,6 +880,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 1,
             run: avx512_checkerboard,
             loops: 8,
+            options: TestOptions::default(),
         }),
         (AntiPatterns, TestDefinition {
             name: "anti_patterns",
@@ -260,6 +888,7 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 34,
             run: avx512_anti_patterns,
             loops: 1,
+            options: TestOptions::default(),
         }),
         (InverseDataPatterns, TestDefinition {
             name: "inverse_data_patterns",
@@ -267,32 +896,1295 @@ pub fn avx512_definitions() -> HashMap<TestKind, TestDefinition> {
             iters: 14,
             run: avx512_inverse_data_patterns,
             loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RowHammer, TestDefinition {
+            name: "rowhammer",
+            passes: 2,
+            iters: 2,
+            run: avx512_rowhammer,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BitFade, TestDefinition {
+            name: "bit_fade",
+            passes: 2,
+            iters: 2,
+            run: avx512_bit_fade,
+            loops: 0,
+            options: TestOptions { sleep: 300, ..TestOptions::default() },
+        }),
+        (Modulo20, TestDefinition {
+            name: "modulo_20",
+            passes: 2,
+            iters: 20,
+            run: avx512_modulo_20,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BlockMove, TestDefinition {
+            name: "block_move",
+            passes: 6,
+            iters: 4,
+            run: avx512_block_move,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RandomAddress, TestDefinition {
+            name: "random_address",
+            passes: 2,
+            iters: 1,
+            run: avx512_random_address,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PrimeStride, TestDefinition {
+            name: "prime_stride",
+            passes: 2,
+            iters: 4,
+            run: avx512_prime_stride,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Addressing, TestDefinition {
+            name: "addressing",
+            passes: 4,
+            iters: 16,
+            run: avx512_addressing,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (AddressLine, TestDefinition {
+            name: "address_line",
+            passes: 2,
+            iters: 1,
+            run: avx512_address_line_test,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Sgemm, TestDefinition {
+            name: "sgemm",
+            passes: 1,
+            iters: SGEMM_ROUNDS,
+            run: avx512_sgemm,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (CustomPatterns, TestDefinition {
+            name: "custom_patterns",
+            passes: 4,
+            iters: 1,
+            run: avx512_custom_patterns,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking1Line, TestDefinition {
+            name: "walking_1_line",
+            passes: 2,
+            iters: 512,
+            run: avx512_walking_1_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking0Line, TestDefinition {
+            name: "walking_0_line",
+            passes: 2,
+            iters: 512,
+            run: avx512_walking_0_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (RefreshStress, TestDefinition {
+            name: "refresh_stress",
+            passes: 1,
+            iters: 1,
+            run: avx512_refresh_stress,
+            loops: 1,
+            options: TestOptions { dwell: 64, ..TestOptions::default() },
+        }),
+        (LfsrFill, TestDefinition {
+            name: "lfsr_fill",
+            passes: 2,
+            iters: 2,
+            run: avx512_lfsr_fill,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (ChannelStripes, TestDefinition {
+            name: "channel_stripes",
+            passes: 2,
+            iters: 4,
+            run: avx512_channel_stripes,
+            loops: 1,
+            options: TestOptions { interleave: 256, ..TestOptions::default() },
+        }),
+        (CoherencePingPong, TestDefinition {
+            name: "coherence_ping_pong",
+            passes: 1,
+            iters: 1,
+            run: avx512_coherence_ping_pong,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PointerChase, TestDefinition {
+            name: "pointer_chase",
+            passes: 1,
+            iters: 1,
+            run: avx512_pointer_chase,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MixedRw, TestDefinition {
+            name: "mixed_rw",
+            passes: 2,
+            iters: 4,
+            run: avx512_mixed_rw,
+            loops: 1,
+            options: TestOptions { ratio: (2, 1), ..TestOptions::default() },
+        }),
+        (ThermalCycle, TestDefinition {
+            name: "thermal_cycle",
+            passes: 2,
+            iters: 1,
+            run: avx512_thermal_cycle,
+            loops: 0,
+            options: TestOptions { sleep: 60, heat: 60, ..TestOptions::default() },
+        }),
+        (ByteLanes, TestDefinition {
+            name: "byte_lanes",
+            passes: 4,
+            iters: 64,
+            run: avx512_byte_lanes,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Butterfly, TestDefinition {
+            name: "butterfly",
+            passes: 4,
+            iters: 4,
+            run: avx512_butterfly,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (GatherScatter, TestDefinition {
+            name: "gather_scatter",
+            passes: 2,
+            iters: 1,
+            run: avx512_gather_scatter,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (CrossCheck, TestDefinition {
+            name: "cross_check",
+            passes: 2,
+            iters: 1,
+            run: avx512_cross_check,
+            loops: 1,
+            options: TestOptions::default(),
         }),
     ])
 }
 
-#[allow(dead_code)]
-pub fn get_test_definitions_for_isa(isa: InstructionSet) -> HashMap<TestKind, TestDefinition> {
-    match isa {
-        InstructionSet::AVX512 => {
-            avx512_definitions()
-        }
-        InstructionSet::AVX2 => {
-            avx2_definitions()
-        },
-        InstructionSet::SSE => HashMap::new(),
-    }
-}
-
-pub fn tests_init(cpus: usize, errors: &'static AtomicU64, isa: InstructionSet) {
-    match isa {
-        InstructionSet::AVX512 => {
-            unsafe { avx512_tests_init(cpus, errors); }
-        }
-        InstructionSet::AVX2 => {
-            unsafe { avx2_tests_init(cpus, errors); }
-        },
-        InstructionSet::SSE => error!("Unsupported instruction set: SSE"),
-    }
-}
-
+pub fn scalar_definitions() -> HashMap<TestKind, TestDefinition> {
+    use TestKind::*;
+    HashMap::from([
+        (BasicTests, TestDefinition {
+            name: "basic_tests",
+            passes: 4,
+            iters: 6,
+            run: scalar_basic_tests,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (March, TestDefinition {
+            name: "march",
+            passes: 10,
+            iters: 2,
+            run: scalar_march,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RandomInversions, TestDefinition {
+            name: "random_inversions",
+            passes: 4,
+            iters: 16,
+            run: scalar_random_inversions,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsLeft64, TestDefinition {
+            name: "moving_inversions_left_64",
+            passes: 4,
+            iters: 64,
+            run: scalar_moving_inversions_left_64,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsRight32, TestDefinition {
+            name: "moving_inversions_right_32",
+            passes: 4,
+            iters: 32,
+            run: scalar_moving_inversions_right_32,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsLeft16, TestDefinition {
+            name: "moving_inversions_left_16",
+            passes: 4,
+            iters: 16,
+            run: scalar_moving_inversions_left_16,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsRight8, TestDefinition {
+            name: "moving_inversions_right_8",
+            passes: 4,
+            iters: 8,
+            run: scalar_moving_inversions_right_8,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsLeft4, TestDefinition {
+            name: "moving_inversions_left_4",
+            passes: 4,
+            iters: 4,
+            run: scalar_moving_inversions_left_4,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingSaturationsRight16, TestDefinition {
+            name: "moving_saturations_right_16",
+            passes: 8,
+            iters: 16,
+            run: scalar_moving_saturations_right_16,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingSaturationsLeft8, TestDefinition {
+            name: "moving_saturations_left_8",
+            passes: 8,
+            iters: 8,
+            run: scalar_moving_saturations_left_8,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Walking1, TestDefinition {
+            name: "walking1",
+            passes: 4,
+            iters: 64,
+            run: scalar_walking_1,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Walking0, TestDefinition {
+            name: "walking0",
+            passes: 4,
+            iters: 64,
+            run: scalar_walking_0,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Checkerboard, TestDefinition {
+            name: "checkerboard",
+            passes: 4,
+            iters: 1,
+            run: scalar_checkerboard,
+            loops: 8,
+            options: TestOptions::default(),
+        }),
+        (AntiPatterns, TestDefinition {
+            name: "anti_patterns",
+            passes: 8,
+            iters: 34,
+            run: scalar_anti_patterns,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (InverseDataPatterns, TestDefinition {
+            name: "inverse_data_patterns",
+            passes: 4,
+            iters: 14,
+            run: scalar_inverse_data_patterns,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RowHammer, TestDefinition {
+            name: "rowhammer",
+            passes: 2,
+            iters: 2,
+            run: scalar_rowhammer,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BitFade, TestDefinition {
+            name: "bit_fade",
+            passes: 2,
+            iters: 2,
+            run: scalar_bit_fade,
+            loops: 0,
+            options: TestOptions { sleep: 300, ..TestOptions::default() },
+        }),
+        (Modulo20, TestDefinition {
+            name: "modulo_20",
+            passes: 2,
+            iters: 20,
+            run: scalar_modulo_20,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BlockMove, TestDefinition {
+            name: "block_move",
+            passes: 6,
+            iters: 4,
+            run: scalar_block_move,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RandomAddress, TestDefinition {
+            name: "random_address",
+            passes: 2,
+            iters: 1,
+            run: scalar_random_address,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PrimeStride, TestDefinition {
+            name: "prime_stride",
+            passes: 2,
+            iters: 4,
+            run: scalar_prime_stride,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Addressing, TestDefinition {
+            name: "addressing",
+            passes: 2,
+            iters: 16,
+            run: scalar_addressing,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (AddressLine, TestDefinition {
+            name: "address_line",
+            passes: 2,
+            iters: 1,
+            run: scalar_address_line_test,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Sgemm, TestDefinition {
+            name: "sgemm",
+            passes: 1,
+            iters: SGEMM_ROUNDS,
+            run: scalar_sgemm,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (CustomPatterns, TestDefinition {
+            name: "custom_patterns",
+            passes: 4,
+            iters: 1,
+            run: scalar_custom_patterns,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking1Line, TestDefinition {
+            name: "walking_1_line",
+            passes: 2,
+            iters: 512,
+            run: scalar_walking_1_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking0Line, TestDefinition {
+            name: "walking_0_line",
+            passes: 2,
+            iters: 512,
+            run: scalar_walking_0_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (RefreshStress, TestDefinition {
+            name: "refresh_stress",
+            passes: 1,
+            iters: 1,
+            run: scalar_refresh_stress,
+            loops: 1,
+            options: TestOptions { dwell: 64, ..TestOptions::default() },
+        }),
+        (LfsrFill, TestDefinition {
+            name: "lfsr_fill",
+            passes: 2,
+            iters: 2,
+            run: scalar_lfsr_fill,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (ChannelStripes, TestDefinition {
+            name: "channel_stripes",
+            passes: 2,
+            iters: 4,
+            run: scalar_channel_stripes,
+            loops: 1,
+            options: TestOptions { interleave: 256, ..TestOptions::default() },
+        }),
+        (CoherencePingPong, TestDefinition {
+            name: "coherence_ping_pong",
+            passes: 1,
+            iters: 1,
+            run: scalar_coherence_ping_pong,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PointerChase, TestDefinition {
+            name: "pointer_chase",
+            passes: 1,
+            iters: 1,
+            run: scalar_pointer_chase,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MixedRw, TestDefinition {
+            name: "mixed_rw",
+            passes: 2,
+            iters: 4,
+            run: scalar_mixed_rw,
+            loops: 1,
+            options: TestOptions { ratio: (2, 1), ..TestOptions::default() },
+        }),
+        (ThermalCycle, TestDefinition {
+            name: "thermal_cycle",
+            passes: 2,
+            iters: 1,
+            run: scalar_thermal_cycle,
+            loops: 0,
+            options: TestOptions { sleep: 60, heat: 60, ..TestOptions::default() },
+        }),
+        (ByteLanes, TestDefinition {
+            name: "byte_lanes",
+            passes: 4,
+            iters: 64,
+            run: scalar_byte_lanes,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Butterfly, TestDefinition {
+            name: "butterfly",
+            passes: 4,
+            iters: 4,
+            run: scalar_butterfly,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+    ])
+}
+
+pub fn neon_definitions() -> HashMap<TestKind, TestDefinition> {
+    use TestKind::*;
+    HashMap::from([
+        (BasicTests, TestDefinition {
+            name: "basic_tests",
+            passes: 4,
+            iters: 6,
+            run: neon_basic_tests,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (March, TestDefinition {
+            name: "march",
+            passes: 10,
+            iters: 2,
+            run: neon_march,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RandomInversions, TestDefinition {
+            name: "random_inversions",
+            passes: 4,
+            iters: 16,
+            run: neon_random_inversions,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsLeft64, TestDefinition {
+            name: "moving_inversions_left_64",
+            passes: 4,
+            iters: 64,
+            run: neon_moving_inversions_left_64,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsRight32, TestDefinition {
+            name: "moving_inversions_right_32",
+            passes: 4,
+            iters: 32,
+            run: neon_moving_inversions_right_32,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsLeft16, TestDefinition {
+            name: "moving_inversions_left_16",
+            passes: 4,
+            iters: 16,
+            run: neon_moving_inversions_left_16,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsRight8, TestDefinition {
+            name: "moving_inversions_right_8",
+            passes: 4,
+            iters: 8,
+            run: neon_moving_inversions_right_8,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingInversionsLeft4, TestDefinition {
+            name: "moving_inversions_left_4",
+            passes: 4,
+            iters: 4,
+            run: neon_moving_inversions_left_4,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingSaturationsRight16, TestDefinition {
+            name: "moving_saturations_right_16",
+            passes: 8,
+            iters: 16,
+            run: neon_moving_saturations_right_16,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MovingSaturationsLeft8, TestDefinition {
+            name: "moving_saturations_left_8",
+            passes: 8,
+            iters: 8,
+            run: neon_moving_saturations_left_8,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Walking1, TestDefinition {
+            name: "walking1",
+            passes: 4,
+            iters: 64,
+            run: neon_walking_1,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Walking0, TestDefinition {
+            name: "walking0",
+            passes: 4,
+            iters: 64,
+            run: neon_walking_0,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Checkerboard, TestDefinition {
+            name: "checkerboard",
+            passes: 4,
+            iters: 1,
+            run: neon_checkerboard,
+            loops: 8,
+            options: TestOptions::default(),
+        }),
+        (AntiPatterns, TestDefinition {
+            name: "anti_patterns",
+            passes: 8,
+            iters: 34,
+            run: neon_anti_patterns,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (InverseDataPatterns, TestDefinition {
+            name: "inverse_data_patterns",
+            passes: 4,
+            iters: 14,
+            run: neon_inverse_data_patterns,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RowHammer, TestDefinition {
+            name: "rowhammer",
+            passes: 2,
+            iters: 2,
+            run: neon_rowhammer,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BitFade, TestDefinition {
+            name: "bit_fade",
+            passes: 2,
+            iters: 2,
+            run: neon_bit_fade,
+            loops: 0,
+            options: TestOptions { sleep: 300, ..TestOptions::default() },
+        }),
+        (Modulo20, TestDefinition {
+            name: "modulo_20",
+            passes: 2,
+            iters: 20,
+            run: neon_modulo_20,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (BlockMove, TestDefinition {
+            name: "block_move",
+            passes: 6,
+            iters: 4,
+            run: neon_block_move,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (RandomAddress, TestDefinition {
+            name: "random_address",
+            passes: 2,
+            iters: 1,
+            run: neon_random_address,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PrimeStride, TestDefinition {
+            name: "prime_stride",
+            passes: 2,
+            iters: 4,
+            run: neon_prime_stride,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Addressing, TestDefinition {
+            name: "addressing",
+            passes: 2,
+            iters: 16,
+            run: neon_addressing,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (AddressLine, TestDefinition {
+            name: "address_line",
+            passes: 2,
+            iters: 1,
+            run: neon_address_line_test,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Sgemm, TestDefinition {
+            name: "sgemm",
+            passes: 1,
+            iters: SGEMM_ROUNDS,
+            run: neon_sgemm,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (CustomPatterns, TestDefinition {
+            name: "custom_patterns",
+            passes: 4,
+            iters: 1,
+            run: neon_custom_patterns,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking1Line, TestDefinition {
+            name: "walking_1_line",
+            passes: 2,
+            iters: 512,
+            run: neon_walking_1_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (Walking0Line, TestDefinition {
+            name: "walking_0_line",
+            passes: 2,
+            iters: 512,
+            run: neon_walking_0_line,
+            loops: 0,
+            options: TestOptions::default(),
+        }),
+        (RefreshStress, TestDefinition {
+            name: "refresh_stress",
+            passes: 1,
+            iters: 1,
+            run: neon_refresh_stress,
+            loops: 1,
+            options: TestOptions { dwell: 64, ..TestOptions::default() },
+        }),
+        (LfsrFill, TestDefinition {
+            name: "lfsr_fill",
+            passes: 2,
+            iters: 2,
+            run: neon_lfsr_fill,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (ChannelStripes, TestDefinition {
+            name: "channel_stripes",
+            passes: 2,
+            iters: 4,
+            run: neon_channel_stripes,
+            loops: 1,
+            options: TestOptions { interleave: 256, ..TestOptions::default() },
+        }),
+        (CoherencePingPong, TestDefinition {
+            name: "coherence_ping_pong",
+            passes: 1,
+            iters: 1,
+            run: neon_coherence_ping_pong,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (PointerChase, TestDefinition {
+            name: "pointer_chase",
+            passes: 1,
+            iters: 1,
+            run: neon_pointer_chase,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (MixedRw, TestDefinition {
+            name: "mixed_rw",
+            passes: 2,
+            iters: 4,
+            run: neon_mixed_rw,
+            loops: 1,
+            options: TestOptions { ratio: (2, 1), ..TestOptions::default() },
+        }),
+        (ThermalCycle, TestDefinition {
+            name: "thermal_cycle",
+            passes: 2,
+            iters: 1,
+            run: neon_thermal_cycle,
+            loops: 0,
+            options: TestOptions { sleep: 60, heat: 60, ..TestOptions::default() },
+        }),
+        (ByteLanes, TestDefinition {
+            name: "byte_lanes",
+            passes: 4,
+            iters: 64,
+            run: neon_byte_lanes,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+        (Butterfly, TestDefinition {
+            name: "butterfly",
+            passes: 4,
+            iters: 4,
+            run: neon_butterfly,
+            loops: 1,
+            options: TestOptions::default(),
+        }),
+    ])
+}
+
+#[allow(dead_code)]
+pub fn get_test_definitions_for_isa(isa: InstructionSet) -> HashMap<TestKind, TestDefinition> {
+    match isa {
+        InstructionSet::AVX512 => {
+            avx512_definitions()
+        }
+        InstructionSet::AVX2 => {
+            avx2_definitions()
+        },
+        InstructionSet::NEON => neon_definitions(),
+        InstructionSet::SSE => scalar_definitions(),
+    }
+}
+
+/// Set up a run on `cpus` threads; the kernels' generator starts from `seed`, a random one if None.
+pub fn tests_init<'a>(cpus: usize, channels: usize, stop_signal: &'a AtomicBool, pause: &'a Pause, isa: InstructionSet, seed: Option<u64>) -> RunState<'a> {
+    RunState::new(cpus, channels, seed.unwrap_or_else(|| random_seed(isa)), stop_signal, pause)
+}
+
+/// Order in which the kernels hand the `ctx.cpus` chunks to the thread pool; rayon splits the
+/// index range between its threads, so a shuffled order moves every thread to other chunks.
+pub fn chunk_order(ctx: &TestContext) -> Vec<usize> {
+    wait_while_paused(ctx);
+    ctx.run.progress.sweep();
+    ctx.run.sweep.fetch_add(1, Ordering::Relaxed);
+    let mut order: Vec<usize> = (0..ctx.cpus).collect();
+    if ctx.run.shuffle.load(Ordering::Relaxed) != 0 {
+        ctx.run.shuffle(&mut order);
+    }
+    order
+}
+
+const ERROR_OFFSETS_MAX: usize = 16;
+const LOGGED_MISCOMPARES: usize = 100;
+thread_local! {
+    // the sweep and line of every thread's last miscompare
+    static LAST_BAD_LINE: Cell<(usize, usize)> = const { Cell::new((usize::MAX, usize::MAX)) };
+}
+
+/// Make `ctx` the context of `test`: its options, on the threads it runs on.
+pub fn tests_configure(test: &TestDefinition, ctx: &mut TestContext) {
+    ctx.options = test.options.clone();
+    ctx.cpus = ctx.run.test_threads(test);
+    ctx.run.miscompares.store(0, Ordering::Relaxed);
+}
+
+/// Run `test` on `mem[..size]` with the threads of the current pool. Overlapped phases split the
+/// region in two halves tested at once, the second one on a pool of its own beside the current
+/// one and starting when the first one starts its second sweep; without two chunks or a region
+/// too small to split, the test runs as usual. The tail the kernels' whole vectors leave is tested afterwards, see `test_tail()`.
+pub unsafe fn run_test(test: &TestDefinition, mem: *mut u8, size: usize, ctx: &TestContext) {
+    let align = ctx.cpus * 4096;
+    let half = size / 2 / align * align;
+    if test.options.phases == Phases::Strict || ctx.cpus < 2 || half == 0 {
+        (test.run)(mem, size, ctx);
+    } else {
+        run_halves(test, mem, size, half, ctx);
+    }
+    if !stop_requested(ctx) {
+        test_tail(mem, size, ctx);
+    }
+}
+
+// The overlapped phases of `run_test()`: `mem[..half]` and `mem[half..size]` at once.
+unsafe fn run_halves(test: &TestDefinition, mem: *mut u8, size: usize, half: usize, ctx: &TestContext) {
+    // the second half's kernel records its offsets from the start of the region
+    let second = TestContext { base: ctx.base + half, options: ctx.options.clone(), ..*ctx };
+    let mem_usize = mem as usize;
+    // a worker of the current pool could steal the second half while waiting on the first one,
+    // so it runs on a pool of its own, whose workers keep the current ones' pinning and NUMA binding
+    let pool = match sibling_thread_pool(ctx.cpus) {
+        Ok(pool) => pool,
+        Err(e) => {
+            warn!("can't start a thread pool for the second half, testing the halves one after the other: {}", e);
+            (test.run)(mem, half, ctx);
+            if !stop_requested(ctx) {
+                (test.run)(mem.add(half), size - half, &second);
+            }
+            return;
+        }
+    };
+    let first_done = AtomicBool::new(false);
+    let sweeps = ctx.run.sweep.load(Ordering::Relaxed);
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            // tests that don't sweep through chunk_order() run one half after the other
+            while ctx.run.sweep.load(Ordering::Relaxed) < sweeps + 2 && !first_done.load(Ordering::Relaxed) && !stop_requested(ctx) {
+                std::thread::sleep(Duration::from_micros(100));
+            }
+            pool.install(|| (test.run)((mem_usize + half) as *mut u8, size - half, &second));
+        });
+        (test.run)(mem_usize as *mut u8, half, ctx);
+        first_done.store(true, Ordering::Relaxed);
+    });
+}
+
+/// Hint the cache line `RunState::set_prefetch_distance()` bytes ahead of the word at `idx` into
+/// the caches, behind it when sweeping down; once per line, when the loop reaches its first word.
+/// A prefetch never faults, so it may run past the chunk or the region. Only x86 has one.
+#[inline(always)]
+pub unsafe fn prefetch_ahead(mem: *const u8, idx: usize, up: bool, ctx: &TestContext) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        let distance = ctx.run.prefetch.load(Ordering::Relaxed);
+        if distance != 0 && idx.is_multiple_of(64) {
+            let target = if up { idx.wrapping_add(distance) } else { idx.wrapping_sub(distance) };
+            _mm_prefetch::<_MM_HINT_T0>(mem.wrapping_add(target) as *const i8);
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = (mem, idx, up, ctx);
+}
+
+// Largest distance `--prefetch` takes, the calibration tries up to 4K
+const PREFETCH_MAX: usize = 64 * 1024;
+// Distances `calibrate_prefetch()` tries, demand loads first
+const PREFETCH_DISTANCES: [usize; 6] = [0, 256, 512, 1024, 2048, 4096];
+// Memory the calibration reads, well beyond the caches, and the sweeps timed per distance
+const CALIBRATION_SPAN: usize = 256 * 1024 * 1024;
+const CALIBRATION_SWEEPS: usize = 3;
+
+/// Find the fastest prefetch distance of the verify loops of `isa`: time read sweeps over the
+/// start of `mem[..size]` with every distance of PREFETCH_DISTANCES, best of a few each, and log
+/// the one kept. Prefetching has to beat demand loads by 2% to be kept, less is timing noise.
+/// Nothing is verified, so the memory may hold anything. Returns the distance, 0 for none.
+pub unsafe fn calibrate_prefetch(isa: InstructionSet, mem: *mut u8, size: usize, ctx: &TestContext) -> usize {
+    if !cfg!(target_arch = "x86_64") {
+        info!("Prefetch Distance : off (no software prefetch on this architecture)");
+        return 0;
+    }
+    let sweep: unsafe fn(*mut u8, usize, &TestContext) = match isa {
+        InstructionSet::AVX512 => avx512_read_sweep,
+        InstructionSet::AVX2 => avx2_read_sweep,
+        InstructionSet::NEON | InstructionSet::SSE => scalar_read_sweep,
+    };
+    let span = CALIBRATION_SPAN.min(size);
+    let mut times = Vec::new();
+    for distance in PREFETCH_DISTANCES {
+        if stop_requested(ctx) {
+            break;
+        }
+        ctx.run.set_prefetch_distance(distance);
+        let best = (0..CALIBRATION_SWEEPS).map(|_| {
+            let timer = Instant::now();
+            sweep(mem, span, ctx);
+            timer.elapsed()
+        }).min().unwrap();
+        times.push((best, distance));
+    }
+    let Some(&(demand, _)) = times.first() else {
+        ctx.run.set_prefetch_distance(0);
+        return 0;
+    };
+    let (fastest, distance) = times.into_iter().min().unwrap();
+    let gain = demand.as_secs_f64() / fastest.as_secs_f64() - 1.0;
+    let distance = if gain > 0.02 { distance } else { 0 };
+    ctx.run.set_prefetch_distance(distance);
+    if distance == 0 {
+        info!("Prefetch Distance : off (calibrated, demand loads are as fast)");
+    } else {
+        info!("Prefetch Distance : {} bytes (calibrated, {:.0}% faster reads than demand loads)", distance, gain * 100.0);
+    }
+    distance
+}
+
+/// Sleep for the configured retention interval without touching the test memory,
+/// waking up early if the run is stopped.
+pub fn retention_sleep(ctx: &TestContext) {
+    let deadline = Instant::now() + Duration::from_secs(ctx.options.sleep);
+    loop {
+        let now = Instant::now();
+        if stop_requested(ctx) || now >= deadline {
+            break;
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(100)));
+    }
+}
+
+/// Remember a miscompare of the 64-bit word at `offset` and queue its error record; the first
+/// one since the last `RunState::take_first_error()` is the starting point for fault isolation,
+/// the first few are kept for `RunState::take_error_offsets()`.
+pub fn record_error(offset: usize, expected: u64, actual: u64, ctx: &TestContext) {
+    let run = ctx.run;
+    let offset = offset + ctx.base;
+    ctx.run.records.push(offset, expected, actual);
+    run.flipped_bits.fetch_add((expected ^ actual).count_ones() as u64, Ordering::Relaxed);
+    // a thread reads the words of a line one after the other, so its line was counted if the last
+    // miscompare of the thread was in it during the same sweep
+    let line = (run.sweep.load(Ordering::Relaxed), offset / 64);
+    if LAST_BAD_LINE.replace(line) != line {
+        run.bad_lines.fetch_add(1, Ordering::Relaxed);
+    }
+    let _ = run.first_error.compare_exchange(usize::MAX, offset, Ordering::Relaxed, Ordering::Relaxed);
+    if run.faults_armed.load(Ordering::Relaxed) {
+        run.detected.lock().unwrap().insert(offset);
+    } else {
+        ctx.run.dump.first_error(offset);
+    }
+    // a failing module miscompares on every word, only the first few take the lock
+    if run.error_offset_slots.load(Ordering::Relaxed) < ERROR_OFFSETS_MAX
+        && run.error_offset_slots.fetch_add(1, Ordering::Relaxed) < ERROR_OFFSETS_MAX {
+        let mut offsets = run.error_offsets.lock().unwrap();
+        if offsets.len() < ERROR_OFFSETS_MAX {
+            offsets.push(offset);
+        }
+    }
+}
+
+/// Bytes that differ between `expected` and `actual`, the unit `RunState::errors` counts in.
+pub fn miscompared_bytes(expected: u64, actual: u64) -> u64 {
+    (expected ^ actual).to_ne_bytes().iter().filter(|&&b| b != 0).count() as u64
+}
+
+/// Whether a kernel logs the miscompare it found: the first LOGGED_MISCOMPARES of a test are,
+/// later ones are only recorded and counted for `RunState::log_unlogged_miscompares()`. A failing
+/// module can miscompare on every vector, and a line for each would slow the test down to the
+/// speed of the log.
+pub fn log_miscompare(ctx: &TestContext) -> bool {
+    ctx.run.miscompares.fetch_add(1, Ordering::Relaxed) < LOGGED_MISCOMPARES
+}
+
+/// `record_error()` for every mismatching 64-bit word of the vector at `offset`.
+pub fn record_mismatches(offset: usize, expected: &[u8], actual: &[u8], ctx: &TestContext) {
+    for (i, (expected, actual)) in expected.chunks_exact(8).zip(actual.chunks_exact(8)).enumerate() {
+        let expected = u64::from_ne_bytes(expected.try_into().unwrap());
+        let actual = u64::from_ne_bytes(actual.try_into().unwrap());
+        if expected != actual {
+            record_error(offset + i * 8, expected, actual, ctx);
+        }
+    }
+}
+
+/// Self-test hook of the verify passes: overwrite the byte at every armed offset of `mem[..size]`
+/// with its expected value from the vector pattern `pattern`, with the lowest bit flipped.
+pub unsafe fn inject_faults(mem: *const u8, size: usize, pattern: &[u8], ctx: &TestContext) {
+    if !ctx.run.faults_armed.load(Ordering::Relaxed) {
+        return;
+    }
+    for &offset in ctx.run.injected.lock().unwrap().iter().filter(|&&offset| offset < size) {
+        std::ptr::write_volatile(mem.add(offset) as *mut u8, pattern[offset % pattern.len()] ^ 1);
+    }
+}
+
+// Block the sweep about to start while the run is paused, a stop ends the pause
+fn wait_while_paused(ctx: &TestContext) {
+    let pause = ctx.run.pause;
+    let mut paused = pause.paused.lock().unwrap();
+    while *paused && !stop_requested(ctx) {
+        paused = pause.resumed.wait_timeout(paused, Duration::from_millis(100)).unwrap().0;
+    }
+}
+
+/// Whether the run has been asked to stop or reached its error or time limit, see
+/// `RunState::stop_requested()`.
+pub fn stop_requested(ctx: &TestContext) -> bool {
+    ctx.run.stop_requested()
+}
+
+// Vectors a worker sweeps between two looks at the stop signal
+const STOP_CHECK_INTERVAL: usize = 1 << 16;
+
+/// The `offsets` of a worker's chunk up to a stop request, checked before the first one and then
+/// every `STOP_CHECK_INTERVAL`, so a stop doesn't wait for the sweeps over all of the memory. Once
+/// it is set every later sweep ends before its first offset, so a write sweep that ended early is
+/// never verified.
+pub fn until_stopped<'a, I: Iterator + 'a>(offsets: I, ctx: &'a TestContext) -> impl Iterator<Item = I::Item> + 'a {
+    offsets.enumerate()
+        .take_while(|(n, _)| n % STOP_CHECK_INTERVAL != 0 || !stop_requested(ctx))
+        .map(|(_, offset)| offset)
+}
+
+/// How long the thermal cycle test keeps the memory busy before cooling down.
+pub fn heat_duration(ctx: &TestContext) -> Duration {
+    Duration::from_secs(ctx.options.heat)
+}
+
+/// Log the CPU/DIMM temperatures at the end of a thermal cycle phase together with the
+/// error count so far, so errors can be matched with the temperature swing.
+pub fn log_temperatures(phase: &str, errors: u64) {
+    let readings = hardware_temperatures();
+    let temperatures = if readings.is_empty() {
+        "no temperature sensors".to_string()
+    } else {
+        readings.iter()
+            .map(|(sensor, celsius)| format!("{} {:.1}°C", sensor, celsius))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    info!("thermal_cycle {} done [{} errors so far]: {}", phase, errors, temperatures);
+}
+
+/// The configured patterns of the custom patterns test.
+pub fn custom_patterns(ctx: &TestContext) -> Vec<u64> {
+    ctx.options.patterns.clone()
+}
+
+/// One step of a maximal-length 64-bit Galois LFSR (x^64 + x^63 + x^61 + x^60 + 1).
+pub fn lfsr64_next(state: u64) -> u64 {
+    (state >> 1) ^ ((state & 1).wrapping_neg() & 0xD800000000000000)
+}
+
+/// How long the refresh stress test keeps hammering one window.
+pub fn refresh_dwell(ctx: &TestContext) -> Duration {
+    Duration::from_millis(ctx.options.dwell)
+}
+
+/// Reads and writes per step of the mixed read/write test, at least one write.
+pub fn rw_ratio(ctx: &TestContext) -> (usize, usize) {
+    let (reads, writes) = ctx.options.ratio;
+    (reads, writes.max(1))
+}
+
+/// Number of populated memory channels the channel stripes test splits the region into.
+pub fn channel_count(ctx: &TestContext) -> usize {
+    ctx.run.channels
+}
+
+/// Size of one channel stripe in bytes, never smaller than a cache line.
+pub fn channel_interleave(ctx: &TestContext) -> usize {
+    ctx.options.interleave.max(64)
+}
+
+/// Channel label as assigned in `collect_system_info()` ("Channel A", "Channel B", ...).
+pub fn channel_name(channel: usize, ctx: &TestContext) -> String {
+    if channel_count(ctx) <= 26 {
+        format!("Channel {}", (b'A' + channel as u8) as char)
+    } else {
+        format!("Channel {}", channel)
+    }
+}
+
+/// Offsets of the `width`-byte vectors thread `i` of `ctx.cpus` visits in a full fill or verify
+/// of `mem[..ctx.cpus * chunk_size]`: all of chunk `i` by default, every `ctx.cpus`th cache line
+/// starting at line `i` with `partition=interleaved`, and only every Nth of those lines with
+/// `stride=N`.
+pub fn chunk_offsets(i: usize, chunk_size: usize, width: usize, ctx: &TestContext) -> impl DoubleEndedIterator<Item = usize> {
+    let cpus = ctx.cpus;
+    let stride = ctx.options.stride.max(1);
+    let (first, end, step, run) = if ctx.options.partition == Partition::Interleaved {
+        (i * 64, cpus * chunk_size, 64 * cpus * stride, 64)
+    } else if stride == 1 {
+        // without a stride the chunk is a single run, keeping the hot loop a plain step_by
+        (i * chunk_size, (i + 1) * chunk_size, chunk_size.max(1), chunk_size)
+    } else {
+        (i * chunk_size, (i + 1) * chunk_size, 64 * stride, 64)
+    };
+    (first..end).step_by(step).flat_map(move |line| (line..(line + run).min(end)).step_by(width))
+}
+
+/// Whether the kernels should use regular cached stores instead of streaming stores.
+pub fn cached_stores(ctx: &TestContext) -> bool {
+    ctx.options.cache == CacheMode::Cached
+}
+
+/// Write back and evict every cache line of `mem[..len]` so the next reads are served
+/// from DRAM again. Does nothing in bypass mode, where the stores never allocate lines.
+pub unsafe fn flush_sweep(mem: *const u8, len: usize, ctx: &TestContext) {
+    flush_lines(mem, (0..len).step_by(64), ctx);
+}
+
+/// `flush_sweep()` of the lines thread `i` just wrote with `chunk_offsets()`. Under interleaved
+/// partitioning the other threads are still writing their lines of the same chunk, so each
+/// thread flushes exactly its own lines instead of a contiguous chunk.
+pub unsafe fn flush_chunk(mem: *const u8, i: usize, chunk_size: usize, ctx: &TestContext) {
+    flush_lines(mem, chunk_offsets(i, chunk_size, 64, ctx), ctx);
+}
+
+unsafe fn flush_lines(mem: *const u8, lines: impl Iterator<Item = usize>, ctx: &TestContext) {
+    if !cached_stores(ctx) {
+        return;
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::asm;
+        use std::arch::x86_64::{__cpuid_count, _mm_clflush, _mm_sfence};
+        // CPUID.(EAX=7,ECX=0):EBX[23], not covered by is_x86_feature_detected!
+        if __cpuid_count(7, 0).ebx & (1 << 23) != 0 {
+            for line in lines {
+                asm!("clflushopt [{l}]", l = in(reg) mem.add(line), options(nostack, preserves_flags));
+            }
+        } else {
+            for line in lines {
+                _mm_clflush(mem.add(line));
+            }
+        }
+        // clflushopt is only ordered by fences
+        _mm_sfence();
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        use std::arch::asm;
+        for line in lines {
+            asm!("dc civac, {l}", l = in(reg) mem.add(line), options(nostack, preserves_flags));
+        }
+        asm!("dsb ish", options(nostack, preserves_flags));
+    }
+}
+
+/// Write the bytes of `mem[from..size]` left over after every thread's whole vectors with the
+/// bytes of the vector pattern `pattern`, continuing its layout as if the vectors went on.
+pub unsafe fn tail_set(mem: *mut u8, from: usize, size: usize, pattern: &[u8], ascending: bool, ctx: &TestContext) {
+    let visit = |idx: usize| std::ptr::write_volatile(mem.add(idx), pattern[idx % pattern.len()]);
+    if ascending {
+        (from..size).for_each(visit);
+    } else {
+        (from..size).rev().for_each(visit);
+    }
+    flush_sweep(mem.add(from), size - from, ctx);
+}
+
+/// Verify the bytes written by `tail_set()`, counting every mismatching byte in the run's errors.
+pub unsafe fn tail_get(mem: *const u8, from: usize, size: usize, pattern: &[u8], ascending: bool, ctx: &TestContext) {
+    let visit = |idx: usize| {
+        let diff = std::ptr::read_volatile(mem.add(idx)) ^ pattern[idx % pattern.len()];
+        if diff != 0 {
+            if log_miscompare(ctx) {
+                error!("errors detected at offset 0x{:016x} [xor mask: 0x{:02x}]", idx, diff);
+            }
+            record_error(idx, pattern[idx % pattern.len()] as u64, (pattern[idx % pattern.len()] ^ diff) as u64, ctx);
+            ctx.run.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    };
+    if ascending {
+        (from..size).for_each(visit);
+    } else {
+        (from..size).rev().for_each(visit);
+    }
+}
+
+// Widest vector of any kernel; a region's bytes past the last thread's whole vectors of this width
+// include every tail a kernel leaves
+const TAIL_WIDTH: usize = 64;
+
+/// Write and verify the bytes of `mem[..size]` past the last thread's whole vectors with a random
+/// pattern and its complement. Most kernels sweep whole vectors of every thread's chunk only, so
+/// without this the end of a region that isn't a multiple of the threads' vectors goes untested.
+unsafe fn test_tail(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let from = ctx.cpus * (size / ctx.cpus / TAIL_WIDTH * TAIL_WIDTH);
+    if from == size {
+        return;
+    }
+    let pattern = xorshift128plus_lanes(&mut ctx.run.rng.lock().unwrap());
+    for pattern in [pattern, !pattern] {
+        let bytes = pattern.to_ne_bytes();
+        tail_set(mem, from, size, &bytes, true, ctx);
+        tail_get(mem, from, size, &bytes, true, ctx);
+    }
+}
+
+/// Vector width in bytes of the test kernels of `isa`; per-thread chunks are whole vectors
+/// and the remaining tail of the region is covered bytewise.
+pub fn vector_width(isa: InstructionSet) -> usize {
+    match isa {
+        InstructionSet::AVX512 => 64,
+        InstructionSet::AVX2 => 32,
+        InstructionSet::NEON => 16,
+        InstructionSet::SSE => 8,
+    }
+}
+
+#[cfg(test)]
+mod unit {
+    use super::*;
+
+    #[test]
+    fn interleaved_partition_covers_every_vector_once() {
+        let (cpus, chunk_size, width) = (3, 5 * 64 + 32, 32);
+        let (stop, pause) = (AtomicBool::new(false), Pause::default());
+        let run = RunState::new(cpus, 1, 0, &stop, &pause);
+        let mut ctx = TestContext::new(&run);
+        ctx.options.partition = Partition::Interleaved;
+        let mut offsets: Vec<usize> = (0..cpus).flat_map(|i| chunk_offsets(i, chunk_size, width, &ctx)).collect();
+        assert!(chunk_offsets(1, chunk_size, width, &ctx).rev().step_by(64 / width).all(|idx| idx / 64 % cpus == 1));
+        offsets.sort();
+        assert_eq!(offsets, (0..cpus * chunk_size).step_by(width).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn mismatches_count_flipped_bits_and_lines() {
+        let expected = [0u8; 128];
+        let mut actual = expected;
+        // two words of the first line, one of the second
+        actual[0] = 0b101;
+        actual[8] = 0x80;
+        actual[64 + 16] = 0xff;
+        let (stop, pause) = (AtomicBool::new(false), Pause::default());
+        let run = RunState::new(1, 1, 0, &stop, &pause);
+        record_mismatches(1 << 20, &expected, &actual, &TestContext::new(&run));
+        assert_eq!(run.flip_totals(), (11, 2));
+    }
+
+    #[test]
+    fn recorded_offsets_include_the_base() {
+        let (stop, pause) = (AtomicBool::new(false), Pause::default());
+        let run = RunState::new(1, 1, 0, &stop, &pause);
+        // the second half of an overlapped phase records relative to the start of the region
+        let ctx = TestContext { base: 1 << 20, ..TestContext::new(&run) };
+        record_error(0x40, 0, 1, &ctx);
+        record_error(0x80, 0, 1, &ctx);
+        assert_eq!(run.take_first_error(), Some((1 << 20) + 0x40));
+        assert_eq!(run.take_error_offsets(), [(1 << 20) + 0x40, (1 << 20) + 0x80]);
+    }
+
+    #[test]
+    fn definition_names_parse_to_their_kind() {
+        for isa in [InstructionSet::AVX512, InstructionSet::AVX2, InstructionSet::NEON, InstructionSet::SSE] {
+            for (kind, test) in get_test_definitions_for_isa(isa) {
+                assert_eq!(TestKind::parse(test.name), Some(kind), "{}", test.name);
+            }
+        }
+    }
+}
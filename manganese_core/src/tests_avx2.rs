@@ -1,231 +1,216 @@
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
-use std::sync::atomic::AtomicU64;
 use log::error;
+use crate::tests::TestContext;
+#[cfg(target_arch = "x86_64")]
+use crate::tests::{cached_stores, channel_count, channel_interleave, channel_name, chunk_offsets, chunk_order, custom_patterns, flush_chunk, flush_sweep, heat_duration, inject_faults, lfsr64_next, log_miscompare, log_temperatures, miscompared_bytes, prefetch_ahead, record_error, record_mismatches, refresh_dwell, retention_sleep, rw_ratio, stop_requested, tail_get, tail_set, until_stopped};
+#[cfg(target_arch = "x86_64")]
+use crate::coherence::coherence_ping_pong;
+#[cfg(target_arch = "x86_64")]
+use crate::pointer_chase::pointer_chase;
+#[cfg(target_arch = "x86_64")]
+use crate::sgemm::{sgemm_chunk, SGEMM_ROUNDS};
 #[cfg(target_arch = "x86_64")]
 use crate::simd_xorshift::AvxXorshift128PlusKey;
 #[cfg(target_arch = "x86_64")]
-use crate::simd_xorshift::{avx_xorshift128plus, avx_xorshift128plus_init};
-
-static mut CPUS: usize = 0;
-static mut ERRORS: *const AtomicU64 = std::ptr::null();
-static mut RNG: AvxXorshift128PlusKey = AvxXorshift128PlusKey {
-    part1: unsafe { std::mem::zeroed() },
-    part2: unsafe { std::mem::zeroed() },
-};
+use crate::simd_xorshift::{avx_xorshift128plus, avx_xorshift128plus_init, avx_xorshift128plus_lanes};
 
+// Next random vector of the run's generator
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_tests_init(cpus: usize, errors: *const AtomicU64) {
-    CPUS = cpus;
-    ERRORS = errors;
-    
-    let mut r1 = 0u64;
-    let mut r2 = 0u64;
-    while r1 == 0 && r2 == 0 {
-        _rdrand64_step(&mut r1);
-        _rdrand64_step(&mut r2);
-    }
-    avx_xorshift128plus_init(r1, r2, &raw mut RNG);
+unsafe fn random(ctx: &TestContext) -> __m256i {
+    avx_xorshift128plus_lanes(&mut ctx.run.rng.lock().unwrap())
 }
 
 #[cfg(target_arch = "x86_64")]
-unsafe fn get(mem: *const u8, idx: usize, expected: __m256i) {
+unsafe fn get(mem: *const u8, idx: usize, expected: __m256i, ctx: &TestContext) {
     let actual = _mm256_load_si256((mem.add(idx)) as *const __m256i);
-    let cmp = _mm256_cmpeq_epi8(expected, actual);
-    let result = _mm256_testz_si256(cmp, cmp);
-    
+    // one bit per byte, set where the bytes differ
+    let result = !(_mm256_movemask_epi8(_mm256_cmpeq_epi8(expected, actual)) as u32);
+
     if result != 0 {
-        error!("errors detected at offset 0x{:016x}", idx);
-        (*ERRORS).fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let error_total = result.count_ones() as u64;
+        if log_miscompare(ctx) {
+            error!("{} errors detected at offset 0x{:016x} [error mask: 0x{:08x}, expected: 0x{}, actual: 0x{}]",
+                error_total, idx, result, to_hex(expected), to_hex(actual));
+        }
+        record_mismatches(idx, &to_bytes(expected), &to_bytes(actual), ctx);
+        ctx.run.errors.fetch_add(error_total, std::sync::atomic::Ordering::Relaxed);
     }
 }
 
+// All 256 bits, most significant byte first
 #[cfg(target_arch = "x86_64")]
-unsafe fn get_all_up(mem: *const u8, size: usize, expected: __m256i) {
+unsafe fn to_hex(val: __m256i) -> String {
+    to_bytes(val).iter().rev().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn to_bytes(val: __m256i) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    _mm256_storeu_si256(bytes.as_mut_ptr() as *mut __m256i, val);
+    bytes
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn get_all_up(mem: *const u8, size: usize, expected: __m256i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;  // Convert to usize for thread safety
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    inject_faults(mem, size, &to_bytes(expected), ctx);
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        let chunk_size = size / CPUS;
-        for j in (0..chunk_size).step_by(32) {
-            let idx = j + i * chunk_size;
-            get(mem_ptr, idx, expected);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx), ctx) {
+            prefetch_ahead(mem_ptr, idx, true, ctx);
+            get(mem_ptr, idx, expected, ctx);
         }
     });
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(expected), true, ctx);
 }
 
 #[cfg(target_arch = "x86_64")]
-unsafe fn get_all_down(mem: *const u8, size: usize, expected: __m256i) {
+unsafe fn get_all_down(mem: *const u8, size: usize, expected: __m256i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
     
-    let chunk_size = size / CPUS;
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    inject_faults(mem, size, &to_bytes(expected), ctx);
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(expected), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        // Iterate from end-32 down to start, stepping by 32
-        let mut j = ((end - start) / 32) * 32 + start;  // Last aligned position
-        while j >= start + 32 {
-            j -= 32;
-            get(mem_ptr, j, expected);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx).rev(), ctx) {
+            prefetch_ahead(mem_ptr, idx, false, ctx);
+            get(mem_ptr, idx, expected, ctx);
         }
     });
 }
 
 #[cfg(target_arch = "x86_64")]
-unsafe fn set(mem: *mut u8, idx: usize, val: __m256i) {
-    _mm256_stream_si256((mem.add(idx)) as *mut __m256i, val);
+unsafe fn set(mem: *mut u8, idx: usize, val: __m256i, cached: bool) {
+    if cached {
+        _mm256_store_si256((mem.add(idx)) as *mut __m256i, val);
+    } else {
+        _mm256_stream_si256((mem.add(idx)) as *mut __m256i, val);
+    }
 }
 
 #[cfg(target_arch = "x86_64")]
-unsafe fn set_all_up(mem: *mut u8, size: usize, val: __m256i) {
+unsafe fn set_all_up(mem: *mut u8, size: usize, val: __m256i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        let chunk_size = size / CPUS;
-        for j in (0..chunk_size).step_by(32) {
-            let idx = j + i * chunk_size;
-            set(mem_ptr, idx, val);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx), ctx) {
+            set(mem_ptr, idx, val, cached);
         }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
     });
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(val), true, ctx);
 }
 
 #[cfg(target_arch = "x86_64")]
-unsafe fn set_all_down(mem: *mut u8, size: usize, val: __m256i) {
+unsafe fn set_all_down(mem: *mut u8, size: usize, val: __m256i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
     
-    let chunk_size = size / CPUS;
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(val), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        // Iterate from end-32 down to start, stepping by 32
-        let mut j = ((end - start) / 32) * 32 + start;  // Last aligned position
-        while j >= start + 32 {
-            j -= 32;
-            set(mem_ptr, j, val);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx).rev(), ctx) {
+            set(mem_ptr, idx, val, cached);
         }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
     });
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_basic_tests(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_basic_tests(mem: *mut u8, size: usize, ctx: &TestContext) {
     let patterns = [0x00u8, 0xFF, 0x0F, 0xF0, 0x55, 0xAA];
     for pattern_val in &patterns {
         let pattern = _mm256_set1_epi8(*pattern_val as i8);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
-        set_all_down(mem, size, pattern);
-        get_all_down(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
     }
 }
 
+// One March element: every vector of every chunk is read (and checked against `expected`)
+// and then overwritten with `val`, walking the addresses either ascending or descending.
+// Indices are derived from whole vectors so descending walks stay aligned.
 #[cfg(target_arch = "x86_64")]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx2_march(mem: *mut u8, size: usize) {
+unsafe fn march_element(mem: *mut u8, size: usize, ascending: bool, expected: __m256i, val: __m256i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
-    
-    for _ in 0..2 {
-        let ones = _mm256_set1_epi8(0xFFu8 as i8);
-        let zeroes = _mm256_set1_epi8(0x00u8 as i8);
-        let chunk_size = size / CPUS;
-        
-        // Down: set zeroes
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(32) {
-                if j + 32 <= end {
-                    set(mem_ptr, j, zeroes);
-                }
-            }
-        });
-        
-        // Up: get zeroes, set ones, get ones, set zeroes, get zeroes, set ones
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(32) {
-                let idx = j + i * chunk_size;
-                get(mem_ptr as *const u8, idx, zeroes);
-                set(mem_ptr, idx, ones);
-                get(mem_ptr as *const u8, idx, ones);
-                set(mem_ptr, idx, zeroes);
-                get(mem_ptr as *const u8, idx, zeroes);
-                set(mem_ptr, idx, ones);
-            }
-        });
-        
-        // Up: get ones, set zeroes, set ones
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(32) {
-                let idx = j + i * chunk_size;
-                get(mem_ptr as *const u8, idx, ones);
-                set(mem_ptr, idx, zeroes);
-                set(mem_ptr, idx, ones);
-            }
-        });
-        
-        // Down: get ones, set zeroes, set ones, set zeroes
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(32) {
-                if j + 32 <= end {
-                    get(mem_ptr as *const u8, j, ones);
-                    set(mem_ptr, j, zeroes);
-                    set(mem_ptr, j, ones);
-                    set(mem_ptr, j, zeroes);
-                }
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+
+    let element = |i: usize| {
+        let mem_ptr = mem_usize as *mut u8;
+        if ascending {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val, cached);
             }
-        });
-        
-        // Down: get zeroes, set ones, set zeroes
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(32) {
-                if j + 32 <= end {
-                    get(mem_ptr as *const u8, j, zeroes);
-                    set(mem_ptr, j, ones);
-                    set(mem_ptr, j, zeroes);
-                }
+        } else {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx).rev(), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val, cached);
             }
-        });
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    };
+
+    if ascending {
+        chunk_order(ctx).into_par_iter().for_each(element);
+    } else {
+        chunk_order(ctx).into_par_iter().rev().for_each(element);
+    }
+}
+
+// March C-: {⇕(w0); ⇑(r0,w1); ⇑(r1,w0); ⇓(r0,w1); ⇓(r1,w0); ⇕(r0)}
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_march(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let ones = _mm256_set1_epi8(0xFFu8 as i8);
+    let zeroes = _mm256_set1_epi8(0x00u8 as i8);
+
+    for _ in 0..2 {
+        set_all_up(mem, size, zeroes, ctx);
+        march_element(mem, size, true, zeroes, ones, ctx);
+        march_element(mem, size, true, ones, zeroes, ctx);
+        march_element(mem, size, false, zeroes, ones, ctx);
+        march_element(mem, size, false, ones, zeroes, ctx);
+        get_all_down(mem as *const u8, size, zeroes, ctx);
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_random_inversions(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_random_inversions(mem: *mut u8, size: usize, ctx: &TestContext) {
     for _ in 0..16 {
-        let pattern = avx_xorshift128plus(&raw mut RNG);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        let pattern = random(ctx);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, not_pattern);
-        get_all_up(mem as *const u8, size, not_pattern);
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_inversions_left_64(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_inversions_left_64(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm256_slli_epi64::<$i>(_mm256_set1_epi64x(0x0000000000000001));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -253,15 +238,15 @@ pub unsafe fn avx2_moving_inversions_left_64(mem: *mut u8, size: usize) {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_inversions_right_32(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_inversions_right_32(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm256_srli_epi64::<$i>(_mm256_set1_epi32(0x80000000u32 as i32));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -281,15 +266,15 @@ pub unsafe fn avx2_moving_inversions_right_32(mem: *mut u8, size: usize) {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_inversions_left_16(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_inversions_left_16(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm256_slli_epi64::<$i>(_mm256_set1_epi16(0x0001));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -305,15 +290,15 @@ pub unsafe fn avx2_moving_inversions_left_16(mem: *mut u8, size: usize) {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_inversions_right_8(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_inversions_right_8(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm256_srli_epi64::<$i>(_mm256_set1_epi8(0x80u8 as i8));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -327,15 +312,15 @@ pub unsafe fn avx2_moving_inversions_right_8(mem: *mut u8, size: usize) {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_inversions_left_4(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_inversions_left_4(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm256_slli_epi64::<$i>(_mm256_set1_epi8(0x11u8 as i8));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -348,20 +333,20 @@ pub unsafe fn avx2_moving_inversions_left_4(mem: *mut u8, size: usize) {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_saturations_right_16(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_saturations_right_16(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_test {
         ($i:expr) => {{
             let pattern = _mm256_srli_epi16::<$i>(_mm256_set1_epi16(0x8000u16 as i16));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let zeroes = _mm256_set1_epi8(0x00u8 as i8);
-            set_all_up(mem, size, zeroes);
-            get_all_up(mem as *const u8, size, zeroes);
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, zeroes, ctx);
+            get_all_up(mem as *const u8, size, zeroes, ctx);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let ones = _mm256_set1_epi8(0xFFu8 as i8);
-            set_all_up(mem, size, ones);
-            get_all_up(mem as *const u8, size, ones);
+            set_all_up(mem, size, ones, ctx);
+            get_all_up(mem as *const u8, size, ones, ctx);
         }};
     }
     
@@ -377,20 +362,20 @@ pub unsafe fn avx2_moving_saturations_right_16(mem: *mut u8, size: usize) {
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_moving_saturations_left_8(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_moving_saturations_left_8(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_test {
         ($i:expr) => {{
             let pattern = _mm256_srli_epi16::<$i>(_mm256_set1_epi16(0x01));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let zeroes = _mm256_set1_epi8(0x00u8 as i8);
-            set_all_up(mem, size, zeroes);
-            get_all_up(mem as *const u8, size, zeroes);
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, zeroes, ctx);
+            get_all_up(mem as *const u8, size, zeroes, ctx);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let ones = _mm256_set1_epi8(0xFFu8 as i8);
-            set_all_up(mem, size, ones);
-            get_all_up(mem as *const u8, size, ones);
+            set_all_up(mem, size, ones, ctx);
+            get_all_up(mem as *const u8, size, ones, ctx);
         }};
     }
     
@@ -403,226 +388,169 @@ pub unsafe fn avx2_moving_saturations_left_8(mem: *mut u8, size: usize) {
     }
 }
 
+// Writes or verifies `pattern(offset)` at every vector of every chunk, ascending or descending.
+// Offsets are derived from whole vector indices and the chunk length is rounded down to whole
+// vectors, so neither direction produces misaligned offsets or spills into the next chunk.
 #[cfg(target_arch = "x86_64")]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx2_addressing(mem: *mut u8, size: usize) {
+unsafe fn address_sweep<F>(mem: *mut u8, size: usize, ascending: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> __m256i + Sync,
+{
     use rayon::prelude::*;
     let mem_usize = mem as usize;
-    let chunk_size = size / CPUS;
-    
-    for _ in 0..16 {
-        let increasing = _mm256_set_epi64x(24, 16, 8, 0);
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(32) {
-                let idx = j + i * chunk_size;
-                let addr_val = idx as i64;
-                let pattern = _mm256_add_epi64(_mm256_set1_epi64x(addr_val), increasing);
-                set(mem_ptr, idx, pattern);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *const u8;
-            for j in (0..chunk_size).step_by(32) {
-                let idx = j + i * chunk_size;
-                let addr_val = idx as i64;
-                let expected = _mm256_add_epi64(_mm256_set1_epi64x(addr_val), increasing);
-                get(mem_ptr, idx, expected);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(32) {
-                if j + 32 <= end {
-                    let addr_val = j as i64;
-                    let pattern = _mm256_add_epi64(_mm256_set1_epi64x(addr_val), increasing);
-                    set(mem_ptr, j, pattern);
-                }
-            }
-        });
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *const u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(32) {
-                if j + 32 <= end {
-                    let addr_val = j as i64;
-                    let expected = _mm256_add_epi64(_mm256_set1_epi64x(addr_val), increasing);
-                    get(mem_ptr, j, expected);
-                }
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let vectors = chunk_size / 32;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 32;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx), cached);
             }
-        });
+        };
+        if ascending {
+            until_stopped(0..vectors, ctx).for_each(visit);
+        } else {
+            until_stopped((0..vectors).rev(), ctx).for_each(visit);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+// Addressing test: every vector holds the addresses of its own 64-bit words, written and
+// verified ascending, then descending.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_addressing(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let increasing = _mm256_set_epi64x(24, 16, 8, 0);
+    let pattern = |idx: usize| _mm256_add_epi64(_mm256_set1_epi64x(idx as i64), increasing);
+
+    for _ in 0..16 {
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
+        address_sweep(mem, size, false, false, pattern, ctx);
+        address_sweep(mem, size, false, true, pattern, ctx);
     }
 }
 
+// SGEMM test: see sgemm.rs. Every round multiplies freshly seeded matrices in all chunks.
 #[cfg(target_arch = "x86_64")]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx2_sgemm(mem: *mut u8, size: usize) {
-    // SGEMM test requires OpenBLAS - skip if not available
-    // In Rust, we'd need bindings to OpenBLAS or implement a simple GEMM
-    // For now, we'll skip it like the C version does when OpenBLAS is not available
-    let _ = mem;
-    let _ = size;
+pub unsafe fn avx2_sgemm(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 32 * 32;
+
+    for _ in 0..SGEMM_ROUNDS {
+        let seed = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let errors = sgemm_chunk(mem_usize as *mut u8, i * chunk_size, chunk_size, seed ^ i as u64, ctx);
+            ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_walking_1(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_walking_1(mem: *mut u8, size: usize, ctx: &TestContext) {
     for bit in 0..64 {
         let pattern_val = 1u64 << bit;
         let pattern = _mm256_set1_epi64x(pattern_val as i64);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, not_pattern);
-        get_all_up(mem as *const u8, size, not_pattern);
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_walking_0(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_walking_0(mem: *mut u8, size: usize, ctx: &TestContext) {
     for bit in 0..64 {
         let pattern_val = !(1u64 << bit);
         let pattern = _mm256_set1_epi64x(pattern_val as i64);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, not_pattern);
-        get_all_up(mem as *const u8, size, not_pattern);
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_checkerboard(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_checkerboard(mem: *mut u8, size: usize, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
-    let chunk_size = size / CPUS;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
     
     let pattern1 = _mm256_set1_epi8(0xAAu8 as i8);
     let pattern2 = _mm256_set1_epi8(0x55u8 as i8);
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        for j in (0..chunk_size).step_by(32) {
+        for j in until_stopped((0..chunk_size).step_by(32), ctx) {
             let idx = j + i * chunk_size;
-            let pattern = if ((idx / 32) % 2) != 0 { pattern1 } else { pattern2 };
-            set(mem_ptr, idx, pattern);
+            let pattern = if !(idx / 32).is_multiple_of(2) { pattern1 } else { pattern2 };
+            set(mem_ptr, idx, pattern, cached);
         }
+        flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
     });
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        for j in (0..chunk_size).step_by(32) {
+        for j in until_stopped((0..chunk_size).step_by(32), ctx) {
             let idx = j + i * chunk_size;
-            let expected = if ((idx / 32) % 2) != 0 { pattern1 } else { pattern2 };
-            get(mem_ptr, idx, expected);
+            let expected = if !(idx / 32).is_multiple_of(2) { pattern1 } else { pattern2 };
+            get(mem_ptr, idx, expected, ctx);
         }
     });
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        for j in (0..chunk_size).step_by(32) {
+        for j in until_stopped((0..chunk_size).step_by(32), ctx) {
             let idx = j + i * chunk_size;
-            let pattern = if ((idx / 32) % 2) != 0 { pattern2 } else { pattern1 };
-            set(mem_ptr, idx, pattern);
+            let pattern = if !(idx / 32).is_multiple_of(2) { pattern2 } else { pattern1 };
+            set(mem_ptr, idx, pattern, cached);
         }
+        flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
     });
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        for j in (0..chunk_size).step_by(32) {
+        for j in until_stopped((0..chunk_size).step_by(32), ctx) {
             let idx = j + i * chunk_size;
-            let expected = if ((idx / 32) % 2) != 0 { pattern2 } else { pattern1 };
-            get(mem_ptr, idx, expected);
+            let expected = if !(idx / 32).is_multiple_of(2) { pattern2 } else { pattern1 };
+            get(mem_ptr, idx, expected, ctx);
         }
     });
 }
 
+// Address line test: own address, inverted address (descending) and the address XORed with
+// itself shifted by powers of two, so every address line is toggled against its neighbours.
 #[cfg(target_arch = "x86_64")]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx2_address_line_test(mem: *mut u8, size: usize) {
-    use rayon::prelude::*;
-    let mem_usize = mem as usize;
-    let chunk_size = size / CPUS;
-    
-    (0..CPUS).into_par_iter().for_each(|i| {
-        let mem_ptr = mem_usize as *mut u8;
-        for j in (0..chunk_size).step_by(32) {
-            let idx = j + i * chunk_size;
-            let addr_pattern = idx as u64;
-            let pattern = _mm256_set1_epi64x(addr_pattern as i64);
-            set(mem_ptr, idx, pattern);
-        }
-    });
-    
-    (0..CPUS).into_par_iter().for_each(|i| {
-        let mem_ptr = mem_usize as *const u8;
-        for j in (0..chunk_size).step_by(32) {
-            let idx = j + i * chunk_size;
-            let addr_pattern = idx as u64;
-            let expected = _mm256_set1_epi64x(addr_pattern as i64);
-            get(mem_ptr, idx, expected);
-        }
-    });
-    
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
-        let mem_ptr = mem_usize as *mut u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        for j in (start..end).rev().step_by(32) {
-            if j + 32 <= end {
-                let addr_pattern = !j as u64;
-                let pattern = _mm256_set1_epi64x(addr_pattern as i64);
-                set(mem_ptr, j, pattern);
-            }
-        }
-    });
-    
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
-        let mem_ptr = mem_usize as *const u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        for j in (start..end).rev().step_by(32) {
-            if j + 32 <= end {
-                let addr_pattern = !j as u64;
-                let expected = _mm256_set1_epi64x(addr_pattern as i64);
-                get(mem_ptr, j, expected);
-            }
-        }
-    });
-    
-    let mut shift = 1;
-    while shift <= 16 {
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(32) {
-                let idx = j + i * chunk_size;
-                let addr_pattern = idx as u64 ^ ((idx as u64) << shift);
-                let pattern = _mm256_set1_epi64x(addr_pattern as i64);
-                set(mem_ptr, idx, pattern);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *const u8;
-            for j in (0..chunk_size).step_by(32) {
-                let idx = j + i * chunk_size;
-                let addr_pattern = idx as u64 ^ ((idx as u64) << shift);
-                let expected = _mm256_set1_epi64x(addr_pattern as i64);
-                get(mem_ptr, idx, expected);
-            }
-        });
-        shift <<= 1;
+pub unsafe fn avx2_address_line_test(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let pattern = |idx: usize| _mm256_set1_epi64x(idx as i64);
+    address_sweep(mem, size, true, false, pattern, ctx);
+    address_sweep(mem, size, true, true, pattern, ctx);
+
+    let pattern = |idx: usize| _mm256_set1_epi64x(!idx as i64);
+    address_sweep(mem, size, false, false, pattern, ctx);
+    address_sweep(mem, size, false, true, pattern, ctx);
+
+    for shift in [1, 2, 4, 8, 16] {
+        let pattern = move |idx: usize| _mm256_set1_epi64x((idx as u64 ^ ((idx as u64) << shift)) as i64);
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_anti_patterns(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_anti_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
     let patterns = [
         0x00, 0xFF, 0x0F, 0xF0, 0x55, 0xAA, 0x33, 0xCC,
         0x11, 0xEE, 0x22, 0xDD, 0x44, 0xBB, 0x66, 0x99,
@@ -635,31 +563,31 @@ pub unsafe fn avx2_anti_patterns(mem: *mut u8, size: usize) {
         let pattern = _mm256_set1_epi8(*pattern_val as i8);
         let anti_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
-        set_all_up(mem, size, anti_pattern);
-        get_all_up(mem as *const u8, size, anti_pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_up(mem, size, anti_pattern, ctx);
+        get_all_up(mem as *const u8, size, anti_pattern, ctx);
         
-        set_all_down(mem, size, pattern);
-        get_all_down(mem as *const u8, size, pattern);
-        set_all_down(mem, size, anti_pattern);
-        get_all_down(mem as *const u8, size, anti_pattern);
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, anti_pattern, ctx);
+        get_all_down(mem as *const u8, size, anti_pattern, ctx);
     }
 }
 
 #[cfg(target_arch = "x86_64")]
-pub unsafe fn avx2_inverse_data_patterns(mem: *mut u8, size: usize) {
+pub unsafe fn avx2_inverse_data_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
     for byte_idx in 0..8 {
         let base_pattern = 0xFFFFFFFFFFFFFFFFu64;
         let pattern_val = base_pattern ^ (0xFFu64 << (byte_idx * 8));
         let pattern = _mm256_set1_epi64x(pattern_val as i64);
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         
         let inverse = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, inverse);
-        get_all_up(mem as *const u8, size, inverse);
+        set_all_up(mem, size, inverse, ctx);
+        get_all_up(mem as *const u8, size, inverse, ctx);
     }
     
     for word_idx in 0..4 {
@@ -667,12 +595,12 @@ pub unsafe fn avx2_inverse_data_patterns(mem: *mut u8, size: usize) {
         let pattern_val = base_pattern ^ (0xFFFFu64 << (word_idx * 16));
         let pattern = _mm256_set1_epi64x(pattern_val as i64);
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         
         let inverse = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, inverse);
-        get_all_up(mem as *const u8, size, inverse);
+        set_all_up(mem, size, inverse, ctx);
+        get_all_up(mem as *const u8, size, inverse, ctx);
     }
     
     for dword_idx in 0..2 {
@@ -680,53 +608,824 @@ pub unsafe fn avx2_inverse_data_patterns(mem: *mut u8, size: usize) {
         let pattern_val = base_pattern ^ (0xFFFFFFFFu64 << (dword_idx * 32));
         let pattern = _mm256_set1_epi64x(pattern_val as i64);
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         
         let inverse = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, inverse);
-        get_all_up(mem as *const u8, size, inverse);
+        set_all_up(mem, size, inverse, ctx);
+        get_all_up(mem as *const u8, size, inverse, ctx);
+    }
+}
+
+// Rows are assumed to be 8KiB (typical for DDR4/DDR5 x8 devices); the real
+// physical row mapping is unknown, so aggressors are simply placed two rows apart
+// with the victim row in between (double-sided hammering).
+#[cfg(target_arch = "x86_64")]
+const ROWHAMMER_ROW_SIZE: usize = 8 * 1024;
+#[cfg(target_arch = "x86_64")]
+const ROWHAMMER_STRIDE: usize = 1024 * 1024;
+#[cfg(target_arch = "x86_64")]
+const ROWHAMMER_ACTIVATIONS: usize = 65536;
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn rowhammer_pattern(idx: usize, pattern: __m256i, anti_pattern: __m256i) -> __m256i {
+    if !(idx / ROWHAMMER_ROW_SIZE).is_multiple_of(2) { anti_pattern } else { pattern }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn rowhammer_hammer(aggressor1: *const u8, aggressor2: *const u8) {
+    for _ in 0..ROWHAMMER_ACTIVATIONS {
+        std::ptr::read_volatile(aggressor1);
+        std::ptr::read_volatile(aggressor2);
+        _mm_clflush(aggressor1);
+        _mm_clflush(aggressor2);
+        _mm_mfence();
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_rowhammer(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+
+    for pattern_val in [0x55u8, 0xAA] {
+        let pattern = _mm256_set1_epi8(pattern_val as i8);
+        let anti_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
+
+        // stripe rows with pattern / anti-pattern so victims differ from their aggressors
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                let idx = j + i * chunk_size;
+                set(mem_ptr, idx, rowhammer_pattern(idx, pattern, anti_pattern), cached);
+            }
+            _mm_sfence();
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            let mut offset = 0;
+            while offset + 3 * ROWHAMMER_ROW_SIZE <= chunk_size && !stop_requested(ctx) {
+                let aggressor1 = mem_ptr.add(start + offset);
+                let aggressor2 = mem_ptr.add(start + offset + 2 * ROWHAMMER_ROW_SIZE);
+                rowhammer_hammer(aggressor1, aggressor2);
+                offset += ROWHAMMER_STRIDE;
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, rowhammer_pattern(idx, pattern, anti_pattern), ctx);
+            }
+        });
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_bit_fade(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in [0x00u8, 0xFF] {
+        let pattern = _mm256_set1_epi8(pattern_val as i8);
+        set_all_up(mem, size, pattern, ctx);
+        retention_sleep(ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+    }
+}
+
+// memtest86-style modulo-X test: a pattern is written to every 20th vector at the current
+// offset, its complement to all other vectors, then everything is verified.
+#[cfg(target_arch = "x86_64")]
+const MODULO: usize = 20;
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_modulo_20(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+
+    let pattern = random(ctx);
+    let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
+    let expected_at = |idx: usize, offset: usize| {
+        if (idx / 32) % MODULO == offset { pattern } else { not_pattern }
+    };
+
+    for offset in 0..MODULO {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 32) % MODULO == offset {
+                    set(mem_ptr, idx, pattern, cached);
+                }
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 32) % MODULO != offset {
+                    set(mem_ptr, idx, not_pattern, cached);
+                }
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, expected_at(idx, offset), ctx);
+            }
+        });
+    }
+}
+
+// Block move (memtest86 test 7 style): every chunk is filled with shifting patterns,
+// moved up by half a 4MiB block with overlapping copies (walking down so sources are
+// read before they get overwritten), moved back down (walking up) and verified. After
+// the round trip, the top `shift` bytes of a chunk hold a copy of the bytes below them.
+#[cfg(target_arch = "x86_64")]
+const BLOCK_MOVE_SIZE: usize = 4 * 1024 * 1024;
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn load(mem: *const u8, idx: usize) -> __m256i {
+    _mm256_load_si256((mem.add(idx)) as *const __m256i)
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn block_move_pattern(offset: usize, round: usize) -> __m256i {
+    // walking bit mixed with the vector index, so a misplaced block can't match by accident
+    let vector = (offset / 32) as u64;
+    let word = 1u64.rotate_left(((vector + round as u64) % 64) as u32) ^ vector.wrapping_mul(0x9E3779B97F4A7C15);
+    _mm256_set1_epi64x(word as i64)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_block_move(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let shift = (BLOCK_MOVE_SIZE.min(chunk_size) / 2) / 32 * 32;
+    if shift == 0 {
+        return;
     }
+    let moved = (chunk_size - shift) / 32;
+
+    for round in 0..4 {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                set(mem_ptr, start + j, block_move_pattern(j, round), cached);
+            }
+            for j in until_stopped((0..moved).rev(), ctx) {
+                let idx = start + j * 32;
+                set(mem_ptr, idx + shift, load(mem_ptr, idx), cached);
+            }
+            for j in until_stopped(0..moved, ctx) {
+                let idx = start + shift + j * 32;
+                set(mem_ptr, idx - shift, load(mem_ptr, idx), cached);
+            }
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(32), ctx) {
+                let source = if j < chunk_size - shift { j } else { j - shift };
+                get(mem_ptr, start + j, block_move_pattern(source, round), ctx);
+            }
+        });
+    }
+}
+
+// Random address test: every worker draws pseudo-random offsets inside its chunk from its own
+// xorshift128+ stream and writes address-derived data there; the stream is then replayed from
+// the same seed to verify. Catches address decode faults that linear sweeps walk past.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_random_address(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let vectors = chunk_size / 32;
+    if vectors == 0 {
+        return;
+    }
+
+    let mut seeds = [0u64; 4];
+    _mm256_storeu_si256(seeds.as_mut_ptr() as *mut __m256i, random(ctx));
+    let salt = seeds[0].rotate_left(32) ^ seeds[1];
+
+    let walk = |i: usize, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut key = AvxXorshift128PlusKey {
+            part1: _mm256_setzero_si256(),
+            part2: _mm256_setzero_si256(),
+        };
+        avx_xorshift128plus_init(seeds[0] ^ i as u64, seeds[1] | 1, &mut key);
+        let mut offsets = [0u64; 4];
+        for _ in until_stopped(0..vectors / 4, ctx) {
+            _mm256_storeu_si256(offsets.as_mut_ptr() as *mut __m256i, avx_xorshift128plus(&mut key));
+            for offset in offsets {
+                let idx = start + (offset as usize % vectors) * 32;
+                let val = _mm256_set1_epi64x((idx as u64 ^ salt) as i64);
+                if verify {
+                    get(mem_ptr as *const u8, idx, val, ctx);
+                } else {
+                    set(mem_ptr, idx, val, cached);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, false));
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, true));
+}
+
+// Prime stride test: every chunk is walked in (k * stride) mod n order for a few large prime
+// strides, so consecutive accesses land far apart and hardware prefetchers cannot mask faults
+// behind linear streams. When the stride shares a factor with the chunk length the walk closes
+// early; it then restarts one vector further until every vector has been visited once.
+#[cfg(target_arch = "x86_64")]
+const PRIME_STRIDES: [usize; 2] = [13, 131071];
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn prime_walk(mem: *mut u8, size: usize, stride: usize, pattern: __m256i, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let vectors = chunk_size / 32;
+    if vectors == 0 {
+        return;
+    }
+    let step = stride % vectors;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut pos = 0;
+        let mut cycle = 0;
+        for _ in until_stopped(0..vectors, ctx) {
+            let idx = start + pos * 32;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern, ctx);
+            } else {
+                set(mem_ptr, idx, pattern, cached);
+            }
+            pos += step;
+            if pos >= vectors {
+                pos -= vectors;
+            }
+            if pos == cycle {
+                cycle += 1;
+                pos = cycle;
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_prime_stride(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for stride in PRIME_STRIDES {
+        let pattern = random(ctx);
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+
+        let pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+    }
+}
+
+// Custom patterns test: every 64-bit pattern of the `patterns=` list in manganese.conf and its
+// complement, so vendor-specific failing patterns can be reproduced without recompiling.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_custom_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in custom_patterns(ctx) {
+        let pattern = _mm256_set1_epi64x(pattern_val as i64);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
+    }
+}
+
+// Intra cache line walking bit tests: a single set (walking-1) or cleared (walking-0) bit walks
+// across all 512 bit positions of a 64-byte cache line, so every line carries a different value
+// per lane and adjacent-bit coupling inside one burst is exercised, unlike the broadcast
+// walking tests.
+#[cfg(target_arch = "x86_64")]
+const LINE_VECTORS: usize = 64 / 32;
+
+// Writes or verifies `line[k]` at the k-th vector of every 64-byte line
+#[cfg(target_arch = "x86_64")]
+unsafe fn line_sweep(mem: *mut u8, size: usize, line: &[__m256i; LINE_VECTORS], verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for j in until_stopped((0..chunk_size / 32).map(|v| start + v * 32), ctx) {
+            let val = line[(j % 64) / 32];
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val, cached);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn walk_line(mem: *mut u8, size: usize, zero: bool, ctx: &TestContext) {
+    for bit in 0..512 {
+        let mut words = [0u64; 8];
+        words[bit / 64] = 1 << (bit % 64);
+        if zero {
+            words = words.map(|w| !w);
+        }
+        let line: [__m256i; LINE_VECTORS] = std::array::from_fn(|k| _mm256_loadu_si256(words.as_ptr().add(k * 4) as *const __m256i));
+        line_sweep(mem, size, &line, false, ctx);
+        line_sweep(mem, size, &line, true, ctx);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_walking_1_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, false, ctx);
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_walking_0_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, true, ctx);
+}
+
+// Refresh stress test: one row-sized window per megabyte is rewritten and re-read back to back
+// (pattern and complement alternating) for the configured dwell time, by default a whole 64ms
+// refresh window, before moving on, so every refresh of that row happens under sustained
+// activation. Exposes marginal refresh and retention behaviour.
+#[cfg(target_arch = "x86_64")]
+const REFRESH_WINDOW: usize = 8 * 1024;
+#[cfg(target_arch = "x86_64")]
+const REFRESH_STRIDE: usize = 1024 * 1024;
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_refresh_stress(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    use std::time::Instant;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let dwell = refresh_dwell(ctx);
+    let pattern = random(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for base in (start..start + chunk_size.saturating_sub(REFRESH_WINDOW - 1)).step_by(REFRESH_STRIDE) {
+            let deadline = Instant::now() + dwell;
+            let mut val = pattern;
+            while Instant::now() < deadline && !stop_requested(ctx) {
+                for j in (base..base + REFRESH_WINDOW).step_by(32) {
+                    set(mem_ptr, j, val, cached);
+                }
+                flush_sweep(mem_ptr.add(base), REFRESH_WINDOW, ctx);
+                for j in (base..base + REFRESH_WINDOW).step_by(32) {
+                    get(mem_ptr as *const u8, j, val, ctx);
+                }
+                val = _mm256_xor_si256(val, _mm256_set1_epi8(0xFFu8 as i8));
+            }
+        }
+    });
+}
+
+// LFSR fill test: every chunk is filled with the continuous output of a maximal-length 64-bit
+// LFSR (one step per 64-bit word), then the sequence is regenerated to verify it, once plain and
+// once complemented. Unlike the uniform patterns every address holds a different value, so
+// aliased addresses show up as miscompares.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_lfsr_fill(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let seed = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+
+    let sweep = |i: usize, invert: bool, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        // the all-zero state is the only one the LFSR never leaves
+        let mut state = (seed ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15)).max(1);
+        let mut words = [0u64; 4];
+        for j in until_stopped((0..chunk_size / 32).map(|v| start + v * 32), ctx) {
+            for word in words.iter_mut() {
+                state = lfsr64_next(state);
+                *word = if invert { !state } else { state };
+            }
+            let val = _mm256_loadu_si256(words.as_ptr() as *const __m256i);
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val, cached);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    for invert in [false, true] {
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, false));
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, true));
+    }
+}
+
+// Channel stripes test: the region is split into interleave-sized stripes assigned round-robin
+// to the populated memory channels (channel = offset / interleave % channels). This matches the
+// physical interleave for simple modulo interleaving as long as interleave × channels does not
+// exceed the page size, since page offsets are identical in virtual and physical addresses.
+// All threads test one channel's stripes at a time while the others idle, so miscompares can be
+// attributed to a channel.
+#[cfg(target_arch = "x86_64")]
+unsafe fn stripe_sweep(mem: *mut u8, size: usize, channel: usize, pattern: __m256i, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let interleave = channel_interleave(ctx);
+    let period = interleave * channel_count(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let end = start + chunk_size;
+        // first stripe of this channel at or before the chunk start
+        let first = start / period * period + channel * interleave;
+        for stripe in until_stopped((first..end).step_by(period), ctx) {
+            for j in (stripe.max(start)..(stripe + interleave).min(end)).step_by(32) {
+                if verify {
+                    get(mem_ptr as *const u8, j, pattern, ctx);
+                } else {
+                    set(mem_ptr, j, pattern, cached);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_channel_stripes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for channel in 0..channel_count(ctx) {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        let random = random(ctx);
+        let patterns = [random, _mm256_xor_si256(random, _mm256_set1_epi8(0xFFu8 as i8)), _mm256_set1_epi8(0x55u8 as i8), _mm256_set1_epi8(0xAAu8 as i8)];
+        for pattern in patterns {
+            stripe_sweep(mem, size, channel, pattern, false, ctx);
+            stripe_sweep(mem, size, channel, pattern, true, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on stripes of channel {}", errors, channel_name(channel, ctx));
+        }
+    }
+}
+
+// Coherence ping-pong test: see coherence.rs. Runs on its own thread pairs, not the rayon pool.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_coherence_ping_pong(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+    let errors = coherence_ping_pong(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// Pointer-chase test: see pointer_chase.rs.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_pointer_chase(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+    let errors = pointer_chase(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// Mixed read/write test: the region holds address-derived values of one generation, then every
+// thread rewrites its own chunk with the next generation while issuing `reads` random reads
+// anywhere in the region for every `writes` writes (see `rw_ratio()`), so reads and writes of all
+// threads interleave in the same region like in real workloads. A random read may race another
+// thread's write and accepts either generation; a full verify of the new generation ends each round.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_mixed_rw(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let (reads, writes) = rw_ratio(ctx);
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let vectors = chunk_size / 32;
+    let word = |idx: usize, seed: u64| (idx as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ seed;
+    let pattern = |idx: usize, seed: u64| _mm256_set1_epi64x(word(idx, seed) as i64);
+    // a vector read while another thread stores it may be torn, every lane holds the old or the new word
+    let bad_lanes = |actual: __m256i, old: __m256i, new: __m256i| {
+        let ok = _mm256_or_si256(_mm256_cmpeq_epi64(actual, old), _mm256_cmpeq_epi64(actual, new));
+        !_mm256_movemask_pd(_mm256_castsi256_pd(ok)) & 0xF
+    };
+
+    let mut old = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+    address_sweep(mem, size, true, false, |idx| pattern(idx, old), ctx);
+    flush_sweep(mem, size, ctx);
+
+    for _ in 0..4 {
+        let new = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let mut key = (new ^ i as u64) | 1;
+            for v in until_stopped((0..vectors).step_by(writes), ctx) {
+                for idx in (v..(v + writes).min(vectors)).map(|v| i * chunk_size + v * 32) {
+                    set(mem_ptr, idx, pattern(idx, new), cached);
+                }
+                for _ in 0..reads {
+                    key ^= key << 13;
+                    key ^= key >> 7;
+                    key ^= key << 17;
+                    let idx = (key as usize % (vectors * ctx.cpus)) * 32;
+                    let actual = _mm256_load_si256(mem_ptr.add(idx) as *const __m256i);
+                    let bad = bad_lanes(actual, pattern(idx, old), pattern(idx, new));
+                    if bad != 0 {
+                        if log_miscompare(ctx) {
+                            error!("errors detected at offset 0x{:016x}", idx);
+                        }
+                        let mut lanes = [0u64; 4];
+                        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, actual);
+                        for lane in (0..4).filter(|lane| bad & (1 << lane) != 0) {
+                            record_error(idx + lane * 8, word(idx, new), lanes[lane], ctx);
+                            ctx.run.errors.fetch_add(miscompared_bytes(word(idx, new), lanes[lane]), std::sync::atomic::Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+        });
+        flush_sweep(mem, size, ctx);
+        address_sweep(mem, size, true, true, |idx| pattern(idx, new), ctx);
+        old = new;
+    }
+}
+
+// Thermal cycle test: a heat phase toggling every bit at full bandwidth (0x55/0xAA written and
+// verified back to back) for `heat_duration()`, followed by an idle cooldown of the configured
+// sleep time. Temperatures are logged after both phases, one loop is one cycle.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_thermal_cycle(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    let deadline = std::time::Instant::now() + heat_duration(ctx);
+    while std::time::Instant::now() < deadline && !stop_requested(ctx) {
+        for pattern in [_mm256_set1_epi8(0x55u8 as i8), _mm256_set1_epi8(0xAAu8 as i8)] {
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem, size, pattern, ctx);
+        }
+    }
+    log_temperatures("heat", ctx.run.errors.load(Ordering::Relaxed));
+
+    retention_sleep(ctx);
+    log_temperatures("cooldown", ctx.run.errors.load(Ordering::Relaxed));
+}
+
+// Byte lane test: walks a single set bit (and its complement) through one byte lane of every
+// 64-bit word while the other seven lanes hold 0x00 (0xFF), lane by lane. A byte lane maps to one
+// DQ group / chip on the module, so errors are reported per lane to point at the failing chip.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_byte_lanes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for lane in 0..8 {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        for bit in 0..8 {
+            let pattern_val = 1u64 << (lane * 8 + bit);
+            let pattern = _mm256_set1_epi64x(pattern_val as i64);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
+            let not_pattern = _mm256_xor_si256(pattern, _mm256_set1_epi8(0xFFu8 as i8));
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on byte lane {}", errors, lane);
+        }
+    }
+}
+
+// Visits the vectors of every chunk in butterfly order: the outermost pair (first, last), then
+// pairs converging on the middle, or the same pairs diverging from the middle outwards.
+#[cfg(target_arch = "x86_64")]
+unsafe fn butterfly_sweep<F>(mem: *mut u8, size: usize, converging: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> __m256i + Sync,
+{
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    let vectors = chunk_size / 32;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 32;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx), cached);
+            }
+        };
+        let pair = |k: usize| {
+            visit(k);
+            if vectors - 1 - k != k {
+                visit(vectors - 1 - k);
+            }
+        };
+        if converging {
+            until_stopped(0..vectors.div_ceil(2), ctx).for_each(pair);
+        } else {
+            until_stopped((0..vectors.div_ceil(2)).rev(), ctx).for_each(pair);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+// Butterfly test: address-derived data written with converging offset pairs (i, n-1-i) and
+// verified diverging, then complemented and run the other way round. The alternating far/near
+// accesses flip most row and column address bits on every step, which trips decoder faults that
+// monotonic sweeps miss.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_butterfly(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..4 {
+        let salt = _mm256_extract_epi64::<0>(random(ctx)) as u64;
+        let pattern = |idx: usize| _mm256_set1_epi64x(((idx as u64) ^ salt) as i64);
+        butterfly_sweep(mem, size, true, false, pattern, ctx);
+        butterfly_sweep(mem, size, false, true, pattern, ctx);
+        let inverse = |idx: usize| _mm256_xor_si256(pattern(idx), _mm256_set1_epi8(0xFFu8 as i8));
+        butterfly_sweep(mem, size, false, false, inverse, ctx);
+        butterfly_sweep(mem, size, true, true, inverse, ctx);
+    }
+}
+
+/// Read `mem[..size]` once up the chunks of every thread, prefetching like the verify loops but
+/// comparing nothing, for `calibrate_prefetch()`.
+#[cfg(target_arch = "x86_64")]
+pub unsafe fn avx2_read_sweep(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 32 * 32;
+    (0..ctx.cpus).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        let mut any = _mm256_setzero_si256();
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 32, ctx), ctx) {
+            prefetch_ahead(mem_ptr, idx, true, ctx);
+            any = _mm256_or_si256(any, _mm256_load_si256(mem_ptr.add(idx) as *const __m256i));
+        }
+        std::hint::black_box(any);
+    });
 }
 
 //FIXME: remove stubs and/or error out when running in unsupported configuration
 // Stub implementations for non-x86_64 targets
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_tests_init(_cpus: usize, _errors: *const AtomicU64) {}
+pub unsafe fn avx2_basic_tests(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_read_sweep(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_basic_tests(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_march(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_march(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_random_inversions(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_random_inversions(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_inversions_left_64(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_inversions_left_64(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_inversions_right_32(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_inversions_right_32(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_inversions_left_16(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_inversions_left_16(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_inversions_right_8(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_inversions_right_8(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_inversions_left_4(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_inversions_left_4(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_saturations_right_16(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_saturations_right_16(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_moving_saturations_left_8(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_moving_saturations_left_8(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_addressing(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_addressing(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_sgemm(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_sgemm(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_walking_1(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_walking_1(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_walking_0(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_walking_0(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_checkerboard(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_checkerboard(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_address_line_test(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_address_line_test(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_anti_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_anti_patterns(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_inverse_data_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(target_arch = "x86_64"))]
-pub unsafe fn avx2_inverse_data_patterns(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx2_rowhammer(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_bit_fade(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_modulo_20(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_block_move(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_random_address(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_prime_stride(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_custom_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_walking_1_line(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_walking_0_line(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_refresh_stress(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_lfsr_fill(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_channel_stripes(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_coherence_ping_pong(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_pointer_chase(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_mixed_rw(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_thermal_cycle(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_byte_lanes(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "x86_64"))]
+pub unsafe fn avx2_butterfly(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use crate::tests::{Pause, RunState};
+    use super::*;
+
+    const GUARD: usize = 256;
+
+    // Per-thread chunks that are not whole vectors used to make the descending walks misaligned
+    // and let the last vector spill past the chunk; run every size on a guarded buffer.
+    #[test]
+    fn addressing_small_buffers() {
+        let stop = AtomicBool::new(false);
+        let pause = Pause::default();
+        let run = RunState::new(4, 1, 0, &stop, &pause);
+        for cpus in [1, 3, 4] {
+            for size in [0, 32, 100, 4096, 3 * 4096 + 96, 64 * 1024 + 8] {
+                unsafe {
+                    let ctx = TestContext { cpus, ..TestContext::new(&run) };
+                    let layout = Layout::from_size_align(size + GUARD, 4096).unwrap();
+                    let mem = alloc_zeroed(layout);
+                    std::ptr::write_bytes(mem.add(size), 0xA5, GUARD);
+
+                    avx2_addressing(mem, size, &ctx);
+                    avx2_address_line_test(mem, size, &ctx);
+
+                    let guard = std::slice::from_raw_parts(mem.add(size), GUARD);
+                    assert!(guard.iter().all(|&b| b == 0xA5), "write past end, size {} cpus {}", size, cpus);
+                    dealloc(mem, layout);
+                }
+            }
+        }
+        assert_eq!(run.errors.load(Ordering::Relaxed), 0);
+    }
+}
@@ -1,228 +1,210 @@
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
 use std::arch::x86_64::*;
-use std::sync::atomic::AtomicU64;
 use log::error;
+use crate::tests::TestContext;
 use crate::simd_xorshift::Avx512Xorshift128PlusKey;
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-use crate::simd_xorshift::{avx512_xorshift128plus, avx512_xorshift128plus_init};
-
-static mut CPUS: usize = 0;
-static mut ERRORS: *const AtomicU64 = std::ptr::null();
-static mut RNG: Avx512Xorshift128PlusKey = Avx512Xorshift128PlusKey {
-    part1: unsafe { std::mem::zeroed() },
-    part2: unsafe { std::mem::zeroed() },
-};
+use crate::tests::{cached_stores, channel_count, channel_interleave, channel_name, chunk_offsets, chunk_order, custom_patterns, flush_chunk, flush_sweep, heat_duration, inject_faults, lfsr64_next, log_miscompare, log_temperatures, miscompared_bytes, prefetch_ahead, record_error, record_mismatches, refresh_dwell, retention_sleep, rw_ratio, stop_requested, tail_get, tail_set, until_stopped};
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+use crate::coherence::coherence_ping_pong;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+use crate::pointer_chase::pointer_chase;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+use crate::sgemm::{sgemm_chunk, SGEMM_ROUNDS};
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+use crate::simd_xorshift::{avx512_xorshift128plus, avx512_xorshift128plus_init, avx512_xorshift128plus_lanes};
 
+// Next random vector of the run's generator
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_tests_init(cpus: usize, errors: *const AtomicU64) {
-    CPUS = cpus;
-    ERRORS = errors;
-    
-    let mut r1 = 0u64;
-    let mut r2 = 0u64;
-    while r1 == 0 && r2 == 0 {
-        _rdrand64_step(&mut r1);
-        _rdrand64_step(&mut r2);
-    }
-    avx512_xorshift128plus_init(r1, r2, &raw mut RNG);
+unsafe fn random(ctx: &TestContext) -> __m512i {
+    avx512_xorshift128plus_lanes(&mut ctx.run.rng.lock().unwrap())
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-unsafe fn get(mem: *const u8, idx: usize, expected: __m512i) {
+unsafe fn get(mem: *const u8, idx: usize, expected: __m512i, ctx: &TestContext) {
     let actual = _mm512_load_si512((mem.add(idx)) as *const __m512i);
     _mm_lfence();
     let result = _mm512_cmp_epu8_mask(expected, actual, _MM_CMPINT_NE);
     
     if result != 0 {
         let error_total = result.count_ones() as u64;
-        error!("{} errors detected at offset 0x{:016x} [error mask: 0x{:016x}]", error_total, idx, result);
-        (*ERRORS).fetch_add(error_total, std::sync::atomic::Ordering::Relaxed);
+        if log_miscompare(ctx) {
+            error!("{} errors detected at offset 0x{:016x} [error mask: 0x{:016x}]", error_total, idx, result);
+        }
+        record_mismatches(idx, &to_bytes(expected), &to_bytes(actual), ctx);
+        ctx.run.errors.fetch_add(error_total, std::sync::atomic::Ordering::Relaxed);
     }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-unsafe fn get_all_up(mem: *const u8, size: usize, expected: __m512i) {
+unsafe fn to_bytes(val: __m512i) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    _mm512_storeu_si512(bytes.as_mut_ptr() as *mut __m512i, val);
+    bytes
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn get_all_up(mem: *const u8, size: usize, expected: __m512i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    inject_faults(mem, size, &to_bytes(expected), ctx);
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        let chunk_size = size / CPUS;
-        for j in (0..chunk_size).step_by(64) {
-            let idx = j + i * chunk_size;
-            get(mem_ptr, idx, expected);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx), ctx) {
+            prefetch_ahead(mem_ptr, idx, true, ctx);
+            get(mem_ptr, idx, expected, ctx);
         }
     });
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(expected), true, ctx);
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-unsafe fn get_all_down(mem: *const u8, size: usize, expected: __m512i) {
+unsafe fn get_all_down(mem: *const u8, size: usize, expected: __m512i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
     
-    let chunk_size = size / CPUS;
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    inject_faults(mem, size, &to_bytes(expected), ctx);
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(expected), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        // Iterate from end-64 down to start, stepping by 64
-        let mut j = ((end - start) / 64) * 64 + start;  // Last aligned position
-        while j >= start + 64 {
-            j -= 64;
-            get(mem_ptr, j, expected);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx).rev(), ctx) {
+            prefetch_ahead(mem_ptr, idx, false, ctx);
+            get(mem_ptr, idx, expected, ctx);
         }
     });
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-unsafe fn set(mem: *mut u8, idx: usize, val: __m512i) {
-    _mm512_stream_si512((mem.add(idx)) as *mut __m512i, val);
+unsafe fn set(mem: *mut u8, idx: usize, val: __m512i, cached: bool) {
+    if cached {
+        _mm512_store_si512((mem.add(idx)) as *mut __m512i, val);
+    } else {
+        _mm512_stream_si512((mem.add(idx)) as *mut __m512i, val);
+    }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-unsafe fn set_all_up(mem: *mut u8, size: usize, val: __m512i) {
+unsafe fn set_all_up(mem: *mut u8, size: usize, val: __m512i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        let chunk_size = size / CPUS;
-        for j in (0..chunk_size).step_by(64) {
-            let idx = j + i * chunk_size;
-            set(mem_ptr, idx, val);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx), ctx) {
+            set(mem_ptr, idx, val, cached);
         }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
     });
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(val), true, ctx);
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-unsafe fn set_all_down(mem: *mut u8, size: usize, val: __m512i) {
+unsafe fn set_all_down(mem: *mut u8, size: usize, val: __m512i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
     
-    let chunk_size = size / CPUS;
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(val), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        // Iterate from end-64 down to start, stepping by 64
-        let mut j = ((end - start) / 64) * 64 + start;  // Last aligned position
-        while j >= start + 64 {
-            j -= 64;
-            set(mem_ptr, j, val);
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx).rev(), ctx) {
+            set(mem_ptr, idx, val, cached);
         }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
     });
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_basic_tests(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_basic_tests(mem: *mut u8, size: usize, ctx: &TestContext) {
     let patterns = [0x00u8, 0xFF, 0x0F, 0xF0, 0x55, 0xAA];
     for pattern_val in &patterns {
         let pattern = _mm512_set1_epi8(*pattern_val as i8);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
-        set_all_down(mem, size, pattern);
-        get_all_down(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
     }
 }
 
+// One March element: every vector of every chunk is read (and checked against `expected`)
+// and then overwritten with `val`, walking the addresses either ascending or descending.
+// Indices are derived from whole vectors so descending walks stay aligned.
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx512_march(mem: *mut u8, size: usize) {
+unsafe fn march_element(mem: *mut u8, size: usize, ascending: bool, expected: __m512i, val: __m512i, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
-    
-    for _ in 0..2 {
-        let ones = _mm512_set1_epi8(0xFFu8 as i8);
-        let zeroes = _mm512_set1_epi8(0x00u8 as i8);
-        let chunk_size = size / CPUS;
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(64) {
-                if j + 64 <= end {
-                    set(mem_ptr, j, zeroes);
-                }
-            }
-        });
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(64) {
-                let idx = j + i * chunk_size;
-                get(mem_ptr as *const u8, idx, zeroes);
-                set(mem_ptr, idx, ones);
-                get(mem_ptr as *const u8, idx, ones);
-                set(mem_ptr, idx, zeroes);
-                get(mem_ptr as *const u8, idx, zeroes);
-                set(mem_ptr, idx, ones);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(64) {
-                let idx = j + i * chunk_size;
-                get(mem_ptr as *const u8, idx, ones);
-                set(mem_ptr, idx, zeroes);
-                set(mem_ptr, idx, ones);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(64) {
-                if j + 64 <= end {
-                    get(mem_ptr as *const u8, j, ones);
-                    set(mem_ptr, j, zeroes);
-                    set(mem_ptr, j, ones);
-                    set(mem_ptr, j, zeroes);
-                }
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+
+    let element = |i: usize| {
+        let mem_ptr = mem_usize as *mut u8;
+        if ascending {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val, cached);
             }
-        });
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(64) {
-                if j + 64 <= end {
-                    get(mem_ptr as *const u8, j, zeroes);
-                    set(mem_ptr, j, ones);
-                    set(mem_ptr, j, zeroes);
-                }
+        } else {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx).rev(), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val, cached);
             }
-        });
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    };
+
+    if ascending {
+        chunk_order(ctx).into_par_iter().for_each(element);
+    } else {
+        chunk_order(ctx).into_par_iter().rev().for_each(element);
     }
 }
 
+// March C-: {⇕(w0); ⇑(r0,w1); ⇑(r1,w0); ⇓(r0,w1); ⇓(r1,w0); ⇕(r0)}
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_random_inversions(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_march(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let ones = _mm512_set1_epi8(0xFFu8 as i8);
+    let zeroes = _mm512_set1_epi8(0x00u8 as i8);
+
+    for _ in 0..2 {
+        set_all_up(mem, size, zeroes, ctx);
+        march_element(mem, size, true, zeroes, ones, ctx);
+        march_element(mem, size, true, ones, zeroes, ctx);
+        march_element(mem, size, false, zeroes, ones, ctx);
+        march_element(mem, size, false, ones, zeroes, ctx);
+        get_all_down(mem as *const u8, size, zeroes, ctx);
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_random_inversions(mem: *mut u8, size: usize, ctx: &TestContext) {
     for _ in 0..16 {
-        let pattern = avx512_xorshift128plus(&raw mut RNG);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        let pattern = random(ctx);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, not_pattern);
-        get_all_up(mem as *const u8, size, not_pattern);
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
     }
 }
 
 // Moving inversions for AVX-512 - using macros for compile-time constant shifts
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_inversions_left_64(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_inversions_left_64(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm512_slli_epi64::<$i>(_mm512_set1_epi64(0x0000000000000001));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -250,15 +232,15 @@ pub unsafe fn avx512_moving_inversions_left_64(mem: *mut u8, size: usize) {
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_inversions_right_32(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_inversions_right_32(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm512_srli_epi64::<$i>(_mm512_set1_epi32(0x80000000u32 as i32));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -278,15 +260,15 @@ pub unsafe fn avx512_moving_inversions_right_32(mem: *mut u8, size: usize) {
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_inversions_left_16(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_inversions_left_16(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm512_slli_epi64::<$i>(_mm512_set1_epi16(0x0001u16 as i16));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -302,15 +284,15 @@ pub unsafe fn avx512_moving_inversions_left_16(mem: *mut u8, size: usize) {
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_inversions_right_8(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_inversions_right_8(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm512_srli_epi64::<$i>(_mm512_set1_epi8(0x80u8 as i8));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -324,15 +306,15 @@ pub unsafe fn avx512_moving_inversions_right_8(mem: *mut u8, size: usize) {
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_inversions_left_4(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_inversions_left_4(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_shift {
         ($i:expr) => {{
             let pattern = _mm512_slli_epi64::<$i>(_mm512_set1_epi8(0x11u8 as i8));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-            set_all_up(mem, size, not_pattern);
-            get_all_up(mem as *const u8, size, not_pattern);
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
         }};
     }
     
@@ -345,20 +327,20 @@ pub unsafe fn avx512_moving_inversions_left_4(mem: *mut u8, size: usize) {
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_saturations_right_16(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_saturations_right_16(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_test {
         ($i:expr) => {{
             let pattern = _mm512_srli_epi16::<$i>(_mm512_set1_epi16(0x8000u16 as i16));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let zeroes = _mm512_set1_epi8(0x00u8 as i8);
-            set_all_up(mem, size, zeroes);
-            get_all_up(mem as *const u8, size, zeroes);
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, zeroes, ctx);
+            get_all_up(mem as *const u8, size, zeroes, ctx);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let ones = _mm512_set1_epi8(0xFFu8 as i8);
-            set_all_up(mem, size, ones);
-            get_all_up(mem as *const u8, size, ones);
+            set_all_up(mem, size, ones, ctx);
+            get_all_up(mem as *const u8, size, ones, ctx);
         }};
     }
     
@@ -374,20 +356,20 @@ pub unsafe fn avx512_moving_saturations_right_16(mem: *mut u8, size: usize) {
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_moving_saturations_left_8(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_moving_saturations_left_8(mem: *mut u8, size: usize, ctx: &TestContext) {
     macro_rules! do_test {
         ($i:expr) => {{
             let pattern = _mm512_srli_epi16::<$i>(_mm512_set1_epi16(0x01u16 as i16));
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let zeroes = _mm512_set1_epi8(0x00u8 as i8);
-            set_all_up(mem, size, zeroes);
-            get_all_up(mem as *const u8, size, zeroes);
-            set_all_up(mem, size, pattern);
-            get_all_up(mem as *const u8, size, pattern);
+            set_all_up(mem, size, zeroes, ctx);
+            get_all_up(mem as *const u8, size, zeroes, ctx);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
             let ones = _mm512_set1_epi8(0xFFu8 as i8);
-            set_all_up(mem, size, ones);
-            get_all_up(mem as *const u8, size, ones);
+            set_all_up(mem, size, ones, ctx);
+            get_all_up(mem as *const u8, size, ones, ctx);
         }};
     }
     
@@ -400,224 +382,163 @@ pub unsafe fn avx512_moving_saturations_left_8(mem: *mut u8, size: usize) {
     }
 }
 
+// See tests_avx2.rs for the address sweep.
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx512_addressing(mem: *mut u8, size: usize) {
+unsafe fn address_sweep<F>(mem: *mut u8, size: usize, ascending: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> __m512i + Sync,
+{
     use rayon::prelude::*;
     let mem_usize = mem as usize;
-    let chunk_size = size / CPUS;
-    
-    for _ in 0..16 {
-        let increasing = _mm512_set_epi64(56, 48, 40, 32, 24, 16, 8, 0);
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(64) {
-                let idx = j + i * chunk_size;
-                let addr_val = idx as i64;
-                let pattern = _mm512_add_epi64(_mm512_set1_epi64(addr_val), increasing);
-                set(mem_ptr, idx, pattern);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *const u8;
-            for j in (0..chunk_size).step_by(64) {
-                let idx = j + i * chunk_size;
-                let addr_val = idx as i64;
-                let expected = _mm512_add_epi64(_mm512_set1_epi64(addr_val), increasing);
-                get(mem_ptr, idx, expected);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(64) {
-                if j + 64 <= end {
-                    let addr_val = j as i64;
-                    let pattern = _mm512_add_epi64(_mm512_set1_epi64(addr_val), increasing);
-                    set(mem_ptr, j, pattern);
-                }
-            }
-        });
-        
-        (0..CPUS).into_par_iter().rev().for_each(|i| {
-            let mem_ptr = mem_usize as *const u8;
-            let start = i * chunk_size;
-            let end = start + chunk_size;
-            for j in (start..end).rev().step_by(64) {
-                if j + 64 <= end {
-                    let addr_val = j as i64;
-                    let expected = _mm512_add_epi64(_mm512_set1_epi64(addr_val), increasing);
-                    get(mem_ptr, j, expected);
-                }
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let vectors = chunk_size / 64;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 64;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx), cached);
             }
-        });
+        };
+        if ascending {
+            until_stopped(0..vectors, ctx).for_each(visit);
+        } else {
+            until_stopped((0..vectors).rev(), ctx).for_each(visit);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_addressing(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let increasing = _mm512_set_epi64(56, 48, 40, 32, 24, 16, 8, 0);
+    let pattern = |idx: usize| _mm512_add_epi64(_mm512_set1_epi64(idx as i64), increasing);
+
+    for _ in 0..16 {
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
+        address_sweep(mem, size, false, false, pattern, ctx);
+        address_sweep(mem, size, false, true, pattern, ctx);
     }
 }
 
+// See sgemm.rs for the SGEMM test.
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx512_sgemm(mem: *mut u8, size: usize) {
-    // SGEMM test requires OpenBLAS - skip if not available
-    let _ = mem;
-    let _ = size;
+pub unsafe fn avx512_sgemm(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 64 * 64;
+
+    for _ in 0..SGEMM_ROUNDS {
+        let seed = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let errors = sgemm_chunk(mem_usize as *mut u8, i * chunk_size, chunk_size, seed ^ i as u64, ctx);
+            ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_walking_1(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_walking_1(mem: *mut u8, size: usize, ctx: &TestContext) {
     for bit in 0..64 {
         let pattern_val = 1u64 << bit;
         let pattern = _mm512_set1_epi64(pattern_val as i64);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, not_pattern);
-        get_all_up(mem as *const u8, size, not_pattern);
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
     }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_walking_0(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_walking_0(mem: *mut u8, size: usize, ctx: &TestContext) {
     for bit in 0..64 {
         let pattern_val = !(1u64 << bit);
         let pattern = _mm512_set1_epi64(pattern_val as i64);
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, not_pattern);
-        get_all_up(mem as *const u8, size, not_pattern);
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
     }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_checkerboard(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_checkerboard(mem: *mut u8, size: usize, ctx: &TestContext) {
     use rayon::prelude::*;
     let mem_usize = mem as usize;
-    let chunk_size = size / CPUS;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
     
     let pattern1 = _mm512_set1_epi8(0xAAu8 as i8);
     let pattern2 = _mm512_set1_epi8(0x55u8 as i8);
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        for j in (0..chunk_size).step_by(64) {
+        for j in until_stopped((0..chunk_size).step_by(64), ctx) {
             let idx = j + i * chunk_size;
-            let pattern = if ((idx / 64) % 2) != 0 { pattern1 } else { pattern2 };
-            set(mem_ptr, idx, pattern);
+            let pattern = if !(idx / 64).is_multiple_of(2) { pattern1 } else { pattern2 };
+            set(mem_ptr, idx, pattern, cached);
         }
+        flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
     });
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        for j in (0..chunk_size).step_by(64) {
+        for j in until_stopped((0..chunk_size).step_by(64), ctx) {
             let idx = j + i * chunk_size;
-            let expected = if ((idx / 64) % 2) != 0 { pattern1 } else { pattern2 };
-            get(mem_ptr, idx, expected);
+            let expected = if !(idx / 64).is_multiple_of(2) { pattern1 } else { pattern2 };
+            get(mem_ptr, idx, expected, ctx);
         }
     });
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *mut u8;
-        for j in (0..chunk_size).step_by(64) {
+        for j in until_stopped((0..chunk_size).step_by(64), ctx) {
             let idx = j + i * chunk_size;
-            let pattern = if ((idx / 64) % 2) != 0 { pattern2 } else { pattern1 };
-            set(mem_ptr, idx, pattern);
+            let pattern = if !(idx / 64).is_multiple_of(2) { pattern2 } else { pattern1 };
+            set(mem_ptr, idx, pattern, cached);
         }
+        flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
     });
     
-    (0..CPUS).into_par_iter().for_each(|i| {
+    chunk_order(ctx).into_par_iter().for_each(|i| {
         let mem_ptr = mem_usize as *const u8;
-        for j in (0..chunk_size).step_by(64) {
+        for j in until_stopped((0..chunk_size).step_by(64), ctx) {
             let idx = j + i * chunk_size;
-            let expected = if ((idx / 64) % 2) != 0 { pattern2 } else { pattern1 };
-            get(mem_ptr, idx, expected);
+            let expected = if !(idx / 64).is_multiple_of(2) { pattern2 } else { pattern1 };
+            get(mem_ptr, idx, expected, ctx);
         }
     });
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-#[allow(dead_code)] // FIXME: segfaults
-pub unsafe fn avx512_address_line_test(mem: *mut u8, size: usize) {
-    use rayon::prelude::*;
-    let mem_usize = mem as usize;
-    let chunk_size = size / CPUS;
-    
-    (0..CPUS).into_par_iter().for_each(|i| {
-        let mem_ptr = mem_usize as *mut u8;
-        for j in (0..chunk_size).step_by(64) {
-            let idx = j + i * chunk_size;
-            let addr_pattern = idx as u64;
-            let pattern = _mm512_set1_epi64(addr_pattern as i64);
-            set(mem_ptr, idx, pattern);
-        }
-    });
-    
-    (0..CPUS).into_par_iter().for_each(|i| {
-        let mem_ptr = mem_usize as *const u8;
-        for j in (0..chunk_size).step_by(64) {
-            let idx = j + i * chunk_size;
-            let addr_pattern = idx as u64;
-            let expected = _mm512_set1_epi64(addr_pattern as i64);
-            get(mem_ptr, idx, expected);
-        }
-    });
-    
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
-        let mem_ptr = mem_usize as *mut u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        for j in (start..end).rev().step_by(64) {
-            if j + 64 <= end {
-                let addr_pattern = !j as u64;
-                let pattern = _mm512_set1_epi64(addr_pattern as i64);
-                set(mem_ptr, j, pattern);
-            }
-        }
-    });
-    
-    (0..CPUS).into_par_iter().rev().for_each(|i| {
-        let mem_ptr = mem_usize as *const u8;
-        let start = i * chunk_size;
-        let end = start + chunk_size;
-        for j in (start..end).rev().step_by(64) {
-            if j + 64 <= end {
-                let addr_pattern = !j as u64;
-                let expected = _mm512_set1_epi64(addr_pattern as i64);
-                get(mem_ptr, j, expected);
-            }
-        }
-    });
-    
-    let mut shift = 1;
-    while shift <= 16 {
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *mut u8;
-            for j in (0..chunk_size).step_by(64) {
-                let idx = j + i * chunk_size;
-                let addr_pattern = idx as u64 ^ ((idx as u64) << shift);
-                let pattern = _mm512_set1_epi64(addr_pattern as i64);
-                set(mem_ptr, idx, pattern);
-            }
-        });
-        
-        (0..CPUS).into_par_iter().for_each(|i| {
-            let mem_ptr = mem_usize as *const u8;
-            for j in (0..chunk_size).step_by(64) {
-                let idx = j + i * chunk_size;
-                let addr_pattern = idx as u64 ^ ((idx as u64) << shift);
-                let expected = _mm512_set1_epi64(addr_pattern as i64);
-                get(mem_ptr, idx, expected);
-            }
-        });
-        shift <<= 1;
+pub unsafe fn avx512_address_line_test(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let pattern = |idx: usize| _mm512_set1_epi64(idx as i64);
+    address_sweep(mem, size, true, false, pattern, ctx);
+    address_sweep(mem, size, true, true, pattern, ctx);
+
+    let pattern = |idx: usize| _mm512_set1_epi64(!idx as i64);
+    address_sweep(mem, size, false, false, pattern, ctx);
+    address_sweep(mem, size, false, true, pattern, ctx);
+
+    for shift in [1, 2, 4, 8, 16] {
+        let pattern = move |idx: usize| _mm512_set1_epi64((idx as u64 ^ ((idx as u64) << shift)) as i64);
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
     }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_anti_patterns(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_anti_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
     let patterns = [
         0x00, 0xFF, 0x0F, 0xF0, 0x55, 0xAA, 0x33, 0xCC,
         0x11, 0xEE, 0x22, 0xDD, 0x44, 0xBB, 0x66, 0x99,
@@ -630,31 +551,31 @@ pub unsafe fn avx512_anti_patterns(mem: *mut u8, size: usize) {
         let pattern = _mm512_set1_epi8(*pattern_val as i8);
         let anti_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
-        set_all_up(mem, size, anti_pattern);
-        get_all_up(mem as *const u8, size, anti_pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_up(mem, size, anti_pattern, ctx);
+        get_all_up(mem as *const u8, size, anti_pattern, ctx);
         
-        set_all_down(mem, size, pattern);
-        get_all_down(mem as *const u8, size, pattern);
-        set_all_down(mem, size, anti_pattern);
-        get_all_down(mem as *const u8, size, anti_pattern);
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, anti_pattern, ctx);
+        get_all_down(mem as *const u8, size, anti_pattern, ctx);
     }
 }
 
 #[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
-pub unsafe fn avx512_inverse_data_patterns(mem: *mut u8, size: usize) {
+pub unsafe fn avx512_inverse_data_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
     for byte_idx in 0..8 {
         let base_pattern = 0xFFFFFFFFFFFFFFFFu64;
         let pattern_val = base_pattern ^ (0xFFu64 << (byte_idx * 8));
         let pattern = _mm512_set1_epi64(pattern_val as i64);
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         
         let inverse = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, inverse);
-        get_all_up(mem as *const u8, size, inverse);
+        set_all_up(mem, size, inverse, ctx);
+        get_all_up(mem as *const u8, size, inverse, ctx);
     }
     
     for word_idx in 0..4 {
@@ -662,12 +583,12 @@ pub unsafe fn avx512_inverse_data_patterns(mem: *mut u8, size: usize) {
         let pattern_val = base_pattern ^ (0xFFFFu64 << (word_idx * 16));
         let pattern = _mm512_set1_epi64(pattern_val as i64);
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         
         let inverse = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, inverse);
-        get_all_up(mem as *const u8, size, inverse);
+        set_all_up(mem, size, inverse, ctx);
+        get_all_up(mem as *const u8, size, inverse, ctx);
     }
     
     for dword_idx in 0..2 {
@@ -675,52 +596,955 @@ pub unsafe fn avx512_inverse_data_patterns(mem: *mut u8, size: usize) {
         let pattern_val = base_pattern ^ (0xFFFFFFFFu64 << (dword_idx * 32));
         let pattern = _mm512_set1_epi64(pattern_val as i64);
         
-        set_all_up(mem, size, pattern);
-        get_all_up(mem as *const u8, size, pattern);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
         
         let inverse = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
-        set_all_up(mem, size, inverse);
-        get_all_up(mem as *const u8, size, inverse);
+        set_all_up(mem, size, inverse, ctx);
+        get_all_up(mem as *const u8, size, inverse, ctx);
+    }
+}
+
+// Rows are assumed to be 8KiB (typical for DDR4/DDR5 x8 devices); the real
+// physical row mapping is unknown, so aggressors are simply placed two rows apart
+// with the victim row in between (double-sided hammering).
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const ROWHAMMER_ROW_SIZE: usize = 8 * 1024;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const ROWHAMMER_STRIDE: usize = 1024 * 1024;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const ROWHAMMER_ACTIVATIONS: usize = 65536;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn rowhammer_pattern(idx: usize, pattern: __m512i, anti_pattern: __m512i) -> __m512i {
+    if !(idx / ROWHAMMER_ROW_SIZE).is_multiple_of(2) { anti_pattern } else { pattern }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn rowhammer_hammer(aggressor1: *const u8, aggressor2: *const u8) {
+    for _ in 0..ROWHAMMER_ACTIVATIONS {
+        std::ptr::read_volatile(aggressor1);
+        std::ptr::read_volatile(aggressor2);
+        _mm_clflush(aggressor1);
+        _mm_clflush(aggressor2);
+        _mm_mfence();
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_rowhammer(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+
+    for pattern_val in [0x55u8, 0xAA] {
+        let pattern = _mm512_set1_epi8(pattern_val as i8);
+        let anti_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
+
+        // stripe rows with pattern / anti-pattern so victims differ from their aggressors
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                let idx = j + i * chunk_size;
+                set(mem_ptr, idx, rowhammer_pattern(idx, pattern, anti_pattern), cached);
+            }
+            _mm_sfence();
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            let mut offset = 0;
+            while offset + 3 * ROWHAMMER_ROW_SIZE <= chunk_size && !stop_requested(ctx) {
+                let aggressor1 = mem_ptr.add(start + offset);
+                let aggressor2 = mem_ptr.add(start + offset + 2 * ROWHAMMER_ROW_SIZE);
+                rowhammer_hammer(aggressor1, aggressor2);
+                offset += ROWHAMMER_STRIDE;
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, rowhammer_pattern(idx, pattern, anti_pattern), ctx);
+            }
+        });
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_bit_fade(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in [0x00u8, 0xFF] {
+        let pattern = _mm512_set1_epi8(pattern_val as i8);
+        set_all_up(mem, size, pattern, ctx);
+        retention_sleep(ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
     }
 }
 
+// See tests_avx2.rs for the modulo-20 layout.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const MODULO: usize = 20;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_modulo_20(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+
+    let pattern = random(ctx);
+    let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
+    let expected_at = |idx: usize, offset: usize| {
+        if (idx / 64) % MODULO == offset { pattern } else { not_pattern }
+    };
+
+    for offset in 0..MODULO {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 64) % MODULO == offset {
+                    set(mem_ptr, idx, pattern, cached);
+                }
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 64) % MODULO != offset {
+                    set(mem_ptr, idx, not_pattern, cached);
+                }
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, expected_at(idx, offset), ctx);
+            }
+        });
+    }
+}
+
+// See tests_avx2.rs for the block move layout.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const BLOCK_MOVE_SIZE: usize = 4 * 1024 * 1024;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn load(mem: *const u8, idx: usize) -> __m512i {
+    _mm512_load_si512((mem.add(idx)) as *const __m512i)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn block_move_pattern(offset: usize, round: usize) -> __m512i {
+    // walking bit mixed with the vector index, so a misplaced block can't match by accident
+    let vector = (offset / 64) as u64;
+    let word = 1u64.rotate_left(((vector + round as u64) % 64) as u32) ^ vector.wrapping_mul(0x9E3779B97F4A7C15);
+    _mm512_set1_epi64(word as i64)
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_block_move(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let shift = (BLOCK_MOVE_SIZE.min(chunk_size) / 2) / 64 * 64;
+    if shift == 0 {
+        return;
+    }
+    let moved = (chunk_size - shift) / 64;
+
+    for round in 0..4 {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                set(mem_ptr, start + j, block_move_pattern(j, round), cached);
+            }
+            for j in until_stopped((0..moved).rev(), ctx) {
+                let idx = start + j * 64;
+                set(mem_ptr, idx + shift, load(mem_ptr, idx), cached);
+            }
+            for j in until_stopped(0..moved, ctx) {
+                let idx = start + shift + j * 64;
+                set(mem_ptr, idx - shift, load(mem_ptr, idx), cached);
+            }
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(64), ctx) {
+                let source = if j < chunk_size - shift { j } else { j - shift };
+                get(mem_ptr, start + j, block_move_pattern(source, round), ctx);
+            }
+        });
+    }
+}
+
+// See tests_avx2.rs for the random address test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_random_address(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let vectors = chunk_size / 64;
+    if vectors == 0 {
+        return;
+    }
+
+    let mut seeds = [0u64; 8];
+    _mm512_storeu_si512(seeds.as_mut_ptr() as *mut __m512i, random(ctx));
+    let salt = seeds[0].rotate_left(32) ^ seeds[1];
+
+    let walk = |i: usize, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut key = Avx512Xorshift128PlusKey {
+            part1: _mm512_setzero_si512(),
+            part2: _mm512_setzero_si512(),
+        };
+        avx512_xorshift128plus_init(seeds[0] ^ i as u64, seeds[1] | 1, &mut key);
+        let mut offsets = [0u64; 8];
+        for _ in until_stopped(0..vectors / 8, ctx) {
+            _mm512_storeu_si512(offsets.as_mut_ptr() as *mut __m512i, avx512_xorshift128plus(&mut key));
+            for offset in offsets {
+                let idx = start + (offset as usize % vectors) * 64;
+                let val = _mm512_set1_epi64((idx as u64 ^ salt) as i64);
+                if verify {
+                    get(mem_ptr as *const u8, idx, val, ctx);
+                } else {
+                    set(mem_ptr, idx, val, cached);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, false));
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, true));
+}
+
+// See tests_avx2.rs for the prime stride walk.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const PRIME_STRIDES: [usize; 2] = [13, 131071];
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn prime_walk(mem: *mut u8, size: usize, stride: usize, pattern: __m512i, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let vectors = chunk_size / 64;
+    if vectors == 0 {
+        return;
+    }
+    let step = stride % vectors;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut pos = 0;
+        let mut cycle = 0;
+        for _ in until_stopped(0..vectors, ctx) {
+            let idx = start + pos * 64;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern, ctx);
+            } else {
+                set(mem_ptr, idx, pattern, cached);
+            }
+            pos += step;
+            if pos >= vectors {
+                pos -= vectors;
+            }
+            if pos == cycle {
+                cycle += 1;
+                pos = cycle;
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_prime_stride(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for stride in PRIME_STRIDES {
+        let pattern = random(ctx);
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+
+        let pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+    }
+}
+
+// See tests_avx2.rs for the custom patterns test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_custom_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in custom_patterns(ctx) {
+        let pattern = _mm512_set1_epi64(pattern_val as i64);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
+    }
+}
+
+// See tests_avx2.rs for the intra cache line walking bit tests.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+// one vector is a whole cache line
+const LINE_VECTORS: usize = 1;
+
+// Writes or verifies `line[k]` at the k-th vector of every 64-byte line
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn line_sweep(mem: *mut u8, size: usize, line: &[__m512i; LINE_VECTORS], verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for j in until_stopped((0..chunk_size / 64).map(|v| start + v * 64), ctx) {
+            let val = line[(j % 64) / 64];
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val, cached);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn walk_line(mem: *mut u8, size: usize, zero: bool, ctx: &TestContext) {
+    for bit in 0..512 {
+        let mut words = [0u64; 8];
+        words[bit / 64] = 1 << (bit % 64);
+        if zero {
+            words = words.map(|w| !w);
+        }
+        let line: [__m512i; LINE_VECTORS] = std::array::from_fn(|k| _mm512_loadu_si512(words.as_ptr().add(k * 8) as *const __m512i));
+        line_sweep(mem, size, &line, false, ctx);
+        line_sweep(mem, size, &line, true, ctx);
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_walking_1_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, false, ctx);
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_walking_0_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, true, ctx);
+}
+
+// See tests_avx2.rs for the refresh stress test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const REFRESH_WINDOW: usize = 8 * 1024;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const REFRESH_STRIDE: usize = 1024 * 1024;
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_refresh_stress(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    use std::time::Instant;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let dwell = refresh_dwell(ctx);
+    let pattern = random(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for base in (start..start + chunk_size.saturating_sub(REFRESH_WINDOW - 1)).step_by(REFRESH_STRIDE) {
+            let deadline = Instant::now() + dwell;
+            let mut val = pattern;
+            while Instant::now() < deadline && !stop_requested(ctx) {
+                for j in (base..base + REFRESH_WINDOW).step_by(64) {
+                    set(mem_ptr, j, val, cached);
+                }
+                flush_sweep(mem_ptr.add(base), REFRESH_WINDOW, ctx);
+                for j in (base..base + REFRESH_WINDOW).step_by(64) {
+                    get(mem_ptr as *const u8, j, val, ctx);
+                }
+                val = _mm512_xor_epi64(val, _mm512_set1_epi8(0xFFu8 as i8));
+            }
+        }
+    });
+}
+
+// See tests_avx2.rs for the LFSR fill test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_lfsr_fill(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let seed = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+
+    let sweep = |i: usize, invert: bool, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        // the all-zero state is the only one the LFSR never leaves
+        let mut state = (seed ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15)).max(1);
+        let mut words = [0u64; 8];
+        for j in until_stopped((0..chunk_size / 64).map(|v| start + v * 64), ctx) {
+            for word in words.iter_mut() {
+                state = lfsr64_next(state);
+                *word = if invert { !state } else { state };
+            }
+            let val = _mm512_loadu_si512(words.as_ptr() as *const __m512i);
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val, cached);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    for invert in [false, true] {
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, false));
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, true));
+    }
+}
+
+// See tests_avx2.rs for the channel stripes test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn stripe_sweep(mem: *mut u8, size: usize, channel: usize, pattern: __m512i, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let interleave = channel_interleave(ctx);
+    let period = interleave * channel_count(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let end = start + chunk_size;
+        // first stripe of this channel at or before the chunk start
+        let first = start / period * period + channel * interleave;
+        for stripe in until_stopped((first..end).step_by(period), ctx) {
+            for j in (stripe.max(start)..(stripe + interleave).min(end)).step_by(64) {
+                if verify {
+                    get(mem_ptr as *const u8, j, pattern, ctx);
+                } else {
+                    set(mem_ptr, j, pattern, cached);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_channel_stripes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for channel in 0..channel_count(ctx) {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        let random = random(ctx);
+        let patterns = [random, _mm512_xor_epi64(random, _mm512_set1_epi8(0xFFu8 as i8)), _mm512_set1_epi8(0x55u8 as i8), _mm512_set1_epi8(0xAAu8 as i8)];
+        for pattern in patterns {
+            stripe_sweep(mem, size, channel, pattern, false, ctx);
+            stripe_sweep(mem, size, channel, pattern, true, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on stripes of channel {}", errors, channel_name(channel, ctx));
+        }
+    }
+}
+
+// See coherence.rs for the coherence ping-pong test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_coherence_ping_pong(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+    let errors = coherence_ping_pong(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// See pointer_chase.rs for the pointer-chase test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_pointer_chase(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+    let errors = pointer_chase(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// See tests_avx2.rs for the mixed read/write test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_mixed_rw(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let (reads, writes) = rw_ratio(ctx);
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let vectors = chunk_size / 64;
+    let word = |idx: usize, seed: u64| (idx as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ seed;
+    let pattern = |idx: usize, seed: u64| _mm512_set1_epi64(word(idx, seed) as i64);
+    // see tests_avx2.rs: every lane on its own holds the old or the new word
+    let bad_lanes = |actual: __m512i, old: __m512i, new: __m512i| !(_mm512_cmpeq_epi64_mask(actual, old) | _mm512_cmpeq_epi64_mask(actual, new));
+
+    let mut old = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+    address_sweep(mem, size, true, false, |idx| pattern(idx, old), ctx);
+    flush_sweep(mem, size, ctx);
+
+    for _ in 0..4 {
+        let new = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let mut key = (new ^ i as u64) | 1;
+            for v in until_stopped((0..vectors).step_by(writes), ctx) {
+                for idx in (v..(v + writes).min(vectors)).map(|v| i * chunk_size + v * 64) {
+                    set(mem_ptr, idx, pattern(idx, new), cached);
+                }
+                for _ in 0..reads {
+                    key ^= key << 13;
+                    key ^= key >> 7;
+                    key ^= key << 17;
+                    let idx = (key as usize % (vectors * ctx.cpus)) * 64;
+                    let actual = _mm512_load_si512(mem_ptr.add(idx) as *const __m512i);
+                    let bad = bad_lanes(actual, pattern(idx, old), pattern(idx, new));
+                    if bad != 0 {
+                        if log_miscompare(ctx) {
+                            error!("errors detected at offset 0x{:016x}", idx);
+                        }
+                        let mut lanes = [0u64; 8];
+                        _mm512_storeu_si512(lanes.as_mut_ptr() as *mut __m512i, actual);
+                        for lane in (0..8).filter(|lane| bad & (1 << lane) != 0) {
+                            record_error(idx + lane * 8, word(idx, new), lanes[lane], ctx);
+                            ctx.run.errors.fetch_add(miscompared_bytes(word(idx, new), lanes[lane]), std::sync::atomic::Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+        });
+        flush_sweep(mem, size, ctx);
+        address_sweep(mem, size, true, true, |idx| pattern(idx, new), ctx);
+        old = new;
+    }
+}
+
+// See tests_avx2.rs for the thermal cycle test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_thermal_cycle(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    let deadline = std::time::Instant::now() + heat_duration(ctx);
+    while std::time::Instant::now() < deadline && !stop_requested(ctx) {
+        for pattern in [_mm512_set1_epi8(0x55u8 as i8), _mm512_set1_epi8(0xAAu8 as i8)] {
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem, size, pattern, ctx);
+        }
+    }
+    log_temperatures("heat", ctx.run.errors.load(Ordering::Relaxed));
+
+    retention_sleep(ctx);
+    log_temperatures("cooldown", ctx.run.errors.load(Ordering::Relaxed));
+}
+
+// See tests_avx2.rs for the byte lane test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_byte_lanes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for lane in 0..8 {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        for bit in 0..8 {
+            let pattern_val = 1u64 << (lane * 8 + bit);
+            let pattern = _mm512_set1_epi64(pattern_val as i64);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
+            let not_pattern = _mm512_xor_epi64(pattern, _mm512_set1_epi8(0xFFu8 as i8));
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on byte lane {}", errors, lane);
+        }
+    }
+}
+
+// See tests_avx2.rs for the butterfly order and test.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn butterfly_sweep<F>(mem: *mut u8, size: usize, converging: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> __m512i + Sync,
+{
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let vectors = chunk_size / 64;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 64;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx), cached);
+            }
+        };
+        let pair = |k: usize| {
+            visit(k);
+            if vectors - 1 - k != k {
+                visit(vectors - 1 - k);
+            }
+        };
+        if converging {
+            until_stopped(0..vectors.div_ceil(2), ctx).for_each(pair);
+        } else {
+            until_stopped((0..vectors.div_ceil(2)).rev(), ctx).for_each(pair);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_butterfly(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..4 {
+        let salt = _mm_cvtsi128_si64(_mm512_castsi512_si128(random(ctx))) as u64;
+        let pattern = |idx: usize| _mm512_set1_epi64(((idx as u64) ^ salt) as i64);
+        butterfly_sweep(mem, size, true, false, pattern, ctx);
+        butterfly_sweep(mem, size, false, true, pattern, ctx);
+        let inverse = |idx: usize| _mm512_xor_epi64(pattern(idx), _mm512_set1_epi8(0xFFu8 as i8));
+        butterfly_sweep(mem, size, false, false, inverse, ctx);
+        butterfly_sweep(mem, size, true, true, inverse, ctx);
+    }
+}
+
+// Gather/scatter test: like the random address test, but the pseudo-random offsets come as index
+// vectors that are written with one vpscatterqq and read back with one vpgatherqq, eight scattered
+// qwords per instruction. Gathers and scatters are split into independent per-lane accesses and
+// load the memory controller very differently from linear streaming (has caught IMC bugs before).
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_gather_scatter(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    // indices are scaled with a 32x32 bit multiply, larger chunks are only partially covered
+    let words = (chunk_size / 8).min(u32::MAX as usize);
+    if words == 0 {
+        return;
+    }
+
+    let mut seeds = [0u64; 8];
+    _mm512_storeu_si512(seeds.as_mut_ptr() as *mut __m512i, random(ctx));
+    let salt = _mm512_set1_epi64((seeds[0].rotate_left(32) ^ seeds[1]) as i64);
+
+    let walk = |i: usize, verify: bool| {
+        let start = i * chunk_size;
+        let base = (mem_usize as *mut u8).add(start) as *mut i64;
+        let mut key = Avx512Xorshift128PlusKey {
+            part1: _mm512_setzero_si512(),
+            part2: _mm512_setzero_si512(),
+        };
+        avx512_xorshift128plus_init(seeds[0] ^ i as u64, seeds[1] | 1, &mut key);
+        for _ in until_stopped(0..words / 8, ctx) {
+            // word index = (random >> 32) * words >> 32, uniform in 0..words
+            let random = _mm512_srli_epi64::<32>(avx512_xorshift128plus(&mut key));
+            let index = _mm512_srli_epi64::<32>(_mm512_mul_epu32(random, _mm512_set1_epi64(words as i64)));
+            let offsets = _mm512_add_epi64(_mm512_set1_epi64(start as i64), _mm512_slli_epi64::<3>(index));
+            let val = _mm512_xor_si512(offsets, salt);
+            if verify {
+                let actual = _mm512_i64gather_epi64::<8>(index, base);
+                let mismatch = _mm512_cmpneq_epi64_mask(actual, val);
+                if mismatch != 0 {
+                    let mut lanes = [[0u64; 8]; 3];
+                    _mm512_storeu_si512(lanes[0].as_mut_ptr() as *mut __m512i, offsets);
+                    _mm512_storeu_si512(lanes[1].as_mut_ptr() as *mut __m512i, val);
+                    _mm512_storeu_si512(lanes[2].as_mut_ptr() as *mut __m512i, actual);
+                    for lane in (0..8).filter(|lane| mismatch & (1 << lane) != 0) {
+                        if log_miscompare(ctx) {
+                            error!("errors detected at offset 0x{:016x}", lanes[0][lane]);
+                        }
+                        record_error(lanes[0][lane] as usize, lanes[1][lane], lanes[2][lane], ctx);
+                        ctx.run.errors.fetch_add(miscompared_bytes(lanes[1][lane], lanes[2][lane]), std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
+            } else {
+                _mm512_i64scatter_epi64::<8>(base, index, val);
+            }
+        }
+        // scatters always go through the cache
+        if !verify {
+            flush_sweep(base as *const u8, chunk_size, ctx);
+        }
+    };
+
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, false));
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, true));
+}
+
+// Cross-check test: one random 512-bit pattern is written and verified twice over the same region,
+// first with 256-bit AVX2 stores and loads, then with 512-bit AVX-512 ones, leaving identical
+// contents both times. A miscompare at the same offset with the same flipped bits in both passes
+// comes from the DRAM; one that only shows up on one data path points at the core's load/store
+// units instead. Up to CROSS_CHECK_MAX miscompares per pass are kept for the comparison.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const CROSS_CHECK_MAX: usize = 4096;
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+const CROSS_CHECK_LOGGED: usize = 16;
+
+// Fills the region with `pattern` and verifies it, through 512-bit (`wide`) or 256-bit accesses.
+// Returns the offset and xor mask of the mismatching qwords.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+unsafe fn cross_check_pass(mem: *mut u8, size: usize, pattern: __m512i, wide: bool, ctx: &TestContext) -> Vec<(usize, u64)> {
+    use rayon::prelude::*;
+    use std::sync::Mutex;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    let halves = [_mm512_castsi512_si256(pattern), _mm512_extracti64x4_epi64::<1>(pattern)];
+    let mismatches = Mutex::new(Vec::new());
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for j in until_stopped((start..start + chunk_size).step_by(64), ctx) {
+            if wide {
+                set(mem_ptr, j, pattern, cached);
+            } else {
+                for (half, val) in halves.iter().enumerate() {
+                    let p = mem_ptr.add(j + half * 32) as *mut __m256i;
+                    if cached {
+                        _mm256_store_si256(p, *val);
+                    } else {
+                        _mm256_stream_si256(p, *val);
+                    }
+                }
+            }
+        }
+        flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+    });
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(pattern), true, ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        let start = i * chunk_size;
+        for j in until_stopped((start..start + chunk_size).step_by(64), ctx) {
+            let actual = if wide {
+                _mm512_load_si512(mem_ptr.add(j) as *const __m512i)
+            } else {
+                let lo = _mm256_load_si256(mem_ptr.add(j) as *const __m256i);
+                let hi = _mm256_load_si256(mem_ptr.add(j + 32) as *const __m256i);
+                _mm512_inserti64x4::<1>(_mm512_castsi256_si512(lo), hi)
+            };
+            let mismatch = _mm512_cmpneq_epi64_mask(actual, pattern);
+            if mismatch != 0 {
+                let mut diff = [0u64; 8];
+                let mut expected = [0u64; 8];
+                _mm512_storeu_si512(diff.as_mut_ptr() as *mut __m512i, _mm512_xor_si512(actual, pattern));
+                _mm512_storeu_si512(expected.as_mut_ptr() as *mut __m512i, pattern);
+                let mut found = mismatches.lock().unwrap();
+                for (lane, mask) in diff.iter().enumerate().filter(|(_, mask)| **mask != 0) {
+                    let offset = j + lane * 8;
+                    if log_miscompare(ctx) {
+                        error!("errors detected at offset 0x{:016x} [xor mask: 0x{:016x}]", offset, mask);
+                    }
+                    record_error(offset, expected[lane], expected[lane] ^ mask, ctx);
+                    ctx.run.errors.fetch_add(miscompared_bytes(0, *mask), std::sync::atomic::Ordering::Relaxed);
+                    if found.len() < CROSS_CHECK_MAX {
+                        found.push((offset, *mask));
+                    }
+                }
+            }
+        }
+    });
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(pattern), true, ctx);
+
+    mismatches.into_inner().unwrap()
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_cross_check(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::collections::HashSet;
+    let pattern = random(ctx);
+    let avx2: HashSet<(usize, u64)> = cross_check_pass(mem, size, pattern, false, ctx).into_iter().collect();
+    let avx512: HashSet<(usize, u64)> = cross_check_pass(mem, size, pattern, true, ctx).into_iter().collect();
+    if avx2.is_empty() && avx512.is_empty() {
+        return;
+    }
+
+    error!("cross_check: {} miscompares with AVX2 accesses, {} with AVX-512 accesses, {} identical in both (DRAM)",
+        avx2.len(), avx512.len(), avx2.intersection(&avx512).count());
+    for (path, only) in [("AVX2", avx2.difference(&avx512)), ("AVX-512", avx512.difference(&avx2))] {
+        for (offset, mask) in only.take(CROSS_CHECK_LOGGED) {
+            error!("cross_check: offset 0x{:016x} [xor mask: 0x{:016x}] only failed with {} accesses (suspect CPU load/store path)",
+                offset, mask, path);
+        }
+    }
+}
+
+/// Read `mem[..size]` once up the chunks of every thread, prefetching like the verify loops but
+/// comparing nothing, for `calibrate_prefetch()`.
+#[cfg(all(target_arch = "x86_64", target_feature = "avx512f"))]
+pub unsafe fn avx512_read_sweep(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 64 * 64;
+    (0..ctx.cpus).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        let mut any = _mm512_setzero_si512();
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 64, ctx), ctx) {
+            prefetch_ahead(mem_ptr, idx, true, ctx);
+            any = _mm512_or_si512(any, _mm512_load_si512(mem_ptr.add(idx) as *const __m512i));
+        }
+        std::hint::black_box(any);
+    });
+}
+
 //FIXME: remove stubs and/or error out when running in unsupported configuration
 // Stub implementations for non-AVX512 targets
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_tests_init(_cpus: usize, _errors: *const AtomicU64) {}
+pub unsafe fn avx512_basic_tests(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_read_sweep(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_march(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_random_inversions(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_basic_tests(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_inversions_left_64(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_march(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_inversions_right_32(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_random_inversions(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_inversions_left_16(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_inversions_left_64(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_inversions_right_8(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_inversions_right_32(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_inversions_left_4(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_inversions_left_16(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_saturations_right_16(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_inversions_right_8(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_moving_saturations_left_8(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_inversions_left_4(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_addressing(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_saturations_right_16(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_sgemm(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_moving_saturations_left_8(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_walking_1(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_addressing(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_walking_0(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_sgemm(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_checkerboard(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_walking_1(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_address_line_test(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_walking_0(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_anti_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_checkerboard(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_inverse_data_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_address_line_test(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_rowhammer(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_anti_patterns(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_bit_fade(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
 #[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
-pub unsafe fn avx512_inverse_data_patterns(_mem: *mut u8, _size: usize) {}
+pub unsafe fn avx512_modulo_20(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_block_move(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_random_address(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_prime_stride(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_custom_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_walking_1_line(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_walking_0_line(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_refresh_stress(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_lfsr_fill(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_channel_stripes(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_coherence_ping_pong(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_pointer_chase(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_mixed_rw(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_thermal_cycle(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_byte_lanes(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_butterfly(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_gather_scatter(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(all(target_arch = "x86_64", target_feature = "avx512f")))]
+pub unsafe fn avx512_cross_check(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+
+#[cfg(all(test, target_arch = "x86_64", target_feature = "avx512f"))]
+mod tests {
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use crate::tests::{Pause, RunState};
+    use super::*;
+
+    const GUARD: usize = 256;
+
+    // Per-thread chunks that are not whole vectors used to make the descending walks misaligned
+    // and let the last vector spill past the chunk; run every size on a guarded buffer.
+    #[test]
+    fn addressing_small_buffers() {
+        if !is_x86_feature_detected!("avx512f") {
+            return;
+        }
+        let stop = AtomicBool::new(false);
+        let pause = Pause::default();
+        let run = RunState::new(4, 1, 0, &stop, &pause);
+        for cpus in [1, 3, 4] {
+            for size in [0, 64, 100, 4096, 3 * 4096 + 96, 64 * 1024 + 8] {
+                unsafe {
+                    let ctx = TestContext { cpus, ..TestContext::new(&run) };
+                    let layout = Layout::from_size_align(size + GUARD, 4096).unwrap();
+                    let mem = alloc_zeroed(layout);
+                    std::ptr::write_bytes(mem.add(size), 0xA5, GUARD);
+
+                    avx512_addressing(mem, size, &ctx);
+                    avx512_address_line_test(mem, size, &ctx);
+
+                    let guard = std::slice::from_raw_parts(mem.add(size), GUARD);
+                    assert!(guard.iter().all(|&b| b == 0xA5), "write past end, size {} cpus {}", size, cpus);
+                    dealloc(mem, layout);
+                }
+            }
+        }
+        assert_eq!(run.errors.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,1134 @@
+// 128-bit NEON backend for AArch64 (Ampere, Graviton, Apple Silicon).
+// NEON is mandatory on AArch64, so this is the default there; SVE would need
+// unstable intrinsics and is not used (yet).
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::*;
+use crate::tests::TestContext;
+#[cfg(target_arch = "aarch64")]
+use log::error;
+#[cfg(target_arch = "aarch64")]
+use crate::tests::{channel_count, channel_interleave, channel_name, chunk_offsets, chunk_order, custom_patterns, flush_chunk, flush_sweep, heat_duration, inject_faults, lfsr64_next, log_miscompare, log_temperatures, miscompared_bytes, record_error, record_mismatches, refresh_dwell, retention_sleep, rw_ratio, stop_requested, tail_get, tail_set, until_stopped};
+#[cfg(target_arch = "aarch64")]
+use crate::coherence::coherence_ping_pong;
+#[cfg(target_arch = "aarch64")]
+use crate::pointer_chase::pointer_chase;
+#[cfg(target_arch = "aarch64")]
+use crate::sgemm::{sgemm_chunk, SGEMM_ROUNDS};
+#[cfg(target_arch = "aarch64")]
+use crate::simd_xorshift::{xorshift128plus, xorshift128plus_init, xorshift128plus_lanes, Xorshift128PlusKey};
+
+// Next random word of the run's generator
+#[cfg(target_arch = "aarch64")]
+fn random(ctx: &TestContext) -> u64 {
+    xorshift128plus_lanes(&mut ctx.run.rng.lock().unwrap())
+}
+
+#[cfg(target_arch = "aarch64")]
+fn splat8(val: u8) -> u64 {
+    u64::from_ne_bytes([val; 8])
+}
+
+#[cfg(target_arch = "aarch64")]
+fn splat16(val: u16) -> u64 {
+    0x0001000100010001u64 * val as u64
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn get(mem: *const u8, idx: usize, expected: uint64x2_t, ctx: &TestContext) {
+    let actual = vld1q_u64((mem.add(idx)) as *const u64);
+    let diff = vreinterpretq_u8_u64(veorq_u64(expected, actual));
+
+    if vmaxvq_u8(diff) != 0 {
+        // one 0xFF lane per mismatching byte, shifted down to 1 and summed
+        let error_total = vaddvq_u8(vshrq_n_u8::<7>(vtstq_u8(diff, diff))) as u64;
+        let diff = vreinterpretq_u64_u8(diff);
+        if log_miscompare(ctx) {
+            error!("{} errors detected at offset 0x{:016x} [xor mask: 0x{:016x}{:016x}]",
+                error_total, idx, vgetq_lane_u64::<1>(diff), vgetq_lane_u64::<0>(diff));
+        }
+        record_mismatches(idx, &to_bytes(expected), &to_bytes(actual), ctx);
+        ctx.run.errors.fetch_add(error_total, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn to_bytes(val: uint64x2_t) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    vst1q_u64(bytes.as_mut_ptr() as *mut u64, val);
+    bytes
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn get_all_up(mem: *const u8, size: usize, expected: uint64x2_t, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    inject_faults(mem, size, &to_bytes(expected), ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 16, ctx), ctx) {
+            get(mem_ptr, idx, expected, ctx);
+        }
+    });
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(expected), true, ctx);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn get_all_down(mem: *const u8, size: usize, expected: uint64x2_t, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    inject_faults(mem, size, &to_bytes(expected), ctx);
+    tail_get(mem, ctx.cpus * chunk_size, size, &to_bytes(expected), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 16, ctx).rev(), ctx) {
+            get(mem_ptr, idx, expected, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn set(mem: *mut u8, idx: usize, val: uint64x2_t) {
+    vst1q_u64((mem.add(idx)) as *mut u64, val);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn set_all_up(mem: *mut u8, size: usize, val: uint64x2_t, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 16, ctx), ctx) {
+            set(mem_ptr, idx, val);
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    });
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(val), true, ctx);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn set_all_down(mem: *mut u8, size: usize, val: uint64x2_t, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    tail_set(mem, ctx.cpus * chunk_size, size, &to_bytes(val), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 16, ctx).rev(), ctx) {
+            set(mem_ptr, idx, val);
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn set_get_inverse(mem: *mut u8, size: usize, pattern: uint64x2_t, ctx: &TestContext) {
+    let not_pattern = veorq_u64(pattern, vdupq_n_u64(u64::MAX));
+    set_all_up(mem, size, pattern, ctx);
+    get_all_up(mem as *const u8, size, pattern, ctx);
+    set_all_up(mem, size, not_pattern, ctx);
+    get_all_up(mem as *const u8, size, not_pattern, ctx);
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn set_get_saturation(mem: *mut u8, size: usize, pattern: uint64x2_t, ctx: &TestContext) {
+    let zeroes = vdupq_n_u64(0);
+    let ones = vdupq_n_u64(u64::MAX);
+    set_all_up(mem, size, pattern, ctx);
+    get_all_up(mem as *const u8, size, pattern, ctx);
+    set_all_up(mem, size, zeroes, ctx);
+    get_all_up(mem as *const u8, size, zeroes, ctx);
+    set_all_up(mem, size, pattern, ctx);
+    get_all_up(mem as *const u8, size, pattern, ctx);
+    set_all_up(mem, size, ones, ctx);
+    get_all_up(mem as *const u8, size, ones, ctx);
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_basic_tests(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let patterns = [0x00u8, 0xFF, 0x0F, 0xF0, 0x55, 0xAA];
+    for pattern_val in &patterns {
+        let pattern = vdupq_n_u64(splat8(*pattern_val));
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
+    }
+}
+
+// See avx2_march / march_element for the element sequence.
+#[cfg(target_arch = "aarch64")]
+unsafe fn march_element(mem: *mut u8, size: usize, ascending: bool, expected: uint64x2_t, val: uint64x2_t, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    let element = |i: usize| {
+        let mem_ptr = mem_usize as *mut u8;
+        if ascending {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 16, ctx), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val);
+            }
+        } else {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 16, ctx).rev(), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val);
+            }
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    };
+
+    if ascending {
+        chunk_order(ctx).into_par_iter().for_each(element);
+    } else {
+        chunk_order(ctx).into_par_iter().rev().for_each(element);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_march(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let ones = vdupq_n_u64(u64::MAX);
+    let zeroes = vdupq_n_u64(0);
+
+    for _ in 0..2 {
+        set_all_up(mem, size, zeroes, ctx);
+        march_element(mem, size, true, zeroes, ones, ctx);
+        march_element(mem, size, true, ones, zeroes, ctx);
+        march_element(mem, size, false, zeroes, ones, ctx);
+        march_element(mem, size, false, ones, zeroes, ctx);
+        get_all_down(mem as *const u8, size, zeroes, ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn random_pattern(ctx: &TestContext) -> uint64x2_t {
+    let lanes = [random(ctx), random(ctx)];
+    vld1q_u64(lanes.as_ptr())
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_random_inversions(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..16 {
+        set_get_inverse(mem, size, random_pattern(ctx), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_inversions_left_64(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..64 {
+        set_get_inverse(mem, size, vdupq_n_u64(0x0000000000000001u64 << i), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_inversions_right_32(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..32 {
+        set_get_inverse(mem, size, vdupq_n_u64(0x8000000080000000u64 >> i), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_inversions_left_16(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..16 {
+        set_get_inverse(mem, size, vdupq_n_u64(splat16(0x0001) << i), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_inversions_right_8(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..8 {
+        set_get_inverse(mem, size, vdupq_n_u64(splat8(0x80) >> i), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_inversions_left_4(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..4 {
+        set_get_inverse(mem, size, vdupq_n_u64(splat8(0x11) << i), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_saturations_right_16(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..16 {
+        set_get_saturation(mem, size, vdupq_n_u64(splat16(0x8000u16 >> i)), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_moving_saturations_left_8(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..8 {
+        set_get_saturation(mem, size, vdupq_n_u64(splat16(0x01u16 >> i)), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_walking_1(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for bit in 0..64 {
+        set_get_inverse(mem, size, vdupq_n_u64(1u64 << bit), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_walking_0(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for bit in 0..64 {
+        set_get_inverse(mem, size, vdupq_n_u64(!(1u64 << bit)), ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_checkerboard(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    let pattern1 = vdupq_n_u64(splat8(0xAA));
+    let pattern2 = vdupq_n_u64(splat8(0x55));
+
+    for (odd, even) in [(pattern1, pattern2), (pattern2, pattern1)] {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                let pattern = if !(idx / 16).is_multiple_of(2) { odd } else { even };
+                set(mem_ptr, idx, pattern);
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                let expected = if !(idx / 16).is_multiple_of(2) { odd } else { even };
+                get(mem_ptr, idx, expected, ctx);
+            }
+        });
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_anti_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let patterns = [
+        0x00, 0xFF, 0x0F, 0xF0, 0x55, 0xAA, 0x33, 0xCC,
+        0x11, 0xEE, 0x22, 0xDD, 0x44, 0xBB, 0x66, 0x99,
+        0x77, 0x88, 0x01, 0xFE, 0x02, 0xFD, 0x04, 0xFB,
+        0x08, 0xF7, 0x10, 0xEF, 0x20, 0xDF, 0x40, 0xBF,
+        0x80, 0x7F,
+    ];
+
+    for pattern_val in &patterns {
+        let pattern = vdupq_n_u64(splat8(*pattern_val));
+        let anti_pattern = veorq_u64(pattern, vdupq_n_u64(u64::MAX));
+
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_up(mem, size, anti_pattern, ctx);
+        get_all_up(mem as *const u8, size, anti_pattern, ctx);
+
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, anti_pattern, ctx);
+        get_all_down(mem as *const u8, size, anti_pattern, ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_inverse_data_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for byte_idx in 0..8 {
+        set_get_inverse(mem, size, vdupq_n_u64(u64::MAX ^ (0xFFu64 << (byte_idx * 8))), ctx);
+    }
+
+    for word_idx in 0..4 {
+        set_get_inverse(mem, size, vdupq_n_u64(u64::MAX ^ (0xFFFFu64 << (word_idx * 16))), ctx);
+    }
+
+    for dword_idx in 0..2 {
+        set_get_inverse(mem, size, vdupq_n_u64(u64::MAX ^ (0xFFFFFFFFu64 << (dword_idx * 32))), ctx);
+    }
+}
+
+// See tests_avx2.rs for the row hammer layout.
+#[cfg(target_arch = "aarch64")]
+const ROWHAMMER_ROW_SIZE: usize = 8 * 1024;
+#[cfg(target_arch = "aarch64")]
+const ROWHAMMER_STRIDE: usize = 1024 * 1024;
+#[cfg(target_arch = "aarch64")]
+const ROWHAMMER_ACTIVATIONS: usize = 65536;
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn rowhammer_pattern(idx: usize, pattern: u64) -> uint64x2_t {
+    let pattern = if !(idx / ROWHAMMER_ROW_SIZE).is_multiple_of(2) { !pattern } else { pattern };
+    vdupq_n_u64(pattern)
+}
+
+// DC CIVAC is permitted from EL0 on Linux and macOS
+#[cfg(target_arch = "aarch64")]
+unsafe fn rowhammer_hammer(aggressor1: *const u8, aggressor2: *const u8) {
+    use std::arch::asm;
+    for _ in 0..ROWHAMMER_ACTIVATIONS {
+        std::ptr::read_volatile(aggressor1);
+        std::ptr::read_volatile(aggressor2);
+        asm!(
+            "dc civac, {a1}",
+            "dc civac, {a2}",
+            "dsb ish",
+            a1 = in(reg) aggressor1,
+            a2 = in(reg) aggressor2,
+            options(nostack, preserves_flags),
+        );
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_rowhammer(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    for pattern in [splat8(0x55), splat8(0xAA)] {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                set(mem_ptr, idx, rowhammer_pattern(idx, pattern));
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            let mut offset = 0;
+            while offset + 3 * ROWHAMMER_ROW_SIZE <= chunk_size && !stop_requested(ctx) {
+                let aggressor1 = mem_ptr.add(start + offset);
+                let aggressor2 = mem_ptr.add(start + offset + 2 * ROWHAMMER_ROW_SIZE);
+                rowhammer_hammer(aggressor1, aggressor2);
+                offset += ROWHAMMER_STRIDE;
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, rowhammer_pattern(idx, pattern), ctx);
+            }
+        });
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_bit_fade(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in [0x00u8, 0xFF] {
+        let pattern = vdupq_n_u64(splat8(pattern_val));
+        set_all_up(mem, size, pattern, ctx);
+        retention_sleep(ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+    }
+}
+
+// See tests_avx2.rs for the modulo-20 layout.
+#[cfg(target_arch = "aarch64")]
+const MODULO: usize = 20;
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_modulo_20(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    let pattern = random_pattern(ctx);
+    let not_pattern = veorq_u64(pattern, vdupq_n_u64(u64::MAX));
+    let expected_at = |idx: usize, offset: usize| {
+        if (idx / 16) % MODULO == offset { pattern } else { not_pattern }
+    };
+
+    for offset in 0..MODULO {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 16) % MODULO == offset {
+                    set(mem_ptr, idx, pattern);
+                }
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 16) % MODULO != offset {
+                    set(mem_ptr, idx, not_pattern);
+                }
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, expected_at(idx, offset), ctx);
+            }
+        });
+    }
+}
+
+// See tests_avx2.rs for the block move layout.
+#[cfg(target_arch = "aarch64")]
+const BLOCK_MOVE_SIZE: usize = 4 * 1024 * 1024;
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn load(mem: *const u8, idx: usize) -> uint64x2_t {
+    vld1q_u64((mem.add(idx)) as *const u64)
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn block_move_pattern(offset: usize, round: usize) -> uint64x2_t {
+    // walking bit mixed with the vector index, so a misplaced block can't match by accident
+    let vector = (offset / 16) as u64;
+    let word = 1u64.rotate_left(((vector + round as u64) % 64) as u32) ^ vector.wrapping_mul(0x9E3779B97F4A7C15);
+    vdupq_n_u64(word)
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_block_move(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let shift = (BLOCK_MOVE_SIZE.min(chunk_size) / 2) / 16 * 16;
+    if shift == 0 {
+        return;
+    }
+    let moved = (chunk_size - shift) / 16;
+
+    for round in 0..4 {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                set(mem_ptr, start + j, block_move_pattern(j, round));
+            }
+            for j in until_stopped((0..moved).rev(), ctx) {
+                let idx = start + j * 16;
+                set(mem_ptr, idx + shift, load(mem_ptr, idx));
+            }
+            for j in until_stopped(0..moved, ctx) {
+                let idx = start + shift + j * 16;
+                set(mem_ptr, idx - shift, load(mem_ptr, idx));
+            }
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(16), ctx) {
+                let source = if j < chunk_size - shift { j } else { j - shift };
+                get(mem_ptr, start + j, block_move_pattern(source, round), ctx);
+            }
+        });
+    }
+}
+
+// See tests_avx2.rs for the random address test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_random_address(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let vectors = chunk_size / 16;
+    if vectors == 0 {
+        return;
+    }
+
+    let mut seeds = [0u64; 2];
+    vst1q_u64(seeds.as_mut_ptr(), random_pattern(ctx));
+    let salt = seeds[0].rotate_left(32) ^ seeds[1];
+
+    let walk = |i: usize, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut key = Xorshift128PlusKey { part1: 0, part2: 0 };
+        xorshift128plus_init(seeds[0] ^ i as u64, seeds[1] | 1, &mut key);
+        for _ in until_stopped(0..vectors / 2, ctx) {
+            let offsets = [xorshift128plus(&mut key), xorshift128plus(&mut key)];
+            for offset in offsets {
+                let idx = start + (offset as usize % vectors) * 16;
+                let val = vdupq_n_u64(idx as u64 ^ salt);
+                if verify {
+                    get(mem_ptr as *const u8, idx, val, ctx);
+                } else {
+                    set(mem_ptr, idx, val);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, false));
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, true));
+}
+
+// See tests_avx2.rs for the prime stride walk.
+#[cfg(target_arch = "aarch64")]
+const PRIME_STRIDES: [usize; 2] = [13, 131071];
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn prime_walk(mem: *mut u8, size: usize, stride: usize, pattern: uint64x2_t, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let vectors = chunk_size / 16;
+    if vectors == 0 {
+        return;
+    }
+    let step = stride % vectors;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut pos = 0;
+        let mut cycle = 0;
+        for _ in until_stopped(0..vectors, ctx) {
+            let idx = start + pos * 16;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern, ctx);
+            } else {
+                set(mem_ptr, idx, pattern);
+            }
+            pos += step;
+            if pos >= vectors {
+                pos -= vectors;
+            }
+            if pos == cycle {
+                cycle += 1;
+                pos = cycle;
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_prime_stride(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for stride in PRIME_STRIDES {
+        let pattern = random_pattern(ctx);
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+
+        let pattern = veorq_u64(pattern, vdupq_n_u64(u64::MAX));
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+    }
+}
+
+// See tests_avx2.rs for the address sweep.
+#[cfg(target_arch = "aarch64")]
+unsafe fn address_sweep<F>(mem: *mut u8, size: usize, ascending: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> uint64x2_t + Sync,
+{
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let vectors = chunk_size / 16;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 16;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx));
+            }
+        };
+        if ascending {
+            until_stopped(0..vectors, ctx).for_each(visit);
+        } else {
+            until_stopped((0..vectors).rev(), ctx).for_each(visit);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_addressing(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let increasing = vld1q_u64([0u64, 8].as_ptr());
+    let pattern = |idx: usize| vaddq_u64(vdupq_n_u64(idx), increasing);
+
+    for _ in 0..16 {
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
+        address_sweep(mem, size, false, false, pattern, ctx);
+        address_sweep(mem, size, false, true, pattern, ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_address_line_test(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let pattern = |idx: usize| vdupq_n_u64(idx as u64);
+    address_sweep(mem, size, true, false, pattern, ctx);
+    address_sweep(mem, size, true, true, pattern, ctx);
+
+    let pattern = |idx: usize| vdupq_n_u64(!idx as u64);
+    address_sweep(mem, size, false, false, pattern, ctx);
+    address_sweep(mem, size, false, true, pattern, ctx);
+
+    for shift in [1, 2, 4, 8, 16] {
+        let pattern = move |idx: usize| vdupq_n_u64(idx as u64 ^ ((idx as u64) << shift));
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
+    }
+}
+
+// See sgemm.rs for the SGEMM test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_sgemm(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    for _ in 0..SGEMM_ROUNDS {
+        let seed = vgetq_lane_u64::<0>(random_pattern(ctx));
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let errors = sgemm_chunk(mem_usize as *mut u8, i * chunk_size, chunk_size, seed ^ i as u64, ctx);
+            ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
+}
+
+// See tests_avx2.rs for the custom patterns test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_custom_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in custom_patterns(ctx) {
+        let pattern = vdupq_n_u64(pattern_val);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        let not_pattern = veorq_u64(pattern, vdupq_n_u64(u64::MAX));
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
+    }
+}
+
+// See tests_avx2.rs for the intra cache line walking bit tests.
+#[cfg(target_arch = "aarch64")]
+const LINE_VECTORS: usize = 64 / 16;
+
+// Writes or verifies `line[k]` at the k-th vector of every 64-byte line
+#[cfg(target_arch = "aarch64")]
+unsafe fn line_sweep(mem: *mut u8, size: usize, line: &[uint64x2_t; LINE_VECTORS], verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for j in until_stopped((0..chunk_size / 16).map(|v| start + v * 16), ctx) {
+            let val = line[(j % 64) / 16];
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+unsafe fn walk_line(mem: *mut u8, size: usize, zero: bool, ctx: &TestContext) {
+    for bit in 0..512 {
+        let mut words = [0u64; 8];
+        words[bit / 64] = 1 << (bit % 64);
+        if zero {
+            words = words.map(|w| !w);
+        }
+        let line: [uint64x2_t; LINE_VECTORS] = std::array::from_fn(|k| vld1q_u64(words.as_ptr().add(k * 2)));
+        line_sweep(mem, size, &line, false, ctx);
+        line_sweep(mem, size, &line, true, ctx);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_walking_1_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, false, ctx);
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_walking_0_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, true, ctx);
+}
+
+// See tests_avx2.rs for the refresh stress test.
+#[cfg(target_arch = "aarch64")]
+const REFRESH_WINDOW: usize = 8 * 1024;
+#[cfg(target_arch = "aarch64")]
+const REFRESH_STRIDE: usize = 1024 * 1024;
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_refresh_stress(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    use std::time::Instant;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let dwell = refresh_dwell(ctx);
+    let pattern = random_pattern(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for base in (start..start + chunk_size.saturating_sub(REFRESH_WINDOW - 1)).step_by(REFRESH_STRIDE) {
+            let deadline = Instant::now() + dwell;
+            let mut val = pattern;
+            while Instant::now() < deadline && !stop_requested(ctx) {
+                for j in (base..base + REFRESH_WINDOW).step_by(16) {
+                    set(mem_ptr, j, val);
+                }
+                flush_sweep(mem_ptr.add(base), REFRESH_WINDOW, ctx);
+                for j in (base..base + REFRESH_WINDOW).step_by(16) {
+                    get(mem_ptr as *const u8, j, val, ctx);
+                }
+                val = veorq_u64(val, vdupq_n_u64(u64::MAX));
+            }
+        }
+    });
+}
+
+// See tests_avx2.rs for the LFSR fill test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_lfsr_fill(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let seed = vgetq_lane_u64::<0>(random_pattern(ctx));
+
+    let sweep = |i: usize, invert: bool, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        // the all-zero state is the only one the LFSR never leaves
+        let mut state = (seed ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15)).max(1);
+        let mut words = [0u64; 2];
+        for j in until_stopped((0..chunk_size / 16).map(|v| start + v * 16), ctx) {
+            for word in words.iter_mut() {
+                state = lfsr64_next(state);
+                *word = if invert { !state } else { state };
+            }
+            let val = vld1q_u64(words.as_ptr());
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    for invert in [false, true] {
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, false));
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, true));
+    }
+}
+
+// See tests_avx2.rs for the channel stripes test.
+#[cfg(target_arch = "aarch64")]
+unsafe fn stripe_sweep(mem: *mut u8, size: usize, channel: usize, pattern: uint64x2_t, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let interleave = channel_interleave(ctx);
+    let period = interleave * channel_count(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let end = start + chunk_size;
+        // first stripe of this channel at or before the chunk start
+        let first = start / period * period + channel * interleave;
+        for stripe in until_stopped((first..end).step_by(period), ctx) {
+            for j in (stripe.max(start)..(stripe + interleave).min(end)).step_by(16) {
+                if verify {
+                    get(mem_ptr as *const u8, j, pattern, ctx);
+                } else {
+                    set(mem_ptr, j, pattern);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_channel_stripes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for channel in 0..channel_count(ctx) {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        let random = random_pattern(ctx);
+        let patterns = [random, veorq_u64(random, vdupq_n_u64(u64::MAX)), vdupq_n_u64(splat8(0x55)), vdupq_n_u64(splat8(0xAA))];
+        for pattern in patterns {
+            stripe_sweep(mem, size, channel, pattern, false, ctx);
+            stripe_sweep(mem, size, channel, pattern, true, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on stripes of channel {}", errors, channel_name(channel, ctx));
+        }
+    }
+}
+
+// See coherence.rs for the coherence ping-pong test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_coherence_ping_pong(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = vgetq_lane_u64::<0>(random_pattern(ctx));
+    let errors = coherence_ping_pong(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// See pointer_chase.rs for the pointer-chase test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_pointer_chase(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = vgetq_lane_u64::<0>(random_pattern(ctx));
+    let errors = pointer_chase(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// See tests_avx2.rs for the mixed read/write test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_mixed_rw(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let (reads, writes) = rw_ratio(ctx);
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let vectors = chunk_size / 16;
+    let word = |idx: usize, seed: u64| (idx as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ seed;
+    let pattern = |idx: usize, seed: u64| vdupq_n_u64(word(idx, seed));
+
+    let mut old = vgetq_lane_u64::<0>(random_pattern(ctx));
+    address_sweep(mem, size, true, false, |idx| pattern(idx, old), ctx);
+    flush_sweep(mem, size, ctx);
+
+    for _ in 0..4 {
+        let new = vgetq_lane_u64::<0>(random_pattern(ctx));
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let mut key = (new ^ i as u64) | 1;
+            for v in until_stopped((0..vectors).step_by(writes), ctx) {
+                for idx in (v..(v + writes).min(vectors)).map(|v| i * chunk_size + v * 16) {
+                    set(mem_ptr, idx, pattern(idx, new));
+                }
+                for _ in 0..reads {
+                    key ^= key << 13;
+                    key ^= key >> 7;
+                    key ^= key << 17;
+                    let idx = (key as usize % (vectors * ctx.cpus)) * 16;
+                    // see tests_avx2.rs: every lane on its own holds the old or the new word
+                    let mut lanes = [0u64; 2];
+                    vst1q_u64(lanes.as_mut_ptr(), vld1q_u64(mem_ptr.add(idx) as *const u64));
+                    let bad = (0..2).filter(|&lane| lanes[lane] != word(idx, old) && lanes[lane] != word(idx, new)).fold(0u32, |bad, lane| bad | 1 << lane);
+                    if bad != 0 {
+                        if log_miscompare(ctx) {
+                            error!("errors detected at offset 0x{:016x}", idx);
+                        }
+                        for lane in (0..2).filter(|lane| bad & (1 << lane) != 0) {
+                            record_error(idx + lane * 8, word(idx, new), lanes[lane], ctx);
+                            ctx.run.errors.fetch_add(miscompared_bytes(word(idx, new), lanes[lane]), std::sync::atomic::Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+        });
+        flush_sweep(mem, size, ctx);
+        address_sweep(mem, size, true, true, |idx| pattern(idx, new), ctx);
+        old = new;
+    }
+}
+
+// See tests_avx2.rs for the thermal cycle test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_thermal_cycle(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    let deadline = std::time::Instant::now() + heat_duration(ctx);
+    while std::time::Instant::now() < deadline && !stop_requested(ctx) {
+        for pattern in [vdupq_n_u64(splat8(0x55)), vdupq_n_u64(splat8(0xAA))] {
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem, size, pattern, ctx);
+        }
+    }
+    log_temperatures("heat", ctx.run.errors.load(Ordering::Relaxed));
+
+    retention_sleep(ctx);
+    log_temperatures("cooldown", ctx.run.errors.load(Ordering::Relaxed));
+}
+
+// See tests_avx2.rs for the byte lane test.
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_byte_lanes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for lane in 0..8 {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        for bit in 0..8 {
+            let pattern_val = 1u64 << (lane * 8 + bit);
+            let pattern = vdupq_n_u64(pattern_val);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
+            let not_pattern = veorq_u64(pattern, vdupq_n_u64(u64::MAX));
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on byte lane {}", errors, lane);
+        }
+    }
+}
+
+// See tests_avx2.rs for the butterfly order and test.
+#[cfg(target_arch = "aarch64")]
+unsafe fn butterfly_sweep<F>(mem: *mut u8, size: usize, converging: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> uint64x2_t + Sync,
+{
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 16 * 16;
+    let vectors = chunk_size / 16;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 16;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx));
+            }
+        };
+        let pair = |k: usize| {
+            visit(k);
+            if vectors - 1 - k != k {
+                visit(vectors - 1 - k);
+            }
+        };
+        if converging {
+            until_stopped(0..vectors.div_ceil(2), ctx).for_each(pair);
+        } else {
+            until_stopped((0..vectors.div_ceil(2)).rev(), ctx).for_each(pair);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "aarch64")]
+pub unsafe fn neon_butterfly(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..4 {
+        let salt = vgetq_lane_u64::<0>(random_pattern(ctx));
+        let pattern = |idx: usize| vdupq_n_u64((idx as u64) ^ salt);
+        butterfly_sweep(mem, size, true, false, pattern, ctx);
+        butterfly_sweep(mem, size, false, true, pattern, ctx);
+        let inverse = |idx: usize| veorq_u64(pattern(idx), vdupq_n_u64(u64::MAX));
+        butterfly_sweep(mem, size, false, false, inverse, ctx);
+        butterfly_sweep(mem, size, true, true, inverse, ctx);
+    }
+}
+
+//FIXME: remove stubs and/or error out when running in unsupported configuration
+// Stub implementations for non-AArch64 targets
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_basic_tests(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_march(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_random_inversions(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_inversions_left_64(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_inversions_right_32(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_inversions_left_16(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_inversions_right_8(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_inversions_left_4(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_saturations_right_16(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_moving_saturations_left_8(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_walking_1(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_walking_0(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_checkerboard(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_anti_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_inverse_data_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_rowhammer(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_bit_fade(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_modulo_20(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_block_move(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_random_address(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_prime_stride(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_addressing(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_address_line_test(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_sgemm(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_custom_patterns(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_walking_1_line(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_walking_0_line(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_refresh_stress(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_lfsr_fill(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_channel_stripes(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_coherence_ping_pong(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_pointer_chase(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_mixed_rw(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_thermal_cycle(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_byte_lanes(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
+#[cfg(not(target_arch = "aarch64"))]
+pub unsafe fn neon_butterfly(_mem: *mut u8, _size: usize, _ctx: &TestContext) {}
@@ -0,0 +1,1042 @@
+// Portable fallback for CPUs without AVX2/AVX-512 (old CPUs, VMs with masked CPUID, non-x86).
+// Works on 64-bit words; every pattern of the SIMD backends is lane-wise 64-bit, so the
+// same patterns are reproduced exactly, just at a fraction of the bandwidth.
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use log::error;
+use crate::tests::TestContext;
+use crate::tests::{cached_stores, channel_count, channel_interleave, channel_name, chunk_offsets, chunk_order, custom_patterns, flush_chunk, flush_sweep, heat_duration, inject_faults, lfsr64_next, log_miscompare, log_temperatures, miscompared_bytes, prefetch_ahead, record_error, refresh_dwell, retention_sleep, rw_ratio, stop_requested, tail_get, tail_set, until_stopped};
+use crate::coherence::coherence_ping_pong;
+use crate::pointer_chase::pointer_chase;
+use crate::sgemm::{sgemm_chunk, SGEMM_ROUNDS};
+use crate::simd_xorshift::{xorshift128plus, xorshift128plus_init, xorshift128plus_lanes, Xorshift128PlusKey};
+
+// Next random word of the run's generator
+fn random(ctx: &TestContext) -> u64 {
+    xorshift128plus_lanes(&mut ctx.run.rng.lock().unwrap())
+}
+
+fn splat8(val: u8) -> u64 {
+    u64::from_ne_bytes([val; 8])
+}
+
+fn splat16(val: u16) -> u64 {
+    0x0001000100010001u64 * val as u64
+}
+
+unsafe fn get(mem: *const u8, idx: usize, expected: u64, ctx: &TestContext) {
+    let actual = std::ptr::read_volatile(mem.add(idx) as *const u64);
+    let diff = actual ^ expected;
+
+    if diff != 0 {
+        let error_total = miscompared_bytes(expected, actual);
+        if log_miscompare(ctx) {
+            error!("{} errors detected at offset 0x{:016x} [xor mask: 0x{:016x}]", error_total, idx, diff);
+        }
+        record_error(idx, expected, actual, ctx);
+        ctx.run.errors.fetch_add(error_total, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+unsafe fn get_all_up(mem: *const u8, size: usize, expected: u64, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    inject_faults(mem, size, &expected.to_ne_bytes(), ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx), ctx) {
+            prefetch_ahead(mem_ptr, idx, true, ctx);
+            get(mem_ptr, idx, expected, ctx);
+        }
+    });
+    tail_get(mem, ctx.cpus * chunk_size, size, &expected.to_ne_bytes(), true, ctx);
+}
+
+unsafe fn get_all_down(mem: *const u8, size: usize, expected: u64, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    inject_faults(mem, size, &expected.to_ne_bytes(), ctx);
+    tail_get(mem, ctx.cpus * chunk_size, size, &expected.to_ne_bytes(), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx).rev(), ctx) {
+            prefetch_ahead(mem_ptr, idx, false, ctx);
+            get(mem_ptr, idx, expected, ctx);
+        }
+    });
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn set(mem: *mut u8, idx: usize, val: u64, cached: bool) {
+    if cached {
+        std::ptr::write_volatile((mem.add(idx)) as *mut u64, val);
+    } else {
+        _mm_stream_si64((mem.add(idx)) as *mut i64, val as i64);
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn set(mem: *mut u8, idx: usize, val: u64, _cached: bool) {
+    std::ptr::write_volatile((mem.add(idx)) as *mut u64, val);
+}
+
+unsafe fn set_all_up(mem: *mut u8, size: usize, val: u64, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx), ctx) {
+            set(mem_ptr, idx, val, cached);
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    });
+    tail_set(mem, ctx.cpus * chunk_size, size, &val.to_ne_bytes(), true, ctx);
+}
+
+unsafe fn set_all_down(mem: *mut u8, size: usize, val: u64, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    tail_set(mem, ctx.cpus * chunk_size, size, &val.to_ne_bytes(), false, ctx);
+    chunk_order(ctx).into_par_iter().rev().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx).rev(), ctx) {
+            set(mem_ptr, idx, val, cached);
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    });
+}
+
+unsafe fn set_get_inverse(mem: *mut u8, size: usize, pattern: u64, ctx: &TestContext) {
+    set_all_up(mem, size, pattern, ctx);
+    get_all_up(mem as *const u8, size, pattern, ctx);
+    set_all_up(mem, size, !pattern, ctx);
+    get_all_up(mem as *const u8, size, !pattern, ctx);
+}
+
+unsafe fn set_get_saturation(mem: *mut u8, size: usize, pattern: u64, ctx: &TestContext) {
+    set_all_up(mem, size, pattern, ctx);
+    get_all_up(mem as *const u8, size, pattern, ctx);
+    set_all_up(mem, size, 0, ctx);
+    get_all_up(mem as *const u8, size, 0, ctx);
+    set_all_up(mem, size, pattern, ctx);
+    get_all_up(mem as *const u8, size, pattern, ctx);
+    set_all_up(mem, size, u64::MAX, ctx);
+    get_all_up(mem as *const u8, size, u64::MAX, ctx);
+}
+
+/// Read `mem[..size]` once up the chunks of every thread, prefetching like the verify loops but
+/// comparing nothing, for `calibrate_prefetch()`.
+pub unsafe fn scalar_read_sweep(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    (0..ctx.cpus).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *const u8;
+        let mut any = 0;
+        for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx), ctx) {
+            prefetch_ahead(mem_ptr, idx, true, ctx);
+            any |= std::ptr::read_volatile(mem_ptr.add(idx) as *const u64);
+        }
+        std::hint::black_box(any);
+    });
+}
+
+pub unsafe fn scalar_basic_tests(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let patterns = [0x00u8, 0xFF, 0x0F, 0xF0, 0x55, 0xAA];
+    for pattern_val in &patterns {
+        let pattern = splat8(*pattern_val);
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
+    }
+}
+
+// See avx2_march / march_element for the element sequence.
+unsafe fn march_element(mem: *mut u8, size: usize, ascending: bool, expected: u64, val: u64, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    let element = |i: usize| {
+        let mem_ptr = mem_usize as *mut u8;
+        if ascending {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val, cached);
+            }
+        } else {
+            for idx in until_stopped(chunk_offsets(i, chunk_size, 8, ctx).rev(), ctx) {
+                get(mem_ptr as *const u8, idx, expected, ctx);
+                set(mem_ptr, idx, val, cached);
+            }
+        }
+        flush_chunk(mem_ptr, i, chunk_size, ctx);
+    };
+
+    if ascending {
+        chunk_order(ctx).into_par_iter().for_each(element);
+    } else {
+        chunk_order(ctx).into_par_iter().rev().for_each(element);
+    }
+}
+
+pub unsafe fn scalar_march(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..2 {
+        set_all_up(mem, size, 0, ctx);
+        march_element(mem, size, true, 0, u64::MAX, ctx);
+        march_element(mem, size, true, u64::MAX, 0, ctx);
+        march_element(mem, size, false, 0, u64::MAX, ctx);
+        march_element(mem, size, false, u64::MAX, 0, ctx);
+        get_all_down(mem as *const u8, size, 0, ctx);
+    }
+}
+
+pub unsafe fn scalar_random_inversions(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..16 {
+        let pattern = random(ctx);
+        set_get_inverse(mem, size, pattern, ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_inversions_left_64(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..64 {
+        set_get_inverse(mem, size, 0x0000000000000001u64 << i, ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_inversions_right_32(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..32 {
+        set_get_inverse(mem, size, 0x8000000080000000u64 >> i, ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_inversions_left_16(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..16 {
+        set_get_inverse(mem, size, splat16(0x0001) << i, ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_inversions_right_8(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..8 {
+        set_get_inverse(mem, size, splat8(0x80) >> i, ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_inversions_left_4(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..4 {
+        set_get_inverse(mem, size, splat8(0x11) << i, ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_saturations_right_16(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..16 {
+        set_get_saturation(mem, size, splat16(0x8000u16 >> i), ctx);
+    }
+}
+
+pub unsafe fn scalar_moving_saturations_left_8(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for i in 0..8 {
+        set_get_saturation(mem, size, splat16(0x01u16 >> i), ctx);
+    }
+}
+
+pub unsafe fn scalar_walking_1(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for bit in 0..64 {
+        set_get_inverse(mem, size, 1u64 << bit, ctx);
+    }
+}
+
+pub unsafe fn scalar_walking_0(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for bit in 0..64 {
+        set_get_inverse(mem, size, !(1u64 << bit), ctx);
+    }
+}
+
+pub unsafe fn scalar_checkerboard(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    let pattern1 = splat8(0xAA);
+    let pattern2 = splat8(0x55);
+
+    for (odd, even) in [(pattern1, pattern2), (pattern2, pattern1)] {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                let pattern = if !(idx / 8).is_multiple_of(2) { odd } else { even };
+                set(mem_ptr, idx, pattern, cached);
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                let expected = if !(idx / 8).is_multiple_of(2) { odd } else { even };
+                get(mem_ptr, idx, expected, ctx);
+            }
+        });
+    }
+}
+
+pub unsafe fn scalar_anti_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let patterns = [
+        0x00, 0xFF, 0x0F, 0xF0, 0x55, 0xAA, 0x33, 0xCC,
+        0x11, 0xEE, 0x22, 0xDD, 0x44, 0xBB, 0x66, 0x99,
+        0x77, 0x88, 0x01, 0xFE, 0x02, 0xFD, 0x04, 0xFB,
+        0x08, 0xF7, 0x10, 0xEF, 0x20, 0xDF, 0x40, 0xBF,
+        0x80, 0x7F,
+    ];
+
+    for pattern_val in &patterns {
+        let pattern = splat8(*pattern_val);
+        let anti_pattern = !pattern;
+
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        set_all_up(mem, size, anti_pattern, ctx);
+        get_all_up(mem as *const u8, size, anti_pattern, ctx);
+
+        set_all_down(mem, size, pattern, ctx);
+        get_all_down(mem as *const u8, size, pattern, ctx);
+        set_all_down(mem, size, anti_pattern, ctx);
+        get_all_down(mem as *const u8, size, anti_pattern, ctx);
+    }
+}
+
+pub unsafe fn scalar_inverse_data_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for byte_idx in 0..8 {
+        set_get_inverse(mem, size, u64::MAX ^ (0xFFu64 << (byte_idx * 8)), ctx);
+    }
+
+    for word_idx in 0..4 {
+        set_get_inverse(mem, size, u64::MAX ^ (0xFFFFu64 << (word_idx * 16)), ctx);
+    }
+
+    for dword_idx in 0..2 {
+        set_get_inverse(mem, size, u64::MAX ^ (0xFFFFFFFFu64 << (dword_idx * 32)), ctx);
+    }
+}
+
+// See tests_avx2.rs for the row hammer layout.
+const ROWHAMMER_ROW_SIZE: usize = 8 * 1024;
+const ROWHAMMER_STRIDE: usize = 1024 * 1024;
+const ROWHAMMER_ACTIVATIONS: usize = 65536;
+
+fn rowhammer_pattern(idx: usize, pattern: u64) -> u64 {
+    if !(idx / ROWHAMMER_ROW_SIZE).is_multiple_of(2) { !pattern } else { pattern }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn rowhammer_hammer(aggressor1: *const u8, aggressor2: *const u8) {
+    for _ in 0..ROWHAMMER_ACTIVATIONS {
+        std::ptr::read_volatile(aggressor1);
+        std::ptr::read_volatile(aggressor2);
+        _mm_clflush(aggressor1);
+        _mm_clflush(aggressor2);
+        _mm_mfence();
+    }
+}
+
+// without a portable cache flush, the reads are mostly served from cache and
+// hammer far less effectively than on x86_64
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn rowhammer_hammer(aggressor1: *const u8, aggressor2: *const u8) {
+    for _ in 0..ROWHAMMER_ACTIVATIONS {
+        std::ptr::read_volatile(aggressor1);
+        std::ptr::read_volatile(aggressor2);
+    }
+}
+
+pub unsafe fn scalar_rowhammer(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    for pattern in [splat8(0x55), splat8(0xAA)] {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                set(mem_ptr, idx, rowhammer_pattern(idx, pattern), cached);
+            }
+            #[cfg(target_arch = "x86_64")]
+            _mm_sfence();
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            let mut offset = 0;
+            while offset + 3 * ROWHAMMER_ROW_SIZE <= chunk_size && !stop_requested(ctx) {
+                let aggressor1 = mem_ptr.add(start + offset);
+                let aggressor2 = mem_ptr.add(start + offset + 2 * ROWHAMMER_ROW_SIZE);
+                rowhammer_hammer(aggressor1, aggressor2);
+                offset += ROWHAMMER_STRIDE;
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, rowhammer_pattern(idx, pattern), ctx);
+            }
+        });
+    }
+}
+
+pub unsafe fn scalar_bit_fade(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in [0x00u8, 0xFF] {
+        let pattern = splat8(pattern_val);
+        set_all_up(mem, size, pattern, ctx);
+        retention_sleep(ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+    }
+}
+
+// See tests_avx2.rs for the modulo-20 layout.
+const MODULO: usize = 20;
+
+pub unsafe fn scalar_modulo_20(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    let pattern = random(ctx);
+    let not_pattern = !pattern;
+    let expected_at = |idx: usize, offset: usize| {
+        if (idx / 8) % MODULO == offset { pattern } else { not_pattern }
+    };
+
+    for offset in 0..MODULO {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 8) % MODULO == offset {
+                    set(mem_ptr, idx, pattern, cached);
+                }
+            }
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                if (idx / 8) % MODULO != offset {
+                    set(mem_ptr, idx, not_pattern, cached);
+                }
+            }
+            flush_sweep(mem_ptr.add(i * chunk_size), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let idx = j + i * chunk_size;
+                get(mem_ptr, idx, expected_at(idx, offset), ctx);
+            }
+        });
+    }
+}
+
+// See tests_avx2.rs for the block move layout.
+const BLOCK_MOVE_SIZE: usize = 4 * 1024 * 1024;
+
+unsafe fn load(mem: *const u8, idx: usize) -> u64 {
+    std::ptr::read_volatile(mem.add(idx) as *const u64)
+}
+
+fn block_move_pattern(offset: usize, round: usize) -> u64 {
+    // walking bit mixed with the word index, so a misplaced block can't match by accident
+    let word = (offset / 8) as u64;
+    1u64.rotate_left(((word + round as u64) % 64) as u32) ^ word.wrapping_mul(0x9E3779B97F4A7C15)
+}
+
+pub unsafe fn scalar_block_move(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let shift = (BLOCK_MOVE_SIZE.min(chunk_size) / 2) / 8 * 8;
+    if shift == 0 {
+        return;
+    }
+    let moved = (chunk_size - shift) / 8;
+
+    for round in 0..4 {
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                set(mem_ptr, start + j, block_move_pattern(j, round), cached);
+            }
+            for j in until_stopped((0..moved).rev(), ctx) {
+                let idx = start + j * 8;
+                set(mem_ptr, idx + shift, load(mem_ptr, idx), cached);
+            }
+            for j in until_stopped(0..moved, ctx) {
+                let idx = start + shift + j * 8;
+                set(mem_ptr, idx - shift, load(mem_ptr, idx), cached);
+            }
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        });
+
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *const u8;
+            let start = i * chunk_size;
+            for j in until_stopped((0..chunk_size).step_by(8), ctx) {
+                let source = if j < chunk_size - shift { j } else { j - shift };
+                get(mem_ptr, start + j, block_move_pattern(source, round), ctx);
+            }
+        });
+    }
+}
+
+// See tests_avx2.rs for the random address test.
+pub unsafe fn scalar_random_address(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let words = chunk_size / 8;
+    if words == 0 {
+        return;
+    }
+
+    let seeds = [random(ctx), random(ctx)];
+    let salt = seeds[0].rotate_left(32) ^ seeds[1];
+
+    let walk = |i: usize, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut key = Xorshift128PlusKey { part1: 0, part2: 0 };
+        xorshift128plus_init(seeds[0] ^ i as u64, seeds[1] | 1, &mut key);
+        for _ in until_stopped(0..words / 2, ctx) {
+            let offsets = [xorshift128plus(&mut key), xorshift128plus(&mut key)];
+            for offset in offsets {
+                let idx = start + (offset as usize % words) * 8;
+                let val = idx as u64 ^ salt;
+                if verify {
+                    get(mem_ptr as *const u8, idx, val, ctx);
+                } else {
+                    set(mem_ptr, idx, val, cached);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, false));
+    chunk_order(ctx).into_par_iter().for_each(|i| walk(i, true));
+}
+
+// See tests_avx2.rs for the prime stride walk.
+const PRIME_STRIDES: [usize; 2] = [13, 131071];
+
+unsafe fn prime_walk(mem: *mut u8, size: usize, stride: usize, pattern: u64, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let words = chunk_size / 8;
+    if words == 0 {
+        return;
+    }
+    let step = stride % words;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let mut pos = 0;
+        let mut cycle = 0;
+        for _ in until_stopped(0..words, ctx) {
+            let idx = start + pos * 8;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern, ctx);
+            } else {
+                set(mem_ptr, idx, pattern, cached);
+            }
+            pos += step;
+            if pos >= words {
+                pos -= words;
+            }
+            if pos == cycle {
+                cycle += 1;
+                pos = cycle;
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+pub unsafe fn scalar_prime_stride(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for stride in PRIME_STRIDES {
+        let pattern = random(ctx);
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+
+        let pattern = !pattern;
+        prime_walk(mem, size, stride, pattern, false, ctx);
+        prime_walk(mem, size, stride, pattern, true, ctx);
+    }
+}
+
+// See tests_avx2.rs for the address sweep.
+unsafe fn address_sweep<F>(mem: *mut u8, size: usize, ascending: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> u64 + Sync,
+{
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let words = chunk_size / 8;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |w: usize| {
+            let idx = start + w * 8;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx), cached);
+            }
+        };
+        if ascending {
+            until_stopped(0..words, ctx).for_each(visit);
+        } else {
+            until_stopped((0..words).rev(), ctx).for_each(visit);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+pub unsafe fn scalar_addressing(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let pattern = |idx: usize| idx as u64;
+
+    for _ in 0..16 {
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
+        address_sweep(mem, size, false, false, pattern, ctx);
+        address_sweep(mem, size, false, true, pattern, ctx);
+    }
+}
+
+pub unsafe fn scalar_address_line_test(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let pattern = |idx: usize| idx as u64;
+    address_sweep(mem, size, true, false, pattern, ctx);
+    address_sweep(mem, size, true, true, pattern, ctx);
+
+    let pattern = |idx: usize| !idx as u64;
+    address_sweep(mem, size, false, false, pattern, ctx);
+    address_sweep(mem, size, false, true, pattern, ctx);
+
+    for shift in [1, 2, 4, 8, 16] {
+        let pattern = move |idx: usize| idx as u64 ^ ((idx as u64) << shift);
+        address_sweep(mem, size, true, false, pattern, ctx);
+        address_sweep(mem, size, true, true, pattern, ctx);
+    }
+}
+
+// See sgemm.rs for the SGEMM test.
+pub unsafe fn scalar_sgemm(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    for _ in 0..SGEMM_ROUNDS {
+        let seed = random(ctx);
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let errors = sgemm_chunk(mem_usize as *mut u8, i * chunk_size, chunk_size, seed ^ i as u64, ctx);
+            ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
+}
+
+// See tests_avx2.rs for the custom patterns test.
+pub unsafe fn scalar_custom_patterns(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for pattern_val in custom_patterns(ctx) {
+        let pattern = pattern_val;
+        set_all_up(mem, size, pattern, ctx);
+        get_all_up(mem as *const u8, size, pattern, ctx);
+        let not_pattern = !pattern;
+        set_all_up(mem, size, not_pattern, ctx);
+        get_all_up(mem as *const u8, size, not_pattern, ctx);
+    }
+}
+
+// See tests_avx2.rs for the intra cache line walking bit tests.
+const LINE_WORDS: usize = 64 / 8;
+
+// Writes or verifies `line[k]` at the k-th word of every 64-byte line
+unsafe fn line_sweep(mem: *mut u8, size: usize, line: &[u64; LINE_WORDS], verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for j in until_stopped((0..chunk_size / 8).map(|w| start + w * 8), ctx) {
+            let val = line[(j % 64) / 8];
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val, cached);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+unsafe fn walk_line(mem: *mut u8, size: usize, zero: bool, ctx: &TestContext) {
+    for bit in 0..512 {
+        let mut words = [0u64; 8];
+        words[bit / 64] = 1 << (bit % 64);
+        if zero {
+            words = words.map(|w| !w);
+        }
+        line_sweep(mem, size, &words, false, ctx);
+        line_sweep(mem, size, &words, true, ctx);
+    }
+}
+
+pub unsafe fn scalar_walking_1_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, false, ctx);
+}
+
+pub unsafe fn scalar_walking_0_line(mem: *mut u8, size: usize, ctx: &TestContext) {
+    walk_line(mem, size, true, ctx);
+}
+
+// See tests_avx2.rs for the refresh stress test.
+const REFRESH_WINDOW: usize = 8 * 1024;
+const REFRESH_STRIDE: usize = 1024 * 1024;
+
+pub unsafe fn scalar_refresh_stress(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    use std::time::Instant;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let dwell = refresh_dwell(ctx);
+    let pattern = random(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        for base in (start..start + chunk_size.saturating_sub(REFRESH_WINDOW - 1)).step_by(REFRESH_STRIDE) {
+            let deadline = Instant::now() + dwell;
+            let mut val = pattern;
+            while Instant::now() < deadline && !stop_requested(ctx) {
+                for j in (base..base + REFRESH_WINDOW).step_by(8) {
+                    set(mem_ptr, j, val, cached);
+                }
+                flush_sweep(mem_ptr.add(base), REFRESH_WINDOW, ctx);
+                for j in (base..base + REFRESH_WINDOW).step_by(8) {
+                    get(mem_ptr as *const u8, j, val, ctx);
+                }
+                val = !val;
+            }
+        }
+    });
+}
+
+// See tests_avx2.rs for the LFSR fill test.
+pub unsafe fn scalar_lfsr_fill(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let seed = random(ctx);
+
+    let sweep = |i: usize, invert: bool, verify: bool| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        // the all-zero state is the only one the LFSR never leaves
+        let mut state = (seed ^ (i as u64).wrapping_mul(0x9E3779B97F4A7C15)).max(1);
+        let mut words = [0u64; 1];
+        for j in until_stopped((0..chunk_size / 8).map(|w| start + w * 8), ctx) {
+            for word in words.iter_mut() {
+                state = lfsr64_next(state);
+                *word = if invert { !state } else { state };
+            }
+            let val = words[0];
+            if verify {
+                get(mem_ptr as *const u8, j, val, ctx);
+            } else {
+                set(mem_ptr, j, val, cached);
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    };
+
+    for invert in [false, true] {
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, false));
+        chunk_order(ctx).into_par_iter().for_each(|i| sweep(i, invert, true));
+    }
+}
+
+// See tests_avx2.rs for the channel stripes test.
+unsafe fn stripe_sweep(mem: *mut u8, size: usize, channel: usize, pattern: u64, verify: bool, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let interleave = channel_interleave(ctx);
+    let period = interleave * channel_count(ctx);
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let end = start + chunk_size;
+        // first stripe of this channel at or before the chunk start
+        let first = start / period * period + channel * interleave;
+        for stripe in until_stopped((first..end).step_by(period), ctx) {
+            for j in (stripe.max(start)..(stripe + interleave).min(end)).step_by(8) {
+                if verify {
+                    get(mem_ptr as *const u8, j, pattern, ctx);
+                } else {
+                    set(mem_ptr, j, pattern, cached);
+                }
+            }
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+pub unsafe fn scalar_channel_stripes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for channel in 0..channel_count(ctx) {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        let random = random(ctx);
+        let patterns = [random, !random, splat8(0x55), splat8(0xAA)];
+        for pattern in patterns {
+            stripe_sweep(mem, size, channel, pattern, false, ctx);
+            stripe_sweep(mem, size, channel, pattern, true, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on stripes of channel {}", errors, channel_name(channel, ctx));
+        }
+    }
+}
+
+// See coherence.rs for the coherence ping-pong test.
+pub unsafe fn scalar_coherence_ping_pong(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = random(ctx);
+    let errors = coherence_ping_pong(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// See pointer_chase.rs for the pointer-chase test.
+pub unsafe fn scalar_pointer_chase(mem: *mut u8, size: usize, ctx: &TestContext) {
+    let seed = random(ctx);
+    let errors = pointer_chase(mem, size, seed, ctx);
+    ctx.run.errors.fetch_add(errors, std::sync::atomic::Ordering::Relaxed);
+}
+
+// See tests_avx2.rs for the mixed read/write test.
+pub unsafe fn scalar_mixed_rw(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use rayon::prelude::*;
+    let (reads, writes) = rw_ratio(ctx);
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let vectors = chunk_size / 8;
+    let pattern = |idx: usize, seed: u64| (idx as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ seed;
+    let same = |a: u64, b: u64| a == b;
+
+    let mut old = random(ctx);
+    address_sweep(mem, size, true, false, |idx| pattern(idx, old), ctx);
+    flush_sweep(mem, size, ctx);
+
+    for _ in 0..4 {
+        let new = random(ctx);
+        chunk_order(ctx).into_par_iter().for_each(|i| {
+            let mem_ptr = mem_usize as *mut u8;
+            let mut key = (new ^ i as u64) | 1;
+            for v in until_stopped((0..vectors).step_by(writes), ctx) {
+                for idx in (v..(v + writes).min(vectors)).map(|v| i * chunk_size + v * 8) {
+                    set(mem_ptr, idx, pattern(idx, new), cached);
+                }
+                for _ in 0..reads {
+                    key ^= key << 13;
+                    key ^= key >> 7;
+                    key ^= key << 17;
+                    let idx = (key as usize % (vectors * ctx.cpus)) * 8;
+                    let actual = std::ptr::read_volatile(mem_ptr.add(idx) as *const u64);
+                    if !same(actual, pattern(idx, old)) && !same(actual, pattern(idx, new)) {
+                        if log_miscompare(ctx) {
+                            error!("errors detected at offset 0x{:016x}", idx);
+                        }
+                        record_error(idx, pattern(idx, new), actual, ctx);
+                        ctx.run.errors.fetch_add(miscompared_bytes(pattern(idx, new), actual), std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
+            }
+        });
+        flush_sweep(mem, size, ctx);
+        address_sweep(mem, size, true, true, |idx| pattern(idx, new), ctx);
+        old = new;
+    }
+}
+
+// See tests_avx2.rs for the thermal cycle test.
+pub unsafe fn scalar_thermal_cycle(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    let deadline = std::time::Instant::now() + heat_duration(ctx);
+    while std::time::Instant::now() < deadline && !stop_requested(ctx) {
+        for pattern in [splat8(0x55), splat8(0xAA)] {
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem, size, pattern, ctx);
+        }
+    }
+    log_temperatures("heat", ctx.run.errors.load(Ordering::Relaxed));
+
+    retention_sleep(ctx);
+    log_temperatures("cooldown", ctx.run.errors.load(Ordering::Relaxed));
+}
+
+// See tests_avx2.rs for the byte lane test.
+pub unsafe fn scalar_byte_lanes(mem: *mut u8, size: usize, ctx: &TestContext) {
+    use std::sync::atomic::Ordering;
+    for lane in 0..8 {
+        let before = ctx.run.errors.load(Ordering::Relaxed);
+        for bit in 0..8 {
+            let pattern = 1u64 << (lane * 8 + bit);
+            set_all_up(mem, size, pattern, ctx);
+            get_all_up(mem as *const u8, size, pattern, ctx);
+            let not_pattern = !pattern;
+            set_all_up(mem, size, not_pattern, ctx);
+            get_all_up(mem as *const u8, size, not_pattern, ctx);
+        }
+        let errors = ctx.run.errors.load(Ordering::Relaxed) - before;
+        if errors > 0 {
+            error!("{} errors detected on byte lane {}", errors, lane);
+        }
+    }
+}
+
+// See tests_avx2.rs for the butterfly order and test.
+unsafe fn butterfly_sweep<F>(mem: *mut u8, size: usize, converging: bool, verify: bool, pattern: F, ctx: &TestContext)
+where
+    F: Fn(usize) -> u64 + Sync,
+{
+    use rayon::prelude::*;
+    let mem_usize = mem as usize;
+    let cached = cached_stores(ctx);
+    let chunk_size = size / ctx.cpus / 8 * 8;
+    let vectors = chunk_size / 8;
+
+    chunk_order(ctx).into_par_iter().for_each(|i| {
+        let mem_ptr = mem_usize as *mut u8;
+        let start = i * chunk_size;
+        let visit = |v: usize| {
+            let idx = start + v * 8;
+            if verify {
+                get(mem_ptr as *const u8, idx, pattern(idx), ctx);
+            } else {
+                set(mem_ptr, idx, pattern(idx), cached);
+            }
+        };
+        let pair = |k: usize| {
+            visit(k);
+            if vectors - 1 - k != k {
+                visit(vectors - 1 - k);
+            }
+        };
+        if converging {
+            until_stopped(0..vectors.div_ceil(2), ctx).for_each(pair);
+        } else {
+            until_stopped((0..vectors.div_ceil(2)).rev(), ctx).for_each(pair);
+        }
+        if !verify {
+            flush_sweep(mem_ptr.add(start), chunk_size, ctx);
+        }
+    });
+}
+
+pub unsafe fn scalar_butterfly(mem: *mut u8, size: usize, ctx: &TestContext) {
+    for _ in 0..4 {
+        let salt = random(ctx);
+        let pattern = |idx: usize| idx as u64 ^ salt;
+        butterfly_sweep(mem, size, true, false, pattern, ctx);
+        butterfly_sweep(mem, size, false, true, pattern, ctx);
+        let inverse = |idx: usize| !pattern(idx);
+        butterfly_sweep(mem, size, false, false, inverse, ctx);
+        butterfly_sweep(mem, size, true, true, inverse, ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use crate::tests::{Pause, RunState};
+    use super::*;
+
+    const GUARD: usize = 256;
+
+    // Per-thread chunks that are not whole vectors used to make the descending walks misaligned
+    // and let the last vector spill past the chunk; run every size on a guarded buffer.
+    #[test]
+    fn addressing_small_buffers() {
+        let stop = AtomicBool::new(false);
+        let pause = Pause::default();
+        let run = RunState::new(4, 1, 0, &stop, &pause);
+        for cpus in [1, 3, 4] {
+            for size in [0, 8, 100, 4096, 3 * 4096 + 96, 64 * 1024 + 8] {
+                unsafe {
+                    let ctx = TestContext { cpus, ..TestContext::new(&run) };
+                    let layout = Layout::from_size_align(size + GUARD, 4096).unwrap();
+                    let mem = alloc_zeroed(layout);
+                    std::ptr::write_bytes(mem.add(size), 0xA5, GUARD);
+
+                    scalar_addressing(mem, size, &ctx);
+                    scalar_address_line_test(mem, size, &ctx);
+
+                    let guard = std::slice::from_raw_parts(mem.add(size), GUARD);
+                    assert!(guard.iter().all(|&b| b == 0xA5), "write past end, size {} cpus {}", size, cpus);
+                    dealloc(mem, layout);
+                }
+            }
+        }
+        assert_eq!(run.errors.load(Ordering::Relaxed), 0);
+    }
+}
@@ -1,22 +1,36 @@
 // src/gui.rs
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
-};
-use std::thread;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
 
 use eframe::{egui, run_native, NativeOptions};
 use egui::{
-    CentralPanel, Color32, ScrollArea, TextEdit,
-    ViewportBuilder,
+    Button, CentralPanel, CollapsingHeader, Color32, DragValue, FontId, Grid, ProgressBar, ScrollArea, Slider, TextStyle,
+    TextEdit, ThemePreference, ViewportBuilder,
+};
+use egui_plot::{Legend, Line, Plot};
+use serde::{Deserialize, Serialize, Serializer};
+
+use manganese_core::{
+    find_config, format_duration, load_custom_config, raise_memlock_limit, sysinfo, test_catalog, CustomConfig, ErrorDetail, Metric,
+    Progress, RunError, RunReport, TestConfigEntry, TestKind, TestSession,
 };
 
-use manganese_core::{parse_ram_spec, run_tests, sysinfo, RamSpec};
+use log::{error, LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::i18n::{set_language, tr, tr_log, trf, Language};
+
+// Lines the console keeps, the oldest dropped first: a multi-day run logs far more
+const LOG_LINES: usize = 10_000;
 
-use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+// Time between repaints without new log lines, for the progress, graphs and error table
+const HEARTBEAT: Duration = Duration::from_millis(500);
 
 struct GuiLogger {
-    buffer: Arc<Mutex<String>>,
+    buffer: Arc<Mutex<VecDeque<String>>>,
+    // repainted for every line logged, from whatever thread logs it
+    ctx: egui::Context,
 }
 
 impl Log for GuiLogger {
@@ -26,16 +40,21 @@ impl Log for GuiLogger {
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
+            let message = format!("[{}] {}", record.level(), tr_log(&record.args().to_string()));
             let mut buf = self.buffer.lock().unwrap();
-            buf.push_str(&format!("[{}] {}\n", record.level(), record.args()));
+            // one row per line, the console lays out only the rows in view
+            buf.extend(message.lines().map(str::to_owned));
+            let excess = buf.len().saturating_sub(LOG_LINES);
+            buf.drain(..excess);
+            self.ctx.request_repaint();
         }
     }
 
     fn flush(&self) {}
 }
 
-fn init_gui_logger(buffer: Arc<Mutex<String>>) -> Result<(), SetLoggerError> {
-    log::set_boxed_logger(Box::new(GuiLogger { buffer }))
+fn init_gui_logger(buffer: Arc<Mutex<VecDeque<String>>>, ctx: egui::Context) -> Result<(), SetLoggerError> {
+    log::set_boxed_logger(Box::new(GuiLogger { buffer, ctx }))
         .map(|()| log::set_max_level(LevelFilter::Info))
 }
 
@@ -53,37 +72,115 @@ pub fn launch_gui() -> eframe::Result<()> {
     run_native(
         format!("Manganese RAM Tester {} 🎉", env!("CARGO_PKG_VERSION")).as_str(),
         native_options,
-        Box::new(|_cc| {
-            let app = Box::new(GuiApp::default());
-            //apply_monospace_fonts(&cc.egui_ctx);
+        Box::new(|cc| {
+            let mut app = Box::new(GuiApp::new(&cc.egui_ctx));
+            if let Some(settings) = cc.storage.and_then(|storage| eframe::get_value(storage, eframe::APP_KEY)) {
+                app.settings = settings;
+            }
+            app.settings.apply(&cc.egui_ctx);
             Ok(app)
         }),
     )
 }
 
+// Look of the window, kept across starts by eframe (its position and size are kept by eframe too)
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    language: Language,
+    theme: ThemePreference,
+    // scale of the whole window, for lab monitors across the room
+    zoom: f32,
+    // size of the monospace font of the console output and the error table
+    font_size: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { language: Language::detect(), theme: ThemePreference::System, zoom: 1.0, font_size: 12.0 }
+    }
+}
+
+impl Settings {
+    fn apply(&self, ctx: &egui::Context) {
+        set_language(self.language);
+        ctx.set_theme(self.theme);
+        ctx.set_zoom_factor(self.zoom);
+        ctx.all_styles_mut(|style| {
+            style.text_styles.insert(TextStyle::Monospace, FontId::monospace(self.font_size));
+        });
+    }
+
+    // Edit the settings, applying them once changed
+    fn show(&mut self, ui: &mut egui::Ui) {
+        let before = self.clone();
+        let mut zoom = None;
+        CollapsingHeader::new(tr("Settings")).id_salt("settings").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Language:"));
+                for language in Language::ALL {
+                    ui.selectable_value(&mut self.language, language, language.name());
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("Theme:"));
+                ui.selectable_value(&mut self.theme, ThemePreference::System, tr("System"));
+                ui.selectable_value(&mut self.theme, ThemePreference::Dark, tr("Dark"));
+                ui.selectable_value(&mut self.theme, ThemePreference::Light, tr("Light"));
+            });
+            // only applied on release, the window would rescale under the pointer
+            zoom = Some(ui.add(Slider::new(&mut self.zoom, 0.5..=3.0).text(tr("Scale"))));
+            ui.add(Slider::new(&mut self.font_size, 8.0..=32.0).fixed_decimals(0).text(tr("Console font size")));
+        });
+        if zoom.as_ref().is_some_and(|zoom| zoom.dragged()) {
+            return;
+        }
+        if *self != before || zoom.is_some_and(|zoom| zoom.drag_stopped()) {
+            self.apply(ui.ctx());
+        } else {
+            // follow Ctrl +/- too
+            self.zoom = ui.ctx().zoom_factor();
+        }
+    }
+}
+
 struct GuiApp {
-    ram_input: String,
+    settings: Settings,
+    // share of the free RAM to test
+    ram_percent: f64,
+    // (bytes asked for, lockable bytes or None if unlimited) of the last memlock check
+    memlock: Option<(usize, Option<usize>)>,
     hide_serials: bool,
-    running: bool,
-    stop_flag: Arc<AtomicBool>,
+    plan: PlanEditor,
+    // the running test, joined once it ends
+    session: Option<TestSession>,
     status: String,
-    test_handle: Option<thread::JoinHandle<()>>,
-    log_buffer: Arc<Mutex<String>>,
+    log_buffer: Arc<Mutex<VecDeque<String>>>,
+    metrics: Option<Receiver<Metric>>,
+    // every loop of the current (or last) run
+    samples: Vec<Metric>,
+    errors: Option<Receiver<ErrorDetail>>,
+    faults: FaultTable,
 }
 
-impl Default for GuiApp {
-    fn default() -> Self {
-        let buffer = Arc::new(Mutex::new(String::new()));
-        init_gui_logger(buffer.clone()).unwrap();
+impl GuiApp {
+    fn new(ctx: &egui::Context) -> Self {
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+        init_gui_logger(buffer.clone(), ctx.clone()).unwrap();
 
         Self {
-            ram_input: "".to_owned(),
+            settings: Settings::default(),
+            ram_percent: 80.0,
+            memlock: None,
             hide_serials: false,
-            running: false,
-            stop_flag: Arc::new(AtomicBool::new(false)),
-            status: "Idle".to_owned(),
-            test_handle: None,
+            plan: PlanEditor::new(),
+            session: None,
+            status: tr("Idle").to_owned(),
             log_buffer: buffer,
+            metrics: None,
+            samples: Vec::new(),
+            errors: None,
+            faults: FaultTable::default(),
         }
     }
 }
@@ -92,95 +189,495 @@ impl eframe::App for GuiApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         CentralPanel::default().show(ctx, |ui| {
             ui.heading("Manganese RAM Tester");
+            self.settings.show(ui);
 
-            ui.horizontal(|ui| {
-                ui.label("RAM to test:");
-                ui.add(
-                    TextEdit::singleline(&mut self.ram_input)
-                        .hint_text("e.g. 4GiB, 50%, 10%t")
-                        .desired_width(200.0),
-                );
-                ui.spacing();
-                ui.checkbox(&mut self.hide_serials, "Hide serial numbers");
+            let sysinfo = sysinfo();
+            let ram_bytes = (sysinfo.freeram as f64 * self.ram_percent / 100.0) as usize;
+            ui.add_enabled_ui(self.session.is_none(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("RAM to test:"));
+                    ui.add(Slider::new(&mut self.ram_percent, 1.0..=100.0).suffix(tr("% of free")).fixed_decimals(0));
+                    for preset in [50.0, 80.0, 90.0] {
+                        if ui.selectable_label(self.ram_percent == preset, format!("{}%", preset)).clicked() {
+                            self.ram_percent = preset;
+                        }
+                    }
+                });
+                ui.label(trf("{} of {} free, {} total", &[&gib(ram_bytes), &gib(sysinfo.freeram), &gib(sysinfo.totalram)]));
+                ui.checkbox(&mut self.hide_serials, tr("Hide serial numbers"));
             });
 
-            if !self.running {
+            // ask for all of the free RAM once rather than on every move of the slider
+            if self.session.is_none() && self.memlock.is_none_or(|(asked, _)| asked < ram_bytes) {
+                self.memlock = Some((sysinfo.freeram, raise_memlock_limit(sysinfo.freeram)));
+            }
+            ui.add_enabled_ui(self.session.is_none(), |ui| {
+                if let Some(status) = self.plan.show(ui) {
+                    self.status = status;
+                }
+            });
+
+            let lockable = self.memlock.and_then(|(_, limit)| limit);
+            let startable = ram_bytes > 0 && lockable.is_none_or(|limit| limit >= ram_bytes);
+            if self.session.is_none() && !startable {
+                ui.colored_label(Color32::RED, match lockable {
+                    Some(limit) if ram_bytes > 0 => trf("Only {} can be locked; run as root (or with CAP_IPC_LOCK), \
+                        raise `ulimit -l` or grant SeLockMemoryPrivilege", &[&gib(limit)]),
+                    _ => tr("No free RAM to test").to_owned(),
+                });
+            }
+            let plan = self.plan.config();
+            if self.session.is_none() && plan.entries.is_empty() {
+                ui.colored_label(Color32::RED, tr("No tests selected"));
+            }
+
+            if self.session.is_none() {
                 if ui
-                    .add(egui::Button::new("Start").fill(Color32::DARK_GREEN))
+                    .add_enabled(startable && !plan.entries.is_empty(), egui::Button::new(tr("Start")).fill(Color32::DARK_GREEN))
                     .clicked()
                 {
-                    // compute ram_bytes
-                    let sysinfo = sysinfo();
-                    let total = sysinfo.totalram;
-                    let avail = sysinfo.freeram;
-
-                    let ram_bytes = match parse_ram_spec(&self.ram_input) {
-                        Some(RamSpec::Bytes(b)) => b,
-                        Some(RamSpec::Percent(fr, true)) => (total as f64 * fr) as usize,
-                        Some(RamSpec::Percent(fr, false)) => (avail as f64 * fr) as usize,
-                        None => {
-                            self.status = format!("Invalid RAM spec: {}", self.ram_input);
-                            return;
-                        }
-                    };
-
-                    self.running = true;
-                    self.stop_flag.store(false, Ordering::SeqCst);
-                    self.status = "Running...".to_string();
+                    self.status = tr("Running...").to_string();
 
                     // Clear previous log
                     {
                         let mut log = self.log_buffer.lock().unwrap();
                         log.clear();
                     }
-                    let stop_clone = self.stop_flag.clone();
-                    let hide_serials = self.hide_serials;
-
-                    self.test_handle = Option::from(thread::spawn(move || {
-                        // run the tests (existing code, no change required)
-                        run_tests(ram_bytes, hide_serials, &stop_clone);
-                    }));
+                    let mut session = TestSession::builder().ram(ram_bytes).hide_serials(self.hide_serials).tests(plan).build();
+                    self.metrics = Some(session.subscribe_metrics());
+                    self.samples.clear();
+                    self.errors = Some(session.subscribe_errors());
+                    self.faults = FaultTable::default();
+                    match session.start() {
+                        Ok(()) => self.session = Some(session),
+                        Err(e) => {
+                            error!("{}", e);
+                            self.status = finished_status(Some(Err(e)), false);
+                        }
+                    }
                 }
-            } else {
-                if ui
-                    .add(egui::Button::new("Stop").fill(Color32::DARK_RED))
-                    .clicked()
-                {
-                    self.stop_flag.store(true, Ordering::SeqCst);
-                    self.status = "Stopping...".to_string();
-                    // after stop, we expect run_tests to exit — the thread will drop guard & capture output
-                    self.test_handle.take().unwrap().join().unwrap();
-                    self.running = false; // allow start button again
-                    self.status = "Idle".to_owned();
+            } else if let Some(session) = &self.session {
+                let stopping = session.stopping();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!stopping, egui::Button::new(tr("Stop")).fill(Color32::DARK_RED))
+                        .clicked()
+                    {
+                        // the session ends after the running sweep, polled below
+                        session.stop();
+                        self.status = tr("Stopping...").to_string();
+                    }
+                    // the running sweep finishes first, the memory stays locked while paused
+                    let paused = session.paused();
+                    if ui.add_enabled(!stopping, egui::Button::new(tr(if paused { "Resume" } else { "Pause" }))).clicked() {
+                        if paused { session.resume() } else { session.pause() }
+                        self.status = tr(if paused { "Running..." } else { "Paused" }).to_string();
+                    }
+                });
+            }
+
+            if self.session.as_ref().is_some_and(TestSession::is_finished) {
+                // allows the start button again
+                let session = self.session.take().unwrap();
+                let stopped = session.stopping();
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| session.join()));
+                if let Ok(Err(e)) = &result {
+                    error!("{}", e);
                 }
+                self.status = finished_status(result.ok(), stopped);
             }
 
             ui.separator();
-            ui.label(format!("Status: {}", self.status));
+            ui.label(trf("Status: {}", &[&self.status]));
+
+            if let Some(progress) = self.session.as_ref().and_then(TestSession::progress).filter(|p| !p.tests.is_empty()) {
+                ui.separator();
+                show_progress(ui, &progress);
+            }
+
+            if let Some(metrics) = &self.metrics {
+                self.samples.extend(metrics.try_iter());
+            }
+            if !self.samples.is_empty() {
+                ui.separator();
+                show_graphs(ui, &self.samples);
+            }
+
+            if let Some(errors) = &self.errors {
+                errors.try_iter().for_each(|error| self.faults.add(error));
+            }
+            if !self.faults.rows.is_empty() {
+                ui.separator();
+                if let Some(status) = self.faults.show(ui) {
+                    self.status = status;
+                }
+            }
 
             ui.separator();
-            ui.label("Console output:");
-            ScrollArea::vertical()
+            ui.label(tr("Console output:"));
+            let row_height = ui.text_style_height(&TextStyle::Monospace);
+            let log = self.log_buffer.lock().unwrap();
+            ScrollArea::both()
                 .auto_shrink([false; 2])
                 .stick_to_bottom(true) // sticky-bottom behavior
-                .show(ui, |ui| {
-                    let log = self.log_buffer.lock().unwrap();
-                    let text = log.as_str();
-                    // Use a label to display the log
-                    ui.label(text);
+                .show_rows(ui, row_height, log.len(), |ui, rows| {
+                    // unwrapped, so that every line is one row of row_height
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                    for line in log.range(rows) {
+                        ui.monospace(line);
+                    }
                 });
+        });
+
+        // new log lines repaint right away, see GuiLogger; the rest is polled at a heartbeat
+        ctx.request_repaint_after(HEARTBEAT);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+    }
+}
+
+// Row of the test plan editor
+struct PlanRow {
+    name: &'static str,
+    // options as read from the file, loops always set
+    entry: TestConfigEntry,
+    enabled: bool,
+}
+
+// The tests to run and their loops, edited in place of manganese.conf and saved to it
+struct PlanEditor {
+    path: String,
+    rows: Vec<PlanRow>,
+    // the file's settings besides its tests and its tests this CPU can't run, kept when saving
+    config: CustomConfig,
+}
+
+impl PlanEditor {
+    // The plan of the config file found, or the default plan
+    fn new() -> Self {
+        let path = find_config();
+        let config = path.as_deref().and_then(|path| load_custom_config(path).ok()).unwrap_or_default();
+        let path = path.map_or("manganese.conf".to_owned(), |path| path.display().to_string());
+        PlanEditor::from_config(path, config)
+    }
 
-            // Reset running status if stop flag is cleared and thread finished
-            //if self.running && self.stop_flag.load(Ordering::SeqCst) == false {
-            // Optimistically check: if thread has finished, mark as stopped
-            // For better detection, you could join a handle (requires storing it)
-            // Here, we just allow restart if stop flag was cleared
-            //    self.running = false;
-            //    self.status = "Idle".to_owned();
-            //}
+    // Rows for the tests of `config` in its order, then the other tests, disabled unless it has
+    // no tests (the defaults)
+    fn from_config(path: String, mut config: CustomConfig) -> Self {
+        let catalog = test_catalog();
+        let defaults = config.entries.is_empty();
+        let mut rows: Vec<PlanRow> = Vec::new();
+        let (supported, unsupported) = config.entries.drain(..)
+            .partition::<Vec<_>, _>(|entry| catalog.iter().any(|test| test.name == entry.kind.name()));
+        config.entries = unsupported;
+        for mut entry in supported {
+            let test = catalog.iter().find(|test| test.name == entry.kind.name()).unwrap();
+            let loops = entry.loops.unwrap_or(test.loops);
+            entry.loops = Some(loops.max(1));
+            rows.push(PlanRow { name: test.name, entry, enabled: loops > 0 });
+        }
+        for test in &catalog {
+            if rows.iter().all(|row| row.name != test.name) {
+                let mut entry = TestConfigEntry::new(TestKind::parse(test.name).unwrap());
+                entry.loops = Some(test.loops.max(1));
+                rows.push(PlanRow { name: test.name, entry, enabled: defaults && test.loops > 0 });
+            }
+        }
+        PlanEditor { path, rows, config }
+    }
+
+    // The config to run and to save: the enabled tests
+    fn config(&self) -> CustomConfig {
+        let mut config = self.config.clone();
+        let enabled = self.rows.iter().filter(|row| row.enabled).map(|row| row.entry.clone());
+        config.entries = enabled.chain(config.entries).collect();
+        config
+    }
+
+    // The editor with buttons to load and save the file, Some(status) after one of them was clicked
+    fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut status = None;
+        let enabled = self.rows.iter().filter(|row| row.enabled).count();
+        CollapsingHeader::new(trf("Test plan ({} of {} tests)", &[&enabled, &self.rows.len()])).id_salt("plan").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.path).desired_width(200.0));
+                if ui.button(tr("Load")).clicked() {
+                    match load_custom_config(self.path.as_ref()) {
+                        Ok(config) => {
+                            *self = PlanEditor::from_config(self.path.clone(), config);
+                            status = Some(trf("Test plan loaded from {}", &[&self.path]));
+                        }
+                        Err(e) => status = Some(trf("Can't load {}: {}", &[&self.path, &e])),
+                    }
+                }
+                if ui.button(tr("Save")).clicked() {
+                    status = Some(match std::fs::write(&self.path, self.config().to_toml()) {
+                        Ok(()) => trf("Test plan saved to {}", &[&self.path]),
+                        Err(e) => trf("Can't write {}: {}", &[&self.path, &e]),
+                    });
+                }
+                if ui.button(tr("Defaults")).clicked() {
+                    let config = CustomConfig { entries: Vec::new(), ..self.config.clone() };
+                    *self = PlanEditor::from_config(self.path.clone(), config);
+                }
+            });
+            ScrollArea::vertical().id_salt("plan").max_height(250.0).show(ui, |ui| {
+                Grid::new("plan_table").striped(true).show(ui, |ui| {
+                    for row in &mut self.rows {
+                        ui.checkbox(&mut row.enabled, row.name);
+                        if let Some(loops) = &mut row.entry.loops {
+                            ui.add_enabled(row.enabled, DragValue::new(loops).range(1..=1000).suffix(tr(" loops")));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        status
+    }
+}
+
+// "3.5 GiB"
+fn gib(bytes: usize) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+// Status once the test thread returned with `result`, None if it panicked
+fn finished_status(result: Option<Result<RunReport, RunError>>, stopped: bool) -> String {
+    let failed = |report: &RunReport| report.errors > 0 || report.ecc_uncorrected > 0;
+    match result {
+        None => tr("Failed, see the console output").to_owned(),
+        Some(Ok(report)) if failed(&report) && stopped => tr("Stopped with errors").to_owned(),
+        Some(Ok(report)) if failed(&report) => tr("Finished with errors").to_owned(),
+        Some(Ok(_)) if stopped => tr("Stopped, no errors").to_owned(),
+        Some(Ok(_)) => tr("Finished, no errors").to_owned(),
+        Some(Err(RunError::Allocation(_))) => tr("Failed to allocate the memory").to_owned(),
+        Some(Err(RunError::SelfTest)) => tr("Failed, see the console output").to_owned(),
+        Some(Err(e)) => trf("Failed: {}", &[&e]),
+    }
+}
+
+// Overall bar of the pass, then one bar per test of it
+fn show_progress(ui: &mut egui::Ui, progress: &Progress) {
+    let eta = |eta: Option<Duration>| eta.map_or(String::new(), |eta| trf(", {} left", &[&format_duration(eta)]));
+    let percent = |fraction: f64| format!("{:.0}", fraction * 100.0);
+    ui.label(trf("Pass {} ({} complete)", &[&(progress.passes + 1), &progress.passes]) + &eta(progress.pass_eta));
+    ui.add(ProgressBar::new(progress.pass_fraction as f32).text(trf(
+        "test {}/{}, {}%", &[&(progress.test + 1), &progress.tests.len(), &percent(progress.pass_fraction)])));
+    CollapsingHeader::new(tr("Tests")).id_salt("tests").default_open(true).show(ui, |ui| {
+        ScrollArea::vertical().id_salt("tests").max_height(200.0).show(ui, |ui| {
+            for (i, name) in progress.tests.iter().enumerate() {
+                let (fraction, text) = match i.cmp(&progress.test) {
+                    std::cmp::Ordering::Less => (1.0, trf("{}: done", &[name])),
+                    std::cmp::Ordering::Equal => (progress.test_fraction, trf("{}: loop {}/{}, {}%",
+                        &[name, &progress.test_loop.max(1), &progress.loops, &percent(progress.test_fraction)]) + &eta(progress.test_eta)),
+                    std::cmp::Ordering::Greater => (0.0, name.to_string()),
+                };
+                ui.add(ProgressBar::new(fraction as f32).text(text));
+            }
+        });
+    });
+}
+
+// Bandwidth of every test loop and the errors so far over the minutes of the run
+fn show_graphs(ui: &mut egui::Ui, samples: &[Metric]) {
+    let minutes = |m: &Metric| m.elapsed / 60.0;
+    // the test of the loop nearest to the pointer
+    let test_at = |x: f64| samples.iter()
+        .min_by(|a, b| (minutes(a) - x).abs().total_cmp(&(minutes(b) - x).abs()))
+        .map_or("", |m| m.test);
+    CollapsingHeader::new(tr("Graphs")).id_salt("graphs").default_open(true).show(ui, |ui| {
+        Plot::new("bandwidth")
+            .height(120.0)
+            .link_axis("timeline", [true, false])
+            .include_y(0.0)
+            .y_axis_label("MB/s")
+            .legend(Legend::default())
+            .label_formatter(|_, point| format!("{}\n{:.1} min, {:.0} MB/s", test_at(point.x), point.x, point.y))
+            .show(ui, |plot| {
+                plot.line(Line::new(tr("Bandwidth"), samples.iter().map(|m| [minutes(m), m.bandwidth]).collect::<Vec<_>>()));
+            });
+        Plot::new("errors")
+            .height(90.0)
+            .link_axis("timeline", [true, false])
+            .include_y(0.0)
+            .x_axis_label(tr("minutes"))
+            .y_axis_label(tr("errors"))
+            .label_formatter(|_, point| trf("{} min, {} errors", &[&format!("{:.1}", point.x), &format!("{:.0}", point.y)]))
+            .show(ui, |plot| {
+                plot.line(Line::new(tr("Errors"), samples.iter().map(|m| [minutes(m), m.errors as f64]).collect::<Vec<_>>())
+                    .color(Color32::RED));
+            });
+    });
+}
+
+// distinct faults kept in the table, and the rows shown of them
+const FAULTS_MAX: usize = 65536;
+const ROWS_SHOWN: usize = 1000;
+
+// Miscompares of one word with the same flipped bits in one test
+#[derive(Serialize)]
+struct FaultRow {
+    test: &'static str,
+    offset: usize,
+    #[serde(rename = "physical_address", serialize_with = "hex_address")]
+    physical: Option<u64>,
+    #[serde(serialize_with = "hex")]
+    expected: u64,
+    #[serde(serialize_with = "hex")]
+    actual: u64,
+    count: usize,
+}
+
+// 64-bit words as hex strings, as in the run report
+fn hex<S: Serializer>(v: &u64, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(&format_args!("0x{:016x}", v))
+}
+
+fn hex_address<S: Serializer>(address: &Option<u64>, s: S) -> Result<S::Ok, S::Error> {
+    address.map(|address| format!("0x{:016x}", address)).serialize(s)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Offset,
+    Physical,
+    Test,
+    Count,
+}
+
+#[derive(Default)]
+struct FaultTable {
+    rows: Vec<FaultRow>,
+    // (test, offset, flipped bits) to the index of its row
+    index: HashMap<(&'static str, usize, u64), usize>,
+    untracked: usize,
+    // column sorted by and descending, the order seen without one
+    sort: Option<(Column, bool)>,
+}
+
+impl FaultTable {
+    fn add(&mut self, error: ErrorDetail) {
+        let record = error.record;
+        let key = (record.test, record.offset, record.bitmask);
+        if let Some(&i) = self.index.get(&key) {
+            self.rows[i].count += 1;
+        } else if self.rows.len() >= FAULTS_MAX {
+            self.untracked += 1;
+        } else {
+            self.index.insert(key, self.rows.len());
+            self.rows.push(FaultRow {
+                test: record.test,
+                offset: record.offset,
+                physical: error.physical,
+                expected: record.expected,
+                actual: record.actual,
+                count: 1,
+            });
+        }
+    }
+
+    // rows in the selected order
+    fn sorted(&self) -> Vec<&FaultRow> {
+        let mut rows: Vec<&FaultRow> = self.rows.iter().collect();
+        if let Some((column, descending)) = self.sort {
+            rows.sort_by(|a, b| {
+                let order = match column {
+                    Column::Offset => a.offset.cmp(&b.offset),
+                    Column::Physical => a.physical.cmp(&b.physical),
+                    Column::Test => a.test.cmp(b.test),
+                    Column::Count => a.count.cmp(&b.count),
+                };
+                if descending { order.reverse() } else { order }
+            });
+        }
+        rows
+    }
+
+    fn csv(&self) -> String {
+        let mut csv = "test,offset,physical_address,expected,actual,count\n".to_owned();
+        for row in self.sorted() {
+            csv.push_str(&format!("{},0x{:016x},{},0x{:016x},0x{:016x},{}\n", row.test, row.offset,
+                row.physical.map_or(String::new(), |address| format!("0x{:016x}", address)), row.expected, row.actual, row.count));
+        }
+        csv
+    }
+
+    fn json(&self) -> String {
+        serde_json::to_string(&self.sorted()).map(|json| json + "\n").unwrap_or_default()
+    }
+
+    // The table with its copy and export buttons, Some(status) after one of them was clicked
+    fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut status = None;
+        let title = if self.untracked > 0 {
+            trf("Errors ({} faults, {} more not tracked)", &[&self.rows.len(), &self.untracked])
+        } else {
+            trf("Errors ({} faults)", &[&self.rows.len()])
+        };
+        CollapsingHeader::new(title).id_salt("errors").default_open(true).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(tr("Copy")).clicked() {
+                    ui.ctx().copy_text(self.csv());
+                    status = Some(tr("Errors copied to the clipboard").to_owned());
+                }
+                if ui.button(tr("Export CSV")).clicked() {
+                    status = Some(export("manganese-errors.csv", &self.csv()));
+                }
+                if ui.button(tr("Export JSON")).clicked() {
+                    status = Some(export("manganese-errors.json", &self.json()));
+                }
+            });
+            ScrollArea::vertical().id_salt("errors").max_height(200.0).show(ui, |ui| {
+                Grid::new("error_table").striped(true).show(ui, |ui| {
+                    for (column, title) in [(Column::Offset, "Offset"), (Column::Physical, "Physical address"), (Column::Test, "Test")] {
+                        self.header(ui, column, title);
+                    }
+                    ui.label(tr("Expected"));
+                    ui.label(tr("Actual"));
+                    self.header(ui, Column::Count, "Count");
+                    ui.end_row();
+                    let rows = self.sorted();
+                    for row in rows.iter().take(ROWS_SHOWN) {
+                        ui.monospace(format!("0x{:016x}", row.offset));
+                        ui.monospace(row.physical.map_or("-".to_owned(), |address| format!("0x{:016x}", address)));
+                        ui.label(row.test);
+                        ui.monospace(format!("0x{:016x}", row.expected));
+                        ui.monospace(format!("0x{:016x}", row.actual));
+                        ui.label(row.count.to_string());
+                        ui.end_row();
+                    }
+                    if rows.len() > ROWS_SHOWN {
+                        ui.label(trf("... and {} more, export them all", &[&(rows.len() - ROWS_SHOWN)]));
+                        ui.end_row();
+                    }
+                });
+            });
         });
+        status
+    }
+
+    // Column title sorting by it when clicked, reversing the order on a second click
+    fn header(&mut self, ui: &mut egui::Ui, column: Column, title: &'static str) {
+        let arrow = match self.sort {
+            Some((sorted, descending)) if sorted == column => if descending { " ⏷" } else { " ⏶" },
+            _ => "",
+        };
+        if ui.add(Button::new(format!("{}{}", tr(title), arrow)).frame(false)).clicked() {
+            self.sort = match self.sort {
+                Some((sorted, descending)) if sorted == column => Some((column, !descending)),
+                _ => Some((column, false)),
+            };
+        }
+    }
+}
 
-        // keep repainting so we see log updates
-        ctx.request_repaint();
+// Write an export next to where the GUI was started
+fn export(path: &str, content: &str) -> String {
+    match std::fs::write(path, content) {
+        Ok(()) => trf("Errors exported to {}", &[&path]),
+        Err(e) => trf("Can't write {}: {}", &[&path, &e]),
     }
 }
@@ -0,0 +1,154 @@
+// Translations of the GUI for the customers repair shops hand it to: every string is looked up by
+// its English text, so anything without a translation stays English. Placeholders are `{}`, filled
+// in order by `trf()`. Of the log, the lines a customer watches (tests starting, the run ending)
+// are translated by their English beginning.
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// The language of the user's locale (LC_ALL, LC_MESSAGES, LANG), English if not bundled.
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        if locale.starts_with("de") { Language::German } else { Language::English }
+    }
+
+    /// The name of the language in itself.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+fn table() -> &'static [(&'static str, &'static str)] {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => GERMAN,
+        _ => &[],
+    }
+}
+
+/// `english` in the selected language.
+pub fn tr(english: &'static str) -> &'static str {
+    table().iter().find(|(key, _)| *key == english).map_or(english, |(_, text)| text)
+}
+
+/// `tr(english)` with its `{}` replaced by `args` in order.
+pub fn trf(english: &'static str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    for (i, part) in tr(english).split("{}").enumerate() {
+        if let Some(arg) = i.checked_sub(1).and_then(|i| args.get(i)) {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// A log line with its beginning translated, if it is one of the translated lines.
+pub fn tr_log(line: &str) -> String {
+    LOG_PREFIXES.iter()
+        .find_map(|prefix| line.strip_prefix(prefix).map(|rest| format!("{}{}", tr(prefix), rest)))
+        .unwrap_or_else(|| line.to_owned())
+}
+
+const LOG_PREFIXES: &[&str] = &[
+    "Running: ",
+    "Skipping: ",
+    "Tests completed in ",
+    "Test stopped after ",
+];
+
+const GERMAN: &[(&str, &str)] = &[
+    ("Settings", "Einstellungen"),
+    ("Language:", "Sprache:"),
+    ("Theme:", "Farbschema:"),
+    ("System", "System"),
+    ("Dark", "Dunkel"),
+    ("Light", "Hell"),
+    ("Scale", "Skalierung"),
+    ("Console font size", "Schriftgröße der Konsole"),
+    ("RAM to test:", "Zu testender RAM:"),
+    ("% of free", "% des freien"),
+    ("{} of {} free, {} total", "{} von {} frei, {} insgesamt"),
+    ("Hide serial numbers", "Seriennummern ausblenden"),
+    ("Only {} can be locked; run as root (or with CAP_IPC_LOCK), raise `ulimit -l` or grant SeLockMemoryPrivilege",
+        "Nur {} können gesperrt werden; als root (oder mit CAP_IPC_LOCK) starten, `ulimit -l` erhöhen oder SeLockMemoryPrivilege gewähren"),
+    ("No free RAM to test", "Kein freier RAM zum Testen"),
+    ("No tests selected", "Keine Tests ausgewählt"),
+    ("Start", "Start"),
+    ("Stop", "Stopp"),
+    ("Pause", "Pause"),
+    ("Resume", "Fortsetzen"),
+    ("Idle", "Bereit"),
+    ("Running...", "Test läuft..."),
+    ("Stopping...", "Wird gestoppt..."),
+    ("Paused", "Pausiert"),
+    ("Status: {}", "Status: {}"),
+    ("Console output:", "Konsolenausgabe:"),
+    ("Failed, see the console output", "Fehlgeschlagen, siehe Konsolenausgabe"),
+    ("Stopped, no errors", "Gestoppt, keine Fehler"),
+    ("Finished, no errors", "Abgeschlossen, keine Fehler"),
+    ("Stopped with errors", "Gestoppt, Fehler gefunden"),
+    ("Finished with errors", "Abgeschlossen, Fehler gefunden"),
+    ("Failed to allocate the memory", "Speicher konnte nicht reserviert werden"),
+    ("Failed: {}", "Fehlgeschlagen: {}"),
+    ("Test plan ({} of {} tests)", "Testplan ({} von {} Tests)"),
+    ("Load", "Laden"),
+    ("Save", "Speichern"),
+    ("Defaults", "Standard"),
+    (" loops", " Durchläufe"),
+    ("Test plan loaded from {}", "Testplan aus {} geladen"),
+    ("Test plan saved to {}", "Testplan in {} gespeichert"),
+    ("Can't load {}: {}", "{} kann nicht geladen werden: {}"),
+    ("Can't write {}: {}", "{} kann nicht geschrieben werden: {}"),
+    ("Pass {} ({} complete)", "Durchgang {} ({} abgeschlossen)"),
+    (", {} left", ", noch {}"),
+    ("test {}/{}, {}%", "Test {}/{}, {}%"),
+    ("Tests", "Tests"),
+    ("{}: done", "{}: fertig"),
+    ("{}: loop {}/{}, {}%", "{}: Durchlauf {}/{}, {}%"),
+    ("Graphs", "Diagramme"),
+    ("Bandwidth", "Bandbreite"),
+    ("Errors", "Fehler"),
+    ("minutes", "Minuten"),
+    ("errors", "Fehler"),
+    ("{} min, {} errors", "{} min, {} Fehler"),
+    ("Errors ({} faults)", "Fehler ({} Fehlerstellen)"),
+    ("Errors ({} faults, {} more not tracked)", "Fehler ({} Fehlerstellen, {} weitere nicht erfasst)"),
+    ("Copy", "Kopieren"),
+    ("Export CSV", "Als CSV exportieren"),
+    ("Export JSON", "Als JSON exportieren"),
+    ("Errors copied to the clipboard", "Fehler in die Zwischenablage kopiert"),
+    ("Errors exported to {}", "Fehler nach {} exportiert"),
+    ("Offset", "Offset"),
+    ("Physical address", "Physische Adresse"),
+    ("Test", "Test"),
+    ("Expected", "Erwartet"),
+    ("Actual", "Gelesen"),
+    ("Count", "Anzahl"),
+    ("... and {} more, export them all", "... und {} weitere, zum Anzeigen aller exportieren"),
+    ("Running: ", "Läuft: "),
+    ("Skipping: ", "Übersprungen: "),
+    ("Tests completed in ", "Tests abgeschlossen in "),
+    ("Test stopped after ", "Test beendet nach "),
+];
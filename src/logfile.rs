@@ -0,0 +1,57 @@
+// Log file for long burn-ins (`--log-file`): every message with date and time, appended to the
+// file and rotated by size (path.1 is the previous file, up to path.N), so a run of days keeps its
+// history after the terminal scrolled away without filling the disk.
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+
+// rotated files kept next to the current one
+const KEEP: usize = 5;
+
+pub struct RotatingFile {
+    path: String,
+    max_bytes: u64,
+    written: u64,
+    // closed while rotating, Windows can't rename open files
+    file: Option<File>,
+    // simplelog writes a line in pieces, only rotate between lines
+    line_start: bool,
+}
+
+impl RotatingFile {
+    /// Append to `path`, rotating once it would grow beyond `max_bytes`.
+    pub fn open(path: &str, max_bytes: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingFile { path: path.to_string(), max_bytes, written, file: Some(file), line_start: true })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        for i in (1..KEEP).rev() {
+            // missing older files are fine
+            let _ = fs::rename(format!("{}.{}", self.path, i), format!("{}.{}", self.path, i + 1));
+        }
+        let renamed = fs::rename(&self.path, format!("{}.1", self.path));
+        // keep appending if the file can't be moved, and retry after another max_bytes
+        self.file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        self.written = 0;
+        renamed
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.line_start && self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let file = self.file.as_mut().ok_or_else(|| io::Error::other("log file rotation failed"))?;
+        let n = file.write(buf)?;
+        self.written += n as u64;
+        self.line_start = buf[..n].ends_with(b"\n");
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().map_or(Ok(()), File::flush)
+    }
+}
@@ -2,40 +2,200 @@
 
 use clap::Parser;
 use std::io::{self, Write};
-use std::sync::atomic::{AtomicBool};
-#[cfg(not(feature = "gui"))]
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use std::io::IsTerminal;
-#[cfg(not(feature = "gui"))]
 use std::env;
 #[cfg(not(feature = "gui"))]
 use std::process::Command;
 
-use manganese_core::{parse_ram_spec, RamSpec, run_tests, sysinfo};
+use manganese_core::{default_config, hardware_instruction_set, load_config, parse_duration, parse_ram_spec, Affinity, Prefetch, Profile, RunLimits, sysinfo, test_catalog, TestSession, EXIT_CONFIG};
 
-use simplelog::{SimpleLogger, ConfigBuilder};
+use simplelog::{CombinedLogger, ConfigBuilder, SharedLogger, SimpleLogger, WriteLogger};
 use log::{error, info, warn, LevelFilter as LogLevelFilter};
 
-fn init_cli_logger() {
+use crate::logfile::RotatingFile;
+
+// Console at Info, plus everything down to Debug with date and time in the log file if one is given
+fn init_cli_logger(log_file: Option<&str>, log_file_size: u64) {
     let config = ConfigBuilder::new()
         .build();
-    SimpleLogger::init(LogLevelFilter::Info, config).unwrap();
+    let mut loggers: Vec<Box<dyn SharedLogger>> = vec![SimpleLogger::new(LogLevelFilter::Info, config)];
+    let mut open_error = None;
+    if let Some(path) = log_file {
+        match RotatingFile::open(path, log_file_size * 1024 * 1024) {
+            Ok(file) => {
+                let mut config = ConfigBuilder::new();
+                // UTC if the local offset can't be determined safely
+                let _ = config.set_time_offset_to_local();
+                loggers.push(WriteLogger::new(LogLevelFilter::Debug, config.set_time_format_rfc3339().build(), file));
+            }
+            Err(e) => open_error = Some((path, e)),
+        }
+    }
+    CombinedLogger::init(loggers).unwrap();
+    if let Some((path, e)) = open_error {
+        error!("can't open the log file {}: {}", path, e);
+        std::process::exit(EXIT_CONFIG);
+    }
+}
+
+fn duration_arg(s: &str) -> Result<Duration, String> {
+    parse_duration(s).ok_or_else(|| "expected a duration such as 90s, 30m, 2h or 1d".to_string())
+}
+
+// 0x-prefixed hexadecimal as logged, or decimal
+fn seed_arg(s: &str) -> Result<u64, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }.map_err(|_| "expected a 64-bit seed such as 0x05703d1c342535a2".to_string())
 }
 
 #[cfg(feature = "gui")]
 mod gui;
+#[cfg(feature = "gui")]
+mod i18n;
+mod logfile;
 
 #[derive(Parser)]
 struct Args {
+    /// Size to test: a % of free RAM, %t of total RAM, an SI size or free-SIZE to leave SIZE free (default: $MANGANESE_RAM, then ram= of the config file, else asks)
+    #[arg(allow_hyphen_values = true)]
     ram: Option<String>,
+    /// Never ask for the size, test 50% of free RAM if none is given
+    #[arg(long, short = 'y', alias = "default-ram")]
+    yes: bool,
     #[arg(long)]
     hide_serials: bool,
     #[arg(long)]
     headless: bool,
+    /// Register the tested memory as CUDA pinned host memory (requires the pinned-gpu feature)
+    #[arg(long)]
+    pinned_gpu: bool,
+    /// Randomize the test order every loop and the thread-to-chunk assignment every pass
+    #[arg(long)]
+    shuffle: bool,
+    /// Overwrite the whole locked region with zeros when the run is stopped (Ctrl-C/SIGTERM)
+    #[arg(long)]
+    scrub_on_exit: bool,
+    /// Rerun the streaming tests on an extra write-combining (USWC) mapping after every loop (Windows)
+    #[arg(long)]
+    write_combining: bool,
+    /// Inject faults at known offsets before testing and abort unless exactly those are reported
+    #[arg(long)]
+    self_test: bool,
+    /// Back the tested memory with 1GiB or 2MiB huge pages when available
+    #[arg(long)]
+    hugepages: bool,
+    /// Test every NUMA node's memory from its own CPUs and report errors per node
+    #[arg(long)]
+    numa: bool,
+    /// Test every L3 cache domain's (CCD's) share of the memory from its own CPUs and report the bandwidth of each
+    #[arg(long, conflicts_with = "numa")]
+    l3_domains: bool,
+    /// With --l3-domains, test every domain's share from the CPUs of the next domain instead
+    #[arg(long, requires = "l3_domains")]
+    cross_domains: bool,
+    /// Keep testing memory that can't be locked (it may be swapped out) instead of backing off
+    #[arg(long)]
+    allow_unlocked: bool,
+    /// Measure copy/scale/add/triad bandwidth from one thread up to all of them and the random access latency of the locked memory instead of testing it
+    #[arg(long)]
+    benchmark: bool,
+    /// Repeatedly borrow most of the free memory, test it briefly and give it back, so the frames the OS uses get tested over time
+    #[arg(long)]
+    cycle: bool,
+    /// Pin one worker thread to each CPU: all, no-smt (one per physical core), p-cores or e-cores (hybrid CPUs)
+    #[arg(long, value_name = "CPUS")]
+    pin: Option<String>,
+    /// Test with N threads instead of one per logical CPU (overrides threads= in manganese.conf)
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    threads: Option<usize>,
+    /// Bytes the verify loops prefetch ahead: auto (calibrated when the run starts), off or a multiple of 64 such as 1024 (overrides prefetch= in manganese.conf)
+    #[arg(long, value_name = "BYTES")]
+    prefetch: Option<String>,
+    /// Write a JSON report of the run (hardware, test plan, results, error records) to this file when it ends
+    #[arg(long, value_name = "PATH")]
+    output_json: Option<String>,
+    /// Write a JUnit XML report of the run (one test case per test and pass) to this file when it ends
+    #[arg(long, value_name = "PATH")]
+    output_junit: Option<String>,
+    /// Also write the log, with date and time, to this file (rotated by size, keeping PATH.1 to PATH.5)
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<String>,
+    /// Size in MiB at which the log file is rotated
+    #[arg(long, value_name = "MIB", default_value_t = 64, value_parser = clap::value_parser!(u64).range(1..))]
+    log_file_size: u64,
+    /// Write a hex dump of the memory around the first miscompare to this file
+    #[arg(long, value_name = "PATH")]
+    dump_on_error: Option<String>,
+    /// Bytes before and after the first miscompare included in the dump
+    #[arg(long, value_name = "BYTES", default_value_t = 4096)]
+    dump_window: usize,
+    /// Checkpoint the run to this file after every test, removed when the run ends on its own (manganese.state with --resume)
+    #[arg(long, value_name = "PATH")]
+    state_file: Option<String>,
+    /// Seed the random patterns with this instead of a random seed, to replay a run with the same patterns (its seed is logged as Random Seed)
+    #[arg(long, value_name = "SEED", value_parser = seed_arg)]
+    seed: Option<u64>,
+    /// Go on with the run checkpointed to the state file: its passes, errors and random seed, skipping the tests its last pass did
+    #[arg(long)]
+    resume: bool,
+    /// Config file to use instead of the first manganese.conf found (working directory, user and system config directories)
+    #[arg(long, value_name = "PATH")]
+    config: Option<String>,
+    /// Run the [plan.NAME] tests and settings of the config file instead of its top-level ones
+    #[arg(long, value_name = "NAME", conflicts_with = "profile")]
+    plan: Option<String>,
+    /// Built-in test plan instead of the tests in manganese.conf: quick (minutes), standard or extended (hours)
+    #[arg(long, value_name = "PROFILE")]
+    profile: Option<String>,
+    /// Run only these tests, in this order (comma-separated names, see --list-tests)
+    #[arg(long, value_name = "TESTS", value_delimiter = ',')]
+    tests: Vec<String>,
+    /// Leave out these tests (comma-separated names)
+    #[arg(long, value_name = "TESTS", value_delimiter = ',')]
+    skip_tests: Vec<String>,
+    /// List the available tests with their default passes and loops, then exit
+    #[arg(long)]
+    list_tests: bool,
+    /// Print a commented manganese.conf with every test and its default options, and exit
+    #[arg(long)]
+    dump_default_config: bool,
+    /// Stop after this many complete passes (overrides passes= in manganese.conf)
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    passes: Option<usize>,
+    /// Stop testing after this long, e.g. 90m, 2h or 1d (overrides duration= in manganese.conf)
+    #[arg(long, value_name = "TIME", value_parser = duration_arg)]
+    duration: Option<Duration>,
+    /// Stop the run once this many errors were detected (overrides max_errors= in manganese.conf)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_errors: Option<u64>,
+    /// Run every test for about this long, e.g. 3m, in as many whole loops as fit after timing the first (overrides per_test_budget= in manganese.conf)
+    #[arg(long, value_name = "TIME", value_parser = duration_arg)]
+    per_test_budget: Option<Duration>,
 }
 
 fn main() {
     let args = Args::parse();
 
+    if args.list_tests {
+        // the list is often piped into grep or head, a closed pipe is no error
+        let mut out = io::stdout().lock();
+        let _ = writeln!(out, "{:<28} {:>6} {:>6} {:>6}", "TEST", "PASSES", "ITERS", "LOOPS");
+        for test in test_catalog() {
+            let loops = if test.loops == 0 { "off".to_string() } else { test.loops.to_string() };
+            let _ = writeln!(out, "{:<28} {:>6} {:>6} {:>6}", test.name, test.passes, test.iters, loops);
+        }
+        return;
+    }
+
+    if args.dump_default_config {
+        let _ = io::stdout().lock().write_all(default_config(hardware_instruction_set()).as_bytes());
+        return;
+    }
+
     // GUI fallback if enabled and no terminal is present
     #[cfg(feature = "gui")]
     if !args.headless {
@@ -43,28 +203,56 @@ fn main() {
         return;
     }
 
-    // CLI-only fallback
+    // CLI-only fallback: started without a terminal (double-click) and nothing to run with but
+    // the prompt, so rerun in a terminal window; services and scripts never get here
     #[cfg(not(feature = "gui"))]
-    if ! io::stdout().is_terminal() {
-        spawn_terminal();
+    if !io::stdout().is_terminal() && ram_argument(&args).is_none() && !args.yes && spawn_terminal() {
+        return;
     }
 
     run_cli(args);
 }
 
+// RAM size used with --yes or without a terminal to ask on
+const DEFAULT_RAM: &str = "50%";
+// environment fallback for the RAM size argument
+const RAM_VARIABLE: &str = "MANGANESE_RAM";
+
+// The RAM size from the command line, or else the environment
+fn ram_argument(args: &Args) -> Option<String> {
+    args.ram.clone().or_else(|| env::var(RAM_VARIABLE).ok().filter(|ram| !ram.trim().is_empty()))
+}
+
 fn run_cli(args: Args) {
     // Refresh memory using sysinfo 0.37 API
     let sysinfo = sysinfo();
     let total = sysinfo.totalram;
     let avail = sysinfo.freeram;
 
-    init_cli_logger();
+    init_cli_logger(args.log_file.as_deref(), args.log_file_size);
 
     info!("manganese v{} 🎉", env!("CARGO_PKG_VERSION"));
 
-    let ram_input = args.ram.unwrap_or_else(|| {
-        warn!("usage: manganese [0%-99%|4GiB|8%t|300MiB]");
-        warn!("where the input is an SI size, % of free RAM, or %t of total RAM.");
+    let mut config = load_config(args.config.as_deref()).unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(e.exit_status());
+    });
+    if let Some(name) = &args.plan {
+        config = config.use_plan(name).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(EXIT_CONFIG);
+        });
+        info!("Named Plan        : {}", name);
+    }
+
+    let ram_input = ram_argument(&args).or_else(|| config.ram.clone()).unwrap_or_else(|| {
+        // no one to answer the prompt
+        if args.yes || !io::stdin().is_terminal() {
+            warn!("no RAM size given (argument, {} or ram= in the config file), testing the default {}", RAM_VARIABLE, DEFAULT_RAM);
+            return DEFAULT_RAM.to_string();
+        }
+        warn!("usage: manganese [0%-99%|4GiB|8%t|300MiB|free-2GiB]");
+        warn!("where the input is an SI size, % of free RAM, %t of total RAM, or all free RAM but a reserve.");
 
         info!(
             "Total RAM: {}MiB, available: {}MiB ({:.2}%)",
@@ -82,23 +270,104 @@ fn run_cli(args: Args) {
     });
 
     // Parse RAM specification
-    let ram_bytes = match parse_ram_spec(&ram_input) {
-        Some(RamSpec::Bytes(b)) => b,
-        Some(RamSpec::Percent(frac, true)) => (total as f64 * frac) as usize,
-        Some(RamSpec::Percent(frac, false)) => (avail as f64 * frac) as usize,
-        None => {
-            error!("Invalid RAM specification: \"{}\"", ram_input);
-            std::process::exit(1);
-        }
+    let Some(ram_bytes) = parse_ram_spec(&ram_input, total, avail) else {
+        error!("Invalid RAM specification: \"{}\"", ram_input);
+        std::process::exit(EXIT_CONFIG);
     };
 
-    let stop_signal = AtomicBool::new(false);
+    let pin = args.pin.as_deref().map(|cpus| Affinity::parse(cpus).unwrap_or_else(|| {
+        error!("Invalid --pin value: \"{}\" (expected all, no-smt, p-cores or e-cores)", cpus);
+        std::process::exit(EXIT_CONFIG);
+    }));
+
+    let prefetch = args.prefetch.as_deref().map(|distance| Prefetch::parse(distance).unwrap_or_else(|| {
+        error!("Invalid --prefetch value: \"{}\" (expected auto, off or a multiple of 64 up to 65536)", distance);
+        std::process::exit(EXIT_CONFIG);
+    }));
+
+    let profile = args.profile.as_deref().map(|name| Profile::parse(name).unwrap_or_else(|| {
+        error!("Invalid --profile value: \"{}\" (expected quick, standard or extended)", name);
+        std::process::exit(EXIT_CONFIG);
+    }));
 
-    run_tests(ram_bytes, args.hide_serials, &stop_signal);
+    let limits = RunLimits { passes: args.passes, duration: args.duration, max_errors: args.max_errors, test_budget: args.per_test_budget };
+    let mut builder = TestSession::builder()
+        .ram(ram_bytes)
+        .tests(config)
+        .only_tests(&args.tests)
+        .skip_tests(&args.skip_tests)
+        .limits(limits)
+        .hide_serials(args.hide_serials)
+        .pinned_gpu(args.pinned_gpu)
+        .shuffle(args.shuffle)
+        .scrub_on_exit(args.scrub_on_exit)
+        .write_combining(args.write_combining)
+        .self_test(args.self_test)
+        .hugepages(args.hugepages)
+        .numa(args.numa)
+        .l3_domains(args.l3_domains)
+        .cross_domains(args.cross_domains)
+        .allow_unlocked(args.allow_unlocked)
+        .cycle(args.cycle)
+        .benchmark(args.benchmark)
+        .resume(args.resume);
+    // only checkpointed when asked for, a run shouldn't leave files in the working directory
+    if args.state_file.is_some() || args.resume {
+        builder = builder.checkpoint(args.state_file.as_deref().unwrap_or("manganese.state"));
+    }
+    if let Some(seed) = args.seed {
+        builder = builder.seed(seed);
+    }
+    if let Some(threads) = args.threads {
+        builder = builder.threads(threads);
+    }
+    if let Some(pin) = pin {
+        builder = builder.pin(pin);
+    }
+    if let Some(prefetch) = prefetch {
+        builder = builder.prefetch(prefetch);
+    }
+    if let Some(profile) = profile {
+        builder = builder.profile(profile);
+    }
+    if let Some(path) = &args.output_json {
+        builder = builder.output_json(path);
+    }
+    if let Some(path) = &args.output_junit {
+        builder = builder.output_junit(path);
+    }
+    if let Some(path) = &args.dump_on_error {
+        builder = builder.dump_on_error(path, args.dump_window);
+    }
+    let session = builder.build();
+
+    // Ctrl-C / SIGTERM (console close on Windows) end the run after the current test, so the
+    // summary is printed and the memory unlocked; a second one aborts immediately
+    let stop = session.stop_signal();
+    let scrub_on_exit = args.scrub_on_exit;
+    ctrlc::set_handler(move || {
+        if stop.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        if scrub_on_exit {
+            warn!("Stopping the current test, then scrubbing memory (Ctrl-C again aborts without scrubbing)");
+        } else {
+            warn!("Stopping the current test (Ctrl-C again aborts immediately)");
+        }
+    }).expect("ERROR: can't install the Ctrl-C handler");
+
+    // the run only ends when stopped, so that's a success unless errors were found
+    match session.join() {
+        Ok(report) => std::process::exit(report.exit_status()),
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(e.exit_status());
+        }
+    }
 }
 
 #[cfg(not(feature = "gui"))]
-fn spawn_terminal() {
+fn spawn_terminal() -> bool {
     let exe_path = env::current_exe().unwrap();
     let exe_str = exe_path.to_str().unwrap();
 
@@ -108,7 +377,7 @@ fn spawn_terminal() {
         Command::new("powershell")
             .args(&["-NoExit", "-Command", &format!("& '{}'", exe_str)])
             .spawn()
-            .expect("Failed to spawn terminal");
+            .is_ok()
     }
 
     #[cfg(target_os = "macos")]
@@ -120,24 +389,21 @@ fn spawn_terminal() {
                 &format!("tell application \"Terminal\" to do script \"{}\"", exe_str),
             ])
             .spawn()
-            .expect("Failed to spawn terminal");
+            .is_ok()
     }
 
     #[cfg(target_os = "linux")]
     {
         // Linux: try common terminals (gnome-terminal, konsole, xterm)
         let terminals = ["gnome-terminal", "konsole", "xterm"];
-        let mut spawned = false;
-
-        for term in &terminals {
-            if Command::new(term).args(&["-e", exe_str]).spawn().is_ok() {
-                spawned = true;
-                break;
-            }
-        }
+        let spawned = terminals.iter().any(|term| Command::new(term).args(["-e", exe_str]).spawn().is_ok());
 
         if !spawned {
             error!("Could not spawn a terminal. Please run this CLI from a terminal manually.");
         }
+        spawned
     }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    false
 }